@@ -0,0 +1,810 @@
+//! 静态类型检查
+//!
+//! 使用--check时不会运行代码, 只根据参数, 属性, 变量和返回值的类型声明遍历语法树,
+//! 找出类型不符, 参数数量错误, 未知类型和访问类中不存在的属性等问题
+//!
+//! 无法确定类型的值都视为Any, 不会被报告
+
+use crate::intern::{intern, Interned};
+use crate::native::NativeClassDef;
+use crate::primitive::litr::{ArgDecl, KsType, Litr, LocalFuncRaw, LocalFuncRawArg};
+use crate::runtime::Class;
+use crate::scan::{expr::{Expr, TmplPart}, stmt::*};
+
+/// 检查时能找到的类型声明
+#[derive(Clone, Copy)]
+enum ClsInfo {
+  Local(*const ClassDefRaw),
+  Enum(*const EnumDefRaw),
+  Native(*const NativeClassDef),
+  /// class A = B的别名, 用到时再找B
+  Alias(Interned),
+  /// 从模块取得的类型, 无法检查
+  Unknown
+}
+impl ClsInfo {
+  /// 两个类型声明是否相同, 无法确定时视为相同
+  fn same(&self, other:&ClsInfo)-> bool {
+    match (self, other) {
+      (ClsInfo::Local(a), ClsInfo::Local(b))=> a == b,
+      (ClsInfo::Enum(a), ClsInfo::Enum(b))=> a == b,
+      (ClsInfo::Native(a), ClsInfo::Native(b))=> a == b,
+      (ClsInfo::Unknown, _)|(_, ClsInfo::Unknown)=> true,
+      _=> false
+    }
+  }
+}
+
+/// 推断出的值的类型
+#[derive(Clone)]
+struct Ty {
+  t: KsType,
+  /// 值是已知的本地函数时, 可以检查对它的调用
+  f: Option<*const LocalFuncRaw>
+}
+impl Ty {
+  fn of(t:KsType)-> Self {
+    Ty {t, f:None}
+  }
+  fn any()-> Self {
+    Ty::of(KsType::Any)
+  }
+}
+
+struct Var {
+  name: Interned,
+  ty: Ty,
+  /// 带有类型声明的变量, 之后的赋值也要符合该类型
+  declared: bool
+}
+
+/// 检查时的作用域
+#[derive(Default)]
+struct Block {
+  vars: Vec<Var>,
+  classes: Vec<(Interned, ClsInfo)>
+}
+
+/// 正在检查的函数
+struct FuncCx {
+  ret: KsType,
+  generator: bool,
+  kself: KsType
+}
+
+struct Checker {
+  blocks: Vec<Block>,
+  funcs: Vec<FuncCx>,
+  line: usize,
+  errors: Vec<(usize, String)>
+}
+
+/// 检查一段程序, 返回(行号, 问题)的列表
+pub fn check(s:&Statements)-> Vec<(usize, String)> {
+  let top = Block {
+    vars: Vec::new(),
+    classes: crate::primitive::classes().into_iter().filter_map(|(name, cls)|match cls {
+      Class::Native(cls)=> Some((name, ClsInfo::Native(cls))),
+      _=> None
+    }).collect()
+  };
+  let mut checker = Checker { blocks: vec![top], funcs: Vec::new(), line: 0, errors: Vec::new() };
+  checker.block(s);
+  checker.errors
+}
+
+/// 字面量的类型
+fn litr_type(v:&Litr)-> KsType {
+  match v {
+    Litr::Int(_)=> KsType::Int,
+    Litr::Uint(_)=> KsType::Uint,
+    Litr::Float(_)=> KsType::Float,
+    Litr::BigInt(_)=> KsType::BigInt,
+    Litr::Bool(_)=> KsType::Bool,
+    Litr::Str(_)=> KsType::Str,
+    Litr::Buf(_)=> KsType::Buf,
+    Litr::List(_)=> KsType::List,
+    Litr::Obj(_)=> KsType::Obj,
+    Litr::Func(_)=> KsType::Func,
+    _=> KsType::Any
+  }
+}
+
+/// 二元运算的结果类型
+fn arith(op:&[u8], l:&KsType, r:&KsType)-> KsType {
+  use KsType::*;
+  match (l, r) {
+    (Str, _) if op == b"+"=> Str,
+    (Int, Int)=> Int,
+    (Uint, Uint)=> Uint,
+    (Float, Float)=> Float,
+    (BigInt, Float)|(Float, BigInt)=> Float,
+    (BigInt, BigInt|Int|Uint)|(Int|Uint, BigInt)=> BigInt,
+    _=> Any
+  }
+}
+
+/// 该类型是否接受uninit
+fn accepts_uninit(t:&KsType)-> bool {
+  match t {
+    KsType::Any|KsType::Optional(_)=> true,
+    KsType::Union(ts)=> ts.iter().any(accepts_uninit),
+    _=> false
+  }
+}
+
+fn is_uninit(e:&Expr)-> bool {
+  matches!(e, Expr::Literal(Litr::Uninit)|Expr::Empty)
+}
+
+impl Checker {
+  fn err(&mut self, mes:String) {
+    self.errors.push((self.line, mes));
+  }
+
+  /// 检查一个块, 块中的类和枚举声明会提前登记
+  fn block(&mut self, s:&Statements) {
+    let mut b = Block::default();
+    for (_, sm) in &s.v {
+      match sm {
+        Stmt::Class(cls)|Stmt::ExportCls(cls)=>
+          b.classes.push((unsafe{(**cls).name}, ClsInfo::Local(*cls))),
+        Stmt::Enum(e)|Stmt::ExportEnum(e)=>
+          b.classes.push((unsafe{(**e).name}, ClsInfo::Enum(*e))),
+        Stmt::Using(alia, e)=> b.classes.push((*alia, match e {
+          Expr::Variant(id)=> ClsInfo::Alias(*id),
+          _=> ClsInfo::Unknown
+        })),
+        _=> ()
+      }
+    }
+    self.blocks.push(b);
+    for (l, sm) in &s.v {
+      self.line = l.end;
+      self.stmt(sm);
+    }
+    self.blocks.pop();
+  }
+
+  fn find_class(&self, s:Interned)-> Option<ClsInfo> {
+    let cls = self.blocks.iter().rev()
+      .find_map(|b|b.classes.iter().rev().find(|(name, _)|*name == s))?.1;
+    match cls {
+      ClsInfo::Alias(id) if id == s=> Some(ClsInfo::Unknown),
+      ClsInfo::Alias(id)=> self.find_class(id),
+      _=> Some(cls)
+    }
+  }
+
+  fn var(&mut self, s:Interned)-> Option<&mut Var> {
+    self.blocks.iter_mut().rev()
+      .find_map(|b|b.vars.iter_mut().rev().find(|v|v.name == s))
+  }
+
+  fn declare(&mut self, name:Interned, ty:Ty, declared:bool) {
+    self.blocks.last_mut().unwrap().vars.push(Var {name, ty, declared});
+  }
+
+  /// 类型声明中的类必须存在
+  fn typ_exists(&mut self, t:&KsType) {
+    match t {
+      KsType::Class(name)=> if self.find_class(*name).is_none() {
+        self.err(format!("未知的类型'{}'", name));
+      }
+      KsType::ListOf(t)|KsType::ObjOf(t)|KsType::Optional(t)=> self.typ_exists(t),
+      KsType::Union(ts)=> for t in ts {
+        self.typ_exists(t);
+      }
+      KsType::Sig(args, ret)=> {
+        for t in args {
+          self.typ_exists(t);
+        }
+        self.typ_exists(ret);
+      }
+      _=> ()
+    }
+  }
+
+  /// got类型的值能否放进声明为want的位置, 无法确定时视为可以
+  fn fits(&self, want:&KsType, got:&KsType)-> bool {
+    use KsType::*;
+    want.accepts(got, &|want, got|match (want, got) {
+      (Class(a), Class(b))=> a == b || match (self.find_class(*a), self.find_class(*b)) {
+        (Some(a), Some(b))=> a.same(&b),
+        _=> true
+      },
+      // 找不到的类已经报告过了
+      (Class(a), _)=> self.find_class(*a).is_none(),
+      (_, Class(b))=> self.find_class(*b).is_none(),
+      _=> true
+    })
+  }
+
+  /// 检查函数声明和函数体, 返回该函数的类型
+  fn func(&mut self, f:*const LocalFuncRaw, kself:KsType)-> Ty {
+    let raw = unsafe {&*f};
+    if let LocalFuncRawArg::Normal(decl) = &raw.argdecl {
+      for a in decl {
+        self.typ_exists(&a.t);
+        if !is_uninit(&a.default) {
+          let t = self.expr(&a.default).t;
+          if !self.fits(&a.t, &t) {
+            self.err(format!("参数'{}'要求{:?}类型, 但默认值为{:?}", a.name, a.t, t));
+          }
+        }
+      }
+    }
+    self.typ_exists(&raw.ret);
+
+    self.blocks.push(Block::default());
+    match &raw.argdecl {
+      LocalFuncRawArg::Normal(decl)=> for a in decl {
+        match &a.pat {
+          Some(pat)=> self.bind(pat),
+          None=> {
+            let declared = !matches!(a.t, KsType::Any);
+            self.declare(a.name, Ty::of(a.t.clone()), declared);
+          }
+        }
+      }
+      LocalFuncRawArg::Custom(name)=> self.declare(*name, Ty::of(KsType::List), false)
+    }
+    self.funcs.push(FuncCx {ret: raw.ret.clone(), generator: raw.generator, kself});
+    self.block(&raw.stmts);
+    self.funcs.pop();
+    self.blocks.pop();
+    Ty {t:KsType::Func, f:Some(f)}
+  }
+
+  /// 声明解构得到的变量, 解构出的值类型都无法确定
+  fn bind(&mut self, to:&AssignTo) {
+    let destr = match to {
+      AssignTo::One(id)=> return self.declare(*id, Ty::any(), false),
+      AssignTo::Destr(d)=> &**d
+    };
+    let (items, rest):(Vec<&DestrItem>, _) = match destr {
+      Destr::List { items, rest }=> (items.iter().collect(), rest),
+      Destr::Obj { items, rest }=> (items.iter().map(|(_, item)|item).collect(), rest)
+    };
+    for item in items {
+      if !is_uninit(&item.default) {
+        self.expr(&item.default);
+      }
+      self.bind(&item.to);
+    }
+    if let Some(rest) = rest {
+      self.declare(*rest, Ty::any(), false);
+    }
+  }
+
+  fn class(&mut self, cls:*const ClassDefRaw) {
+    let cls = unsafe {&*cls};
+    for prop in &cls.props {
+      self.typ_exists(&prop.typ);
+    }
+    for f in &cls.methods {
+      self.func(&f.f, KsType::Class(cls.name));
+    }
+    for f in &cls.statics {
+      self.func(&f.f, KsType::Any);
+    }
+  }
+
+  fn enum_def(&mut self, e:*const EnumDefRaw) {
+    let e = unsafe {&*e};
+    for v in &e.variants {
+      for field in &v.fields {
+        self.typ_exists(&field.t);
+        if !is_uninit(&field.default) {
+          let t = self.expr(&field.default).t;
+          if !self.fits(&field.t, &t) {
+            self.err(format!("枚举成员'{}'的'{}'要求{:?}类型, 但默认值为{:?}", v.name, field.name, field.t, t));
+          }
+        }
+      }
+    }
+  }
+
+  fn stmt(&mut self, s:&Stmt) {
+    match s {
+      Stmt::Let(asn)|Stmt::Const(asn)=> self.assign(asn),
+      Stmt::Class(cls)|Stmt::ExportCls(cls)=> self.class(*cls),
+      Stmt::Enum(e)|Stmt::ExportEnum(e)=> self.enum_def(*e),
+      Stmt::ExportFn(id, f)=> {
+        self.declare(*id, Ty {t:KsType::Func, f:Some(*f)}, false);
+        self.func(*f, KsType::Any);
+      }
+
+      Stmt::Block(s)=> self.block(s),
+      Stmt::If { condition, exec, els }=> {
+        self.expr(condition);
+        self.stmt(exec);
+        if let Some(els) = els {
+          self.stmt(els);
+        }
+      }
+      Stmt::ForLoop { exec, .. }=> self.stmt(exec),
+      Stmt::ForWhile { condition, exec, .. }=> {
+        self.expr(condition);
+        self.stmt(exec);
+      }
+      Stmt::ForIter { iterator, id, exec, .. }=> {
+        let t = self.expr(iterator).t;
+        self.blocks.push(Block::default());
+        match (id, t) {
+          (Some(AssignTo::One(id)), KsType::ListOf(t))=> self.declare(*id, Ty::of(*t), false),
+          (Some(id), _)=> self.bind(id),
+          _=> ()
+        }
+        self.stmt(exec);
+        self.blocks.pop();
+      }
+      Stmt::Match { to, arms, def }=> {
+        self.expr(to);
+        for (conds, stmts) in arms {
+          self.blocks.push(Block::default());
+          for (cond, _) in conds {
+            match cond {
+              // Enum::Variant(a, b)会绑定成员携带的值
+              Expr::Call { args, targ } if self.find_enum(targ).is_some()=> {
+                self.expr(targ);
+                for arg in args {
+                  match arg {
+                    Expr::Variant(id)=> self.declare(*id, Ty::any(), false),
+                    _=> {self.expr(arg);}
+                  }
+                }
+              }
+              _=> {self.expr(cond);}
+            }
+          }
+          self.block(stmts);
+          self.blocks.pop();
+        }
+        if let Some(def) = def {
+          self.block(def);
+        }
+      }
+      Stmt::Try { stmt, catc }=> {
+        self.stmt(stmt);
+        if let Some((id, catc)) = catc {
+          self.blocks.push(Block::default());
+          self.declare(*id, Ty::of(KsType::Str), false);
+          self.block(catc);
+          self.blocks.pop();
+        }
+      }
+
+      Stmt::Return(e)=> {
+        let t = self.expr(e).t;
+        if let Some(f) = self.funcs.last() {
+          if f.generator {
+            return;
+          }
+          let want = f.ret.clone();
+          if is_uninit(e) && !accepts_uninit(&want) {
+            self.err(format!("函数要求返回{:?}类型, 但没有返回值", want));
+          }else if !self.fits(&want, &t) {
+            self.err(format!("函数要求返回{:?}类型, 但返回了{:?}", want, t));
+          }
+        }
+      }
+      Stmt::Break(_, e)|Stmt::Yield(e)|Stmt::Throw(e)|Stmt::Expression(e)=> {self.expr(e);}
+      Stmt::Lock(_)|Stmt::Using(..)|Stmt::Mod(..)|Stmt::NativeMod(..)|Stmt::Continue(_)|Stmt::Empty=> ()
+    }
+  }
+
+  /// let和const
+  fn assign(&mut self, asn:&AssignDef) {
+    self.typ_exists(&asn.typ);
+    // 先声明函数名, 函数体内才能检查递归调用
+    if let (AssignTo::One(id), Expr::LocalDecl(f)) = (&asn.id, &asn.val) {
+      self.declare(*id, Ty {t:KsType::Func, f:Some(*f)}, false);
+      self.func(*f, KsType::Any);
+      return;
+    }
+
+    let ty = self.expr(&asn.val);
+    let declared = !matches!(asn.typ, KsType::Any);
+    if declared && !is_uninit(&asn.val) && !self.fits(&asn.typ, &ty.t) {
+      self.err(format!("变量要求{:?}类型, 但赋值了{:?}", asn.typ, ty.t));
+    }
+    match &asn.id {
+      AssignTo::One(id)=> {
+        let ty = if declared {Ty::of(asn.typ.clone())}else {ty};
+        self.declare(*id, ty, declared);
+      }
+      to=> self.bind(to)
+    }
+  }
+
+  /// 推断表达式的类型, 同时检查其中的调用和属性访问
+  fn expr(&mut self, e:&Expr)-> Ty {
+    use KsType::*;
+    match e {
+      Expr::Literal(v)=> Ty::of(litr_type(v)),
+      Expr::Variant(id)=> self.var(*id).map_or(Ty::any(), |v|v.ty.clone()),
+      Expr::Kself=> Ty::of(self.funcs.last().map_or(Any, |f|f.kself.clone())),
+      Expr::LocalDecl(f)=> self.func(*f, Any),
+
+      Expr::Property(e, name)=> {
+        let t = self.expr(e).t;
+        self.prop(&t, *name)
+      }
+      Expr::ImplAccess(e, name)=> self.impl_access(e, *name),
+      // 左侧为uninit时直接得到uninit
+      Expr::Optional(e)=> {
+        self.expr(e);
+        Ty::any()
+      }
+      Expr::OptLeft(e)=> self.expr(e),
+
+      Expr::Call { args, targ }=> self.call(targ, args),
+      Expr::CallMethod { args, targ, name, .. }=> {
+        let t = self.expr(targ).t;
+        self.method(&t, *name, args)
+      }
+      Expr::Index { left, i }=> {
+        let left = self.expr(left).t;
+        self.expr(i);
+        match left {
+          KsType::ListOf(t)=> Ty::of(*t),
+          _=> Ty::any()
+        }
+      }
+      Expr::NewInst { cls, val }=> self.new_inst(cls, val),
+
+      Expr::List(v)=> {
+        for e in v {
+          self.expr(e);
+        }
+        Ty::of(List)
+      }
+      Expr::Obj(v)=> {
+        for (_, e) in v {
+          self.expr(e);
+        }
+        Ty::of(Obj)
+      }
+      Expr::Template(parts)=> {
+        for p in parts {
+          if let TmplPart::Hole(e, _) = p {
+            self.expr(e);
+          }
+        }
+        Ty::of(Str)
+      }
+
+      Expr::Unary { right, op }=> {
+        let t = self.expr(right).t;
+        Ty::of(match (op, t) {
+          (b'-', Int)=> Int,
+          (b'-', Float)=> Float,
+          (b'!', Bool)=> Bool,
+          (b'!', Int)=> Int,
+          (b'!', Uint)=> Uint,
+          (b'-'|b'!', BigInt)=> BigInt,
+          (_, Any|Union(_)|Optional(_))=> Any,
+          (b'-', t)=> {
+            self.err(format!("负号只能用在有符号数, 但用在了{:?}", t));
+            Any
+          }
+          (b'!', t)=> {
+            self.err(format!("!运算符只能用于整数和Bool, 但用在了{:?}", t));
+            Any
+          }
+          _=> Any
+        })
+      }
+      Expr::Binary { left, right, op }=> self.binary(left, right, op),
+      Expr::Is { left, .. }=> {
+        self.expr(left);
+        Ty::of(Bool)
+      }
+      Expr::Loop(s)=> {
+        self.stmt(s);
+        Ty::any()
+      }
+      Expr::Spread(e)|Expr::Named(_, e)=> {
+        self.expr(e);
+        Ty::any()
+      }
+      Expr::Empty|Expr::ModFuncAcc(..)|Expr::ModClsAcc(..)=> Ty::any()
+    }
+  }
+
+  fn binary(&mut self, left:&Expr, right:&Expr, op:&[u8])-> Ty {
+    use KsType::*;
+    match op {
+      b"="=> {
+        let r = self.expr(right);
+        self.set(left, &r.t);
+        r
+      }
+      b"+="|b"-="|b"*="|b"/="|b"%="|b"&="|b"|="|b"^="|b"<<="|b">>="=> {
+        let l = self.expr(left).t;
+        let r = self.expr(right).t;
+        let t = arith(&op[..op.len()-1], &l, &r);
+        self.set(left, &t);
+        Ty::of(t)
+      }
+      _=> {
+        let l = self.expr(left).t;
+        let r = self.expr(right).t;
+        Ty::of(match op {
+          b"=="|b"!="|b"<"|b">"|b"<="|b">="|b"&&"|b"||"=> Bool,
+          b"+"|b"-"|b"*"|b"/"|b"%"|b"&"|b"|"|b"^"|b"<<"|b">>"=> arith(op, &l, &r),
+          _=> Any
+        })
+      }
+    }
+  }
+
+  /// 检查对left的赋值
+  fn set(&mut self, left:&Expr, t:&KsType) {
+    match left {
+      Expr::Variant(id)=> {
+        let (want, declared) = match self.var(*id) {
+          Some(v)=> (v.ty.t.clone(), v.declared),
+          None=> return
+        };
+        if declared {
+          if !self.fits(&want, t) {
+            self.err(format!("变量'{}'要求{:?}类型, 但赋值了{:?}", id, want, t));
+          }
+        }else {
+          // 没有类型声明的变量赋值后就无法确定类型了
+          self.var(*id).unwrap().ty = Ty::any();
+        }
+      }
+      Expr::Property(e, name)=> {
+        let from = self.expr(e).t;
+        let want = self.prop(&from, *name).t;
+        if !self.fits(&want, t) {
+          self.err(format!("'{}'属性要求{:?}类型, 但赋值了{:?}", name, want, t));
+        }
+      }
+      _=> {self.expr(left);}
+    }
+  }
+
+  /// 本地类实例的属性
+  fn prop(&mut self, t:&KsType, name:Interned)-> Ty {
+    if let KsType::Class(c) = t {
+      if let Some(ClsInfo::Local(cls)) = self.find_class(*c) {
+        let cls = unsafe {&*cls};
+        match cls.props.iter().find(|prop|prop.name == name) {
+          Some(prop)=> return Ty::of(prop.typ.clone()),
+          None=> self.err(format!("'{}'类型上没有'{}'属性", cls.name, name))
+        }
+      }
+    }
+    Ty::any()
+  }
+
+  /// `Enum::Variant`的枚举声明
+  fn find_enum(&self, e:&Expr)-> Option<*const EnumDefRaw> {
+    if let Expr::ImplAccess(cls, _) = e {
+      if let Expr::Variant(id) = &**cls {
+        if let Some(ClsInfo::Enum(def)) = self.find_class(*id) {
+          return Some(def);
+        }
+      }
+    }
+    None
+  }
+
+  /// Class::name
+  fn impl_access(&mut self, e:&Expr, name:Interned)-> Ty {
+    let id = match e {
+      Expr::Variant(id)=> *id,
+      _=> return Ty::any()
+    };
+    match self.find_class(id) {
+      Some(ClsInfo::Local(cls))=> {
+        let cls = unsafe {&*cls};
+        match cls.statics.iter().chain(cls.methods.iter()).find(|f|f.f.name == name) {
+          Some(f)=> return Ty {t:KsType::Func, f:Some(&f.f)},
+          None=> self.err(format!("'{}'类型没有'{}'方法", cls.name, name))
+        }
+      }
+      Some(ClsInfo::Native(cls))=> {
+        let cls = unsafe {&*cls};
+        if cls.statics.iter().any(|(n, _)|*n == name) {
+          return Ty::of(KsType::Func);
+        }
+        self.err(format!("'{}'原生类型中没有'{}'静态方法", cls.name, name));
+      }
+      Some(ClsInfo::Enum(def))=> {
+        let d = unsafe {&*def};
+        if d.variants.iter().any(|v|v.name == name) {
+          return Ty::of(KsType::Class(d.name));
+        }
+        self.err(format!("枚举'{}'没有'{}'成员", d.name, name));
+      }
+      Some(_)=> (),
+      None=> self.err(format!("未定义类'{}'", id))
+    }
+    Ty::any()
+  }
+
+  fn call(&mut self, targ:&Expr, args:&[Expr])-> Ty {
+    // Enum::Variant(a, b)
+    if let Some(def) = self.find_enum(targ) {
+      let d = unsafe {&*def};
+      if let Expr::ImplAccess(_, name) = targ {
+        if let Some(v) = d.variants.iter().find(|v|v.name == *name) {
+          self.args(v.name, &v.fields, args);
+          return Ty::of(KsType::Class(d.name));
+        }
+      }
+    }
+
+    let f = self.expr(targ);
+    match (f.f, f.t) {
+      (Some(f), _)=> self.call_local(f, args),
+      // 按函数签名检查传入的参数
+      (None, KsType::Sig(sig, ret))=> {
+        let mut n = 0;
+        for arg in args {
+          let t = self.expr(arg).t;
+          if let Expr::Spread(_)|Expr::Named(..) = arg {
+            continue;
+          }
+          if let Some(want) = sig.get(n) {
+            if !self.fits(want, &t) {
+              self.err(format!("函数签名的第{}个参数要求{:?}类型, 但传入了{:?}", n + 1, want, t));
+            }
+          }
+          n += 1;
+        }
+        Ty::of(*ret)
+      }
+      (None, t)=> {
+        if !matches!(t, KsType::Any|KsType::Func|KsType::Optional(_)|KsType::Union(_)) {
+          self.err(format!("{:?}类型的值不是函数", t));
+        }
+        for arg in args {
+          self.expr(arg);
+        }
+        Ty::any()
+      }
+    }
+  }
+
+  /// 检查对本地函数的调用, 返回其返回值类型
+  fn call_local(&mut self, f:*const LocalFuncRaw, args:&[Expr])-> Ty {
+    let f = unsafe {&*f};
+    match &f.argdecl {
+      LocalFuncRawArg::Normal(decl)=> self.args(f.name, decl, args),
+      LocalFuncRawArg::Custom(_)=> for arg in args {
+        self.expr(arg);
+      }
+    }
+    if f.generator {
+      Ty::of(KsType::Class(intern(b"Generator")))
+    }else {
+      Ty::of(f.ret.clone())
+    }
+  }
+
+  /// 实例的方法调用
+  fn method(&mut self, t:&KsType, name:Interned, args:&[Expr])-> Ty {
+    if let KsType::Class(c) = t {
+      match self.find_class(*c) {
+        Some(ClsInfo::Local(cls))=> {
+          let cls = unsafe {&*cls};
+          match cls.methods.iter().find(|f|f.f.name == name) {
+            Some(f)=> return self.call_local(&f.f, args),
+            None=> self.err(format!("'{}'类型没有'{}'方法", cls.name, name))
+          }
+        }
+        Some(ClsInfo::Native(cls))=> {
+          let cls = unsafe {&*cls};
+          if !cls.methods.iter().any(|(n, _)|*n == name) {
+            self.err(format!("'{}'原生类型中没有'{}'方法", cls.name, name));
+          }
+        }
+        _=> ()
+      }
+    }
+    for arg in args {
+      self.expr(arg);
+    }
+    Ty::any()
+  }
+
+  /// 按参数声明检查传入的参数
+  fn args(&mut self, fname:Interned, decl:&[ArgDecl], args:&[Expr]) {
+    let mut given = vec![false; decl.len()];
+    // 展开参数后无法确定参数的位置
+    let mut spread = false;
+    let mut n = 0;
+    for arg in args {
+      match arg {
+        Expr::Spread(e)=> {
+          self.expr(e);
+          spread = true;
+        }
+        Expr::Named(name, e)=> {
+          let t = self.expr(e).t;
+          match decl.iter().position(|a|a.name == *name) {
+            Some(i)=> {
+              given[i] = true;
+              self.arg_fits(fname, &decl[i], &t);
+            }
+            // 不是参数名时是赋值表达式, 传入uninit
+            None=> n += 1
+          }
+        }
+        _=> {
+          let t = self.expr(arg).t;
+          if !spread && n < decl.len() {
+            given[n] = true;
+            self.arg_fits(fname, &decl[n], &t);
+          }
+          n += 1;
+        }
+      }
+    }
+    if spread {
+      return;
+    }
+    if n > decl.len() {
+      self.err(format!("'{}'只接受{}个参数, 但传入了{}个", fname, decl.len(), n));
+    }
+    // 缺省的参数会得到uninit, 只有Any类型能接受
+    for (a, given) in decl.iter().zip(given) {
+      if !given && !accepts_uninit(&a.t) && is_uninit(&a.default) {
+        self.err(format!("调用'{}'时缺少{:?}类型的参数'{}'", fname, a.t, a.name));
+      }
+    }
+  }
+
+  fn arg_fits(&mut self, fname:Interned, a:&ArgDecl, t:&KsType) {
+    if !self.fits(&a.t, t) {
+      self.err(format!("'{}'的参数'{}'要求{:?}类型, 但传入了{:?}", fname, a.name, a.t, t));
+    }
+  }
+
+  /// Class::{}
+  fn new_inst(&mut self, cls:&Expr, val:&[(Interned, Expr)])-> Ty {
+    let id = match cls {
+      Expr::Variant(id)=> *id,
+      _=> {
+        for (_, e) in val {
+          self.expr(e);
+        }
+        return Ty::any();
+      }
+    };
+    let def = match self.find_class(id) {
+      Some(ClsInfo::Local(cls))=> Some(unsafe {&*cls}),
+      Some(ClsInfo::Enum(_))=> {
+        self.err(format!("枚举'{}'需要使用'{}::成员'创建", id, id));
+        None
+      }
+      Some(ClsInfo::Native(_))=> {
+        self.err(format!("无法直接构建原生类型'{}'", id));
+        None
+      }
+      Some(_)=> None,
+      None=> {
+        self.err(format!("未定义类'{}'", id));
+        None
+      }
+    };
+    for (name, e) in val {
+      let t = self.expr(e).t;
+      if let (Some(def), false) = (def, matches!(e, Expr::Spread(_))) {
+        match def.props.iter().find(|prop|prop.name == *name) {
+          Some(prop)=> if !self.fits(&prop.typ, &t) {
+            self.err(format!("'{}'属性要求{:?}类型, 但传入了{:?}", name, prop.typ, t));
+          }
+          None=> self.err(format!("'{}'类型不存在'{}'属性", def.name, name))
+        }
+      }
+    }
+    Ty::of(KsType::Class(id))
+  }
+}
@@ -0,0 +1,318 @@
+//! 交互式调试器
+//!
+//! 使用--debug运行时, 每条语句执行前都会经过on_line, 遇到断点或单步时暂停并读取命令.
+//! 断点可以写作`行号`(主脚本), `文件:行号`或`函数名`, 也可以用--break=...在启动时设置
+//!
+//! 暂停时可用的命令见HELP
+
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+use crate::intern::{intern, Interned};
+use crate::primitive::litr::{Function, Litr};
+use crate::runtime::{call::call_stack, Scope};
+use crate::scan::stmt::Stmt;
+
+/// 是否处于调试模式, 为false时钩子直接返回
+pub static mut ACTIVE:bool = false;
+
+/// 当前语句开头所在的行, 调用时记进调用栈给bt用
+pub static mut START:usize = 0;
+
+/// 单步方式, 数字为开始单步时的调用深度
+#[derive(Clone, Copy)]
+enum Mode {
+  /// 运行到下一个断点
+  Run,
+  /// 在下一条语句暂停, 会进入函数
+  Step,
+  /// 在不深于该深度的下一条语句暂停
+  Next(usize),
+  /// 回到更浅的调用层时暂停
+  Out(usize)
+}
+
+enum Breakpoint {
+  /// 文件(为空时指主脚本)和行号
+  Line(Option<String>, usize),
+  Func(Interned)
+}
+
+impl std::fmt::Display for Breakpoint {
+  fn fmt(&self, f:&mut std::fmt::Formatter<'_>)-> std::fmt::Result {
+    match self {
+      Breakpoint::Line(Some(file), line)=> write!(f, "{}:{}", file, line),
+      Breakpoint::Line(None, line)=> write!(f, "第{}行", line),
+      Breakpoint::Func(name)=> write!(f, "函数{}", name)
+    }
+  }
+}
+
+struct Debugger {
+  mode: Mode,
+  breakpoints: Vec<Breakpoint>,
+  /// 主脚本路径
+  main: &'static str,
+  /// 显示源码用的文件缓存
+  sources: HashMap<&'static str, Vec<String>>,
+  /// 直接回车时重复的命令
+  last: String
+}
+
+static mut DEBUGGER:Option<Debugger> = None;
+
+const HELP:&str = "\
+  c            继续运行到下一个断点
+  s            单步, 会进入函数
+  n            单步, 不进入函数
+  o            运行到当前函数返回
+  b            列出断点
+  b <位置>     添加断点, 位置为行号, 文件:行号或函数名
+  d <序号>     删除断点
+  l            打印当前作用域及其上层的变量
+  p <表达式>   在当前作用域求值并打印
+  bt           打印调用栈
+  q            退出
+  h            显示帮助";
+
+/// 开启调试模式, 有启动断点时直接运行, 否则停在第一条语句
+pub fn init(main:&'static str, breaks:&[String]) {
+  let mut dbg = Debugger {
+    mode: Mode::Step,
+    breakpoints: Vec::new(),
+    main,
+    sources: HashMap::new(),
+    last: String::new()
+  };
+  for b in breaks {
+    dbg.breakpoints.push(parse_breakpoint(b));
+    dbg.mode = Mode::Run;
+  }
+  unsafe {
+    DEBUGGER = Some(dbg);
+    ACTIVE = true;
+  }
+}
+
+fn parse_breakpoint(s:&str)-> Breakpoint {
+  if let Ok(line) = s.parse() {
+    return Breakpoint::Line(None, line);
+  }
+  if let Some((file, line)) = s.rsplit_once(':') {
+    if let Ok(line) = line.parse() {
+      return Breakpoint::Line(Some(file.to_string()), line);
+    }
+  }
+  Breakpoint::Func(intern(s.as_bytes()))
+}
+
+fn debugger()-> &'static mut Debugger {
+  unsafe {(*std::ptr::addr_of_mut!(DEBUGGER)).as_mut().expect("调试器未初始化")}
+}
+
+/// 进入函数时检查函数断点
+pub fn on_call(name:Interned) {
+  let dbg = debugger();
+  if dbg.breakpoints.iter().any(|b|matches!(b, Breakpoint::Func(n) if *n == name)) {
+    dbg.mode = Mode::Step;
+  }
+}
+
+/// 每条语句执行前调用, line为语句开头所在的行
+pub fn on_line(scope:Scope, line:usize) {
+  let dbg = debugger();
+  let file = unsafe {
+    START = line;
+    crate::FILE_PATH
+  };
+  let depth = call_stack().len();
+  let stop = match dbg.mode {
+    Mode::Run=> false,
+    Mode::Step=> true,
+    Mode::Next(d)=> depth <= d,
+    Mode::Out(d)=> depth < d
+  } || dbg.breakpoints.iter().any(|b|match b {
+    Breakpoint::Line(Some(f), l)=> *l == line && file.ends_with(f.as_str()),
+    Breakpoint::Line(None, l)=> *l == line && file == dbg.main,
+    Breakpoint::Func(_)=> false
+  });
+  if stop {
+    pause(dbg, scope, file, line, depth);
+  }
+}
+
+fn pause(dbg:&mut Debugger, scope:Scope, file:&'static str, line:usize, depth:usize) {
+  println!("> 暂停于 {}:{}", file, line);
+  if let Some(src) = source(dbg, file, line) {
+    println!("  {:>4} | {}", line, src);
+  }
+
+  let stdin = std::io::stdin();
+  loop {
+    print!("(kdb) ");
+    let _ = std::io::stdout().flush();
+    let mut input = String::new();
+    // 输入结束后不再暂停
+    if stdin.lock().read_line(&mut input).unwrap_or(0) == 0 {
+      unsafe {ACTIVE = false;}
+      return;
+    }
+    let mut input = input.trim().to_string();
+    if input.is_empty() {
+      input = dbg.last.clone();
+    }else {
+      dbg.last = input.clone();
+    }
+    let (cmd, arg) = input.split_once(' ').map_or((&*input, ""), |(c, a)|(c, a.trim()));
+
+    match cmd {
+      "c"=> {
+        dbg.mode = Mode::Run;
+        return;
+      }
+      "s"=> {
+        dbg.mode = Mode::Step;
+        return;
+      }
+      "n"=> {
+        dbg.mode = Mode::Next(depth);
+        return;
+      }
+      "o"=> {
+        dbg.mode = Mode::Out(depth);
+        return;
+      }
+      "b" if arg.is_empty()=> {
+        if dbg.breakpoints.is_empty() {
+          println!("  没有断点");
+        }
+        for (i, b) in dbg.breakpoints.iter().enumerate() {
+          println!("  {}: {}", i, b);
+        }
+      }
+      "b"=> {
+        let b = parse_breakpoint(arg);
+        println!("  断点{}: {}", dbg.breakpoints.len(), b);
+        dbg.breakpoints.push(b);
+      }
+      "d"=> match arg.parse::<usize>() {
+        Ok(i) if i < dbg.breakpoints.len()=> {
+          println!("  已删除断点{}", dbg.breakpoints.remove(i));
+        }
+        _=> println!("  没有断点'{}'", arg)
+      }
+      "l"=> locals(scope),
+      "p" if arg.is_empty()=> println!("  p需要一个表达式"),
+      "p"=> eval(scope, arg),
+      "bt"=> backtrace(file, line),
+      "q"=> std::process::exit(0),
+      "h"=> println!("{}", HELP),
+      _=> println!("  未知命令'{}', 输入h查看帮助", cmd)
+    }
+  }
+}
+
+/// 读取文件中的一行, 读不到时不显示
+fn source<'a>(dbg:&'a mut Debugger, file:&'static str, line:usize)-> Option<&'a str> {
+  let lines = dbg.sources.entry(file).or_insert_with(||
+    std::fs::read(file).map_or(Vec::new(), |b|
+      String::from_utf8_lossy(&b).lines().map(|l|l.to_string()).collect())
+  );
+  lines.get(line.checked_sub(1)?).map(|l|l.trim())
+}
+
+/// 以Key的写法显示值, 过长的值只显示开头
+fn short(v:&Litr)-> String {
+  // 实例的@str钩子可能运行脚本, 不能在其中再次暂停
+  let s = unsafe {
+    let active = ACTIVE;
+    ACTIVE = false;
+    let s = match v {
+      Litr::Str(s)=> format!("\"{}\"", s),
+      v=> v.str()
+    };
+    ACTIVE = active;
+    s
+  };
+  match s.char_indices().nth(120) {
+    Some((i, _))=> format!("{}...", &s[..i]),
+    None=> s
+  }
+}
+
+fn locals(scope:Scope) {
+  let kself = unsafe {&*scope.kself};
+  if !matches!(kself, Litr::Uninit) {
+    println!("  self = {}", short(kself));
+  }
+  let mut cur = Some(scope);
+  let mut level = 0;
+  while let Some(s) = cur {
+    // 顶层作用域中内置的函数不显示
+    let vars:Vec<_> = s.vars.iter()
+      .filter(|v|!(v.locked && matches!(v.v, Litr::Func(Function::Native(_)))))
+      .collect();
+    if !vars.is_empty() {
+      println!("  [{}]", level);
+      for v in vars {
+        println!("    {}{} = {}", if v.locked {"const "}else {""}, v.name, short(&v.v));
+      }
+    }
+    cur = s.parent;
+    level += 1;
+  }
+}
+
+fn backtrace(file:&str, line:usize) {
+  println!("  #0 {}:{}", file, line);
+  for (i, f) in call_stack().iter().rev().enumerate() {
+    println!("  #{} {} 调用于 {}:{}", i + 1, f.fname, f.file, f.start);
+  }
+}
+
+/// 在暂停的作用域中运行一段代码, 出错时只打印错误
+fn eval(mut scope:Scope, src:&str) {
+  let (file, line, start, depth) = unsafe {
+    (crate::FILE_PATH, crate::LINE, START, call_stack().len())
+  };
+  let hook = std::panic::take_hook();
+  std::panic::set_hook(Box::new(|_|()));
+  // 求值时不触发断点, 否则会在暂停中再次暂停
+  unsafe {ACTIVE = false;}
+  let res = std::panic::catch_unwind(move ||{
+    // 其中定义的函数和类会引用这段语法树, 所以不能随求值结束释放
+    let scanned:&'static _ = Box::leak(Box::new(crate::scan::scan(src.as_bytes())));
+    let mut ret = None;
+    for (_, sm) in &scanned.v {
+      ret = match sm {
+        Stmt::Expression(e)=> Some(scope.calc(e)),
+        sm=> {
+          scope.evil(sm);
+          None
+        }
+      };
+    }
+    ret
+  });
+  std::panic::set_hook(hook);
+  unsafe {
+    ACTIVE = true;
+    START = start;
+    crate::FILE_PATH = file;
+    crate::LINE = line;
+    call_stack().truncate(depth);
+  }
+
+  match res {
+    Ok(Some(v))=> println!("  {}", short(&v)),
+    Ok(None)=> (),
+    Err(err)=> {
+      let s = if let Some(mes) = err.downcast_ref::<&'static str>() {
+        mes.to_string()
+      }else if let Some(mes) = err.downcast_ref::<String>() {
+        mes.clone()
+      }else {"错误".to_string()};
+      println!("  错误: {}", s);
+    }
+  }
+}
@@ -5,8 +5,31 @@
 //! 但字符串越短，性能收益越小
 
 use std::collections::HashSet;
+use std::borrow::Borrow;
+use std::hash::{Hash, Hasher};
 
-static mut POOL:*mut HashSet<Box<[u8]>> = std::ptr::null_mut();
+/// 池中的字符串
+/// 
+/// 多套一层Box, 保证HashSet扩容时Interned指向的Box<[u8]>地址不变
+struct PoolKey(Box<Box<[u8]>>);
+impl Borrow<[u8]> for PoolKey {
+  fn borrow(&self)-> &[u8] {
+    &self.0
+  }
+}
+impl Hash for PoolKey {
+  fn hash<H: Hasher>(&self, state: &mut H) {
+    (**self.0).hash(state)
+  }
+}
+impl PartialEq for PoolKey {
+  fn eq(&self, other:&Self)-> bool {
+    self.0 == other.0
+  }
+}
+impl Eq for PoolKey {}
+
+static mut POOL:*mut HashSet<PoolKey> = std::ptr::null_mut();
 
 pub fn init() {
   unsafe{POOL = Box::into_raw(Box::new(HashSet::with_capacity(64)));}
@@ -15,7 +38,8 @@ pub fn init() {
 /// 将字符串缓存为指针
 pub fn intern(s:&[u8])-> Interned {
   let p = unsafe{&mut *POOL};
-  Interned { p:p.get_or_insert(s.into()) as *const Box<[u8]> }
+  let key = p.get_or_insert_with(s, |s|PoolKey(Box::new(s.into())));
+  Interned { p:&*key.0 as *const Box<[u8]> }
 }
 
 /// 字符串缓存
@@ -24,6 +48,10 @@ pub fn intern(s:&[u8])-> Interned {
 pub struct Interned {
   p: *const Box<[u8]>
 }
+/// 池中的字符串只增不减, 也不会被修改, 可以在线程间共享
+unsafe impl Send for Interned {}
+unsafe impl Sync for Interned {}
+
 impl Interned {
   pub const fn vec(&self)-> &[u8] {
     unsafe{&**self.p}
@@ -8,6 +8,8 @@ mod scan;
 mod runtime;
 mod primitive;
 mod utils;
+mod check;
+mod debugger;
 
 mod c;
 mod native;
@@ -15,10 +17,19 @@ mod native;
 /// 全局选项
 struct GlobalOptions {
   /// --ast
-  print_ast: bool
+  print_ast: bool,
+  /// --check 只做类型检查, 不运行
+  check: bool,
+  /// --stack=MB 解释器线程的栈大小
+  stack_size: usize,
+  /// --debug 以调试模式运行
+  debug: bool
 }
 static mut GLOBAL_OPTIONS:GlobalOptions = GlobalOptions {
-  print_ast: false
+  print_ast: false,
+  check: false,
+  stack_size: 256 << 20,
+  debug: false
 };
 
 /// 标志目前走到的行号
@@ -36,6 +47,9 @@ static VERSION:usize = 100062;
 /// 用户可以使用distribution()直接读取此值
 static DISTRIBUTION:&str = "Subkey";
 
+/// 报错时调用栈两端各打印的层数
+const STACK_PRINT:usize = 16;
+
 fn main()-> ExitCode {
   // linux macos支持
   // 脚本打包exe
@@ -45,25 +59,44 @@ fn main()-> ExitCode {
   // 获取路径
   let mut args = std::env::args();
   args.next();
-  let path = if let Some(s) = args.next() {
+  let path:&'static str = if let Some(s) = args.next() {
     utils::to_absolute_path(s).leak()
   }else {
     println!("> Key Lang\n  version: {}\n  by: {}", VERSION, DISTRIBUTION);
     return ExitCode::SUCCESS;
   };
   
+  // --break=位置 调试模式的启动断点, 会同时开启--debug
+  let mut breaks = Vec::new();
   while let Some(n) = args.next() {
     let opts = unsafe {&mut GLOBAL_OPTIONS};
     match &*n {
       "--ast"=> opts.print_ast = true,
-      _=>()
+      "--check"=> opts.check = true,
+      "--debug"=> opts.debug = true,
+      _=> if let Some(v) = n.strip_prefix("--max-depth=") {
+        runtime::call::set_max_depth(v.parse().expect("--max-depth需要一个整数"));
+      }else if let Some(v) = n.strip_prefix("--break=") {
+        opts.debug = true;
+        breaks.push(v.to_string());
+      }else if let Some(v) = n.strip_prefix("--stack=") {
+        opts.stack_size = v.parse::<usize>().expect("--stack需要一个整数(MB)") << 20;
+      }
     }
   }
 
+  if unsafe {GLOBAL_OPTIONS.debug} {
+    debugger::init(path, &breaks);
+  }
+
   // 自定义报错
   unsafe {FILE_PATH = path}
   std::panic::set_hook(Box::new(|inf| {
     use crate::utils::date;
+    // 生成器中的报错会交给调用next的线程
+    if std::thread::current().name() == Some(primitive::generator::THREAD_NAME) {
+      return;
+    }
     let line = unsafe{LINE};
     let place = unsafe{&*FILE_PATH};
     let s = if let Some(mes) = inf.payload().downcast_ref::<&'static str>() {
@@ -72,10 +105,19 @@ fn main()-> ExitCode {
       mes
     }else{"错误"};
 
-    let stack = unsafe{
+    let stack = {
       let mut s = String::new();
       use std::fmt::Write;
-      for n in runtime::call::CALL_STACK.iter().rev() {
+      // 调用栈太深时只打印两端
+      let frames = runtime::call::call_stack();
+      let omit = frames.len().saturating_sub(STACK_PRINT * 2);
+      for (i, n) in frames.iter().rev().enumerate() {
+        if omit > 0 && i >= STACK_PRINT && i < STACK_PRINT + omit {
+          if i == STACK_PRINT {
+            let _ = s.write_fmt(format_args!("\n    ...省略{}层", omit));
+          }
+          continue;
+        }
         let _ = s.write_fmt(format_args!("\n    {} at {}:{}",n.fname,n.file,n.line));
       }
       s
@@ -83,11 +125,40 @@ fn main()-> ExitCode {
     println!("\n> {}\n  {}:第{}行{}\n\n> Key Script CopyLeft by {}\n  {}", s, place, line, stack, DISTRIBUTION, date());
   }));
 
+  // 在单独的线程中运行, 以便使用更大的栈
+  let stack_size = unsafe{GLOBAL_OPTIONS.stack_size};
+  std::thread::Builder::new()
+    .stack_size(stack_size)
+    .spawn(move ||{
+      runtime::call::set_stack_size(stack_size);
+      start(path)
+    })
+    .expect("无法创建解释器线程")
+    .join()
+    // 报错已由panic hook打印
+    .unwrap_or(ExitCode::from(101))
+}
+
+/// 扫描并运行脚本
+fn start(path:&'static str)-> ExitCode {
   // 运行并返回
   let scanned = scan::scan(&fs::read(&path).unwrap_or_else(|e|
     panic!("无法读取'{}': {}", path, e)));
   if unsafe{GLOBAL_OPTIONS.print_ast} {println!("{scanned:?}")}
 
+  if unsafe{GLOBAL_OPTIONS.check} {
+    let errors = check::check(&scanned);
+    if errors.len() == 0 {
+      println!("> 类型检查通过");
+      return ExitCode::SUCCESS;
+    }
+    println!("> 类型检查发现{}个问题", errors.len());
+    for (line, mes) in errors {
+      println!("  {}:第{}行: {}", path, line, mes);
+    }
+    return ExitCode::FAILURE;
+  }
+
   let exit = runtime::run(&scanned, path);
 
   // 如果原生模块调用了wait_inc就堵住当前线程
@@ -68,6 +68,7 @@ struct FuncTable {
   planet_ok: fn(&mut planet::Planet, Litr),
   local_instance_clone: fn(&Instance)-> Instance,
   local_instance_drop: fn(&mut Instance),
+  set_max_depth: fn(usize),
 }
 static FUNCTABLE:FuncTable = FuncTable {
   intern, 
@@ -93,10 +94,11 @@ static FUNCTABLE:FuncTable = FuncTable {
   planet_ok: planet::rust_ok,
   local_instance_clone: <Instance as Clone>::clone,
   local_instance_drop: |v|unsafe{std::ptr::drop_in_place(v)},
+  set_max_depth: crate::runtime::call::set_max_depth,
 };
 
 /// 原生类型实例
-#[derive(Debug, PartialEq)]
+#[derive(PartialEq)]
 #[repr(C)]
 pub struct NativeInstance {
   pub v: usize,
@@ -115,6 +117,12 @@ impl Drop for NativeInstance {
     (unsafe{&*self.cls}.ondrop)(self)
   }
 }
+impl std::fmt::Debug for NativeInstance {
+  /// 调试输出同样使用原生类的to_str
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    f.write_str(&(unsafe{&*self.cls}.to_str)(self))
+  }
+}
 
 /// wait_inc和wait_dec的主线程阻塞器
 pub static mut WAITING: Mutex<isize> = Mutex::new(0);
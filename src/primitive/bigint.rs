@@ -0,0 +1,653 @@
+//! 任意精度整数BigInt
+//!
+//! 字面量写作`123n`, 和Int或Uint运算时会自动转为BigInt
+
+use super::*;
+use std::cmp::Ordering;
+use std::sync::LazyLock;
+
+/// 符号和绝对值分开存储的大整数
+///
+/// 绝对值按32位分段小端存储, 最高段不为0, 0的绝对值为空
+#[derive(Clone, PartialEq, Eq, Hash, Default)]
+pub struct BigInt {
+  neg: bool,
+  mag: Vec<u32>
+}
+
+impl std::fmt::Debug for BigInt {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(f, "{}n", self.to_str_radix(10))
+  }
+}
+
+// - 绝对值运算 -
+
+fn trim(mut v:Vec<u32>)-> Vec<u32> {
+  while v.last() == Some(&0) {
+    v.pop();
+  }
+  v
+}
+
+fn mag_cmp(a:&[u32], b:&[u32])-> Ordering {
+  if a.len() != b.len() {
+    return a.len().cmp(&b.len());
+  }
+  for (x, y) in a.iter().rev().zip(b.iter().rev()) {
+    if x != y {
+      return x.cmp(y);
+    }
+  }
+  Ordering::Equal
+}
+
+fn mag_add(a:&[u32], b:&[u32])-> Vec<u32> {
+  let (a, b) = if a.len() >= b.len() {(a, b)}else {(b, a)};
+  let mut out = Vec::with_capacity(a.len() + 1);
+  let mut carry = 0u64;
+  for (i, x) in a.iter().enumerate() {
+    let s = *x as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+    out.push(s as u32);
+    carry = s >> 32;
+  }
+  if carry > 0 {
+    out.push(carry as u32);
+  }
+  out
+}
+
+/// a必须不小于b
+fn mag_sub(a:&[u32], b:&[u32])-> Vec<u32> {
+  let mut out = Vec::with_capacity(a.len());
+  let mut borrow = 0i64;
+  for (i, x) in a.iter().enumerate() {
+    let mut d = *x as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+    borrow = if d < 0 {
+      d += 1 << 32;
+      1
+    }else {0};
+    out.push(d as u32);
+  }
+  trim(out)
+}
+
+fn mag_mul(a:&[u32], b:&[u32])-> Vec<u32> {
+  if a.is_empty() || b.is_empty() {
+    return Vec::new();
+  }
+  let mut out = vec![0u32; a.len() + b.len()];
+  for (i, x) in a.iter().enumerate() {
+    let mut carry = 0u64;
+    for (j, y) in b.iter().enumerate() {
+      let t = *x as u64 * *y as u64 + out[i + j] as u64 + carry;
+      out[i + j] = t as u32;
+      carry = t >> 32;
+    }
+    out[i + b.len()] = carry as u32;
+  }
+  trim(out)
+}
+
+/// 除以单段数字, 返回商和余数
+fn mag_divrem_small(a:&[u32], b:u32)-> (Vec<u32>, u32) {
+  let mut out = vec![0u32; a.len()];
+  let mut rem = 0u64;
+  for i in (0..a.len()).rev() {
+    let cur = (rem << 32) | a[i] as u64;
+    out[i] = (cur / b as u64) as u32;
+    rem = cur % b as u64;
+  }
+  (trim(out), rem as u32)
+}
+
+/// Knuth算法D, 返回商和余数
+fn mag_divrem(a:&[u32], b:&[u32])-> (Vec<u32>, Vec<u32>) {
+  assert!(!b.is_empty(), "除数不能为0");
+  if mag_cmp(a, b) == Ordering::Less {
+    return (Vec::new(), a.to_vec());
+  }
+  if b.len() == 1 {
+    let (q, r) = mag_divrem_small(a, b[0]);
+    return (q, trim(vec![r]));
+  }
+
+  // 让除数最高位为1
+  let s = b.last().unwrap().leading_zeros();
+  let b = trim(shl_bits(b, s));
+  // 被除数多出的一段用于试商
+  let mut a = shl_bits(a, s);
+  let n = b.len();
+  let m = a.len() - n - 1;
+  let mut q = vec![0u32; m + 1];
+  let top = b[n - 1] as u64;
+  let second = b[n - 2] as u64;
+
+  for j in (0..=m).rev() {
+    let num = ((a[j + n] as u64) << 32) | a[j + n - 1] as u64;
+    let mut qhat = num / top;
+    let mut rhat = num % top;
+    while qhat > u32::MAX as u64 || qhat * second > ((rhat << 32) | a[j + n - 2] as u64) {
+      qhat -= 1;
+      rhat += top;
+      if rhat > u32::MAX as u64 {
+        break;
+      }
+    }
+
+    // a[j..j+n+1] -= qhat * b
+    let mut borrow = 0i64;
+    let mut carry = 0u64;
+    for i in 0..n {
+      let p = qhat * b[i] as u64 + carry;
+      carry = p >> 32;
+      let t = a[i + j] as i64 - borrow - (p & 0xffff_ffff) as i64;
+      a[i + j] = t as u32;
+      borrow = if t < 0 {1}else {0};
+    }
+    let t = a[j + n] as i64 - borrow - carry as i64;
+    a[j + n] = t as u32;
+
+    // 减多了就加回来
+    if t < 0 {
+      qhat -= 1;
+      let mut carry = 0u64;
+      for i in 0..n {
+        let s = a[i + j] as u64 + b[i] as u64 + carry;
+        a[i + j] = s as u32;
+        carry = s >> 32;
+      }
+      a[j + n] = a[j + n].wrapping_add(carry as u32);
+    }
+    q[j] = qhat as u32;
+  }
+
+  a.truncate(n);
+  (trim(q), trim(shr_bits(&a, s)))
+}
+
+/// 左移不足32的位数, 结果可能多出一段
+fn shl_bits(a:&[u32], s:u32)-> Vec<u32> {
+  if s == 0 {
+    let mut v = a.to_vec();
+    v.push(0);
+    return v;
+  }
+  let mut out = Vec::with_capacity(a.len() + 1);
+  let mut carry = 0u32;
+  for x in a {
+    out.push((x << s) | carry);
+    carry = x >> (32 - s);
+  }
+  out.push(carry);
+  out
+}
+
+fn shr_bits(a:&[u32], s:u32)-> Vec<u32> {
+  if s == 0 {
+    return a.to_vec();
+  }
+  let mut out = vec![0u32; a.len()];
+  for i in 0..a.len() {
+    let hi = if i + 1 < a.len() {a[i + 1] << (32 - s)}else {0};
+    out[i] = (a[i] >> s) | hi;
+  }
+  out
+}
+
+impl BigInt {
+  fn new(neg:bool, mag:Vec<u32>)-> BigInt {
+    let mag = trim(mag);
+    BigInt {neg: neg && !mag.is_empty(), mag}
+  }
+
+  pub fn from_u128(n:u128)-> BigInt {
+    BigInt::new(false, vec![n as u32, (n >> 32) as u32, (n >> 64) as u32, (n >> 96) as u32])
+  }
+
+  pub fn from_i128(n:i128)-> BigInt {
+    let mut b = BigInt::from_u128(n.unsigned_abs());
+    b.neg = n < 0;
+    b
+  }
+
+  pub fn is_zero(&self)-> bool {
+    self.mag.is_empty()
+  }
+
+  pub fn is_neg(&self)-> bool {
+    self.neg
+  }
+
+  /// 超出u128时返回None
+  fn to_u128(&self)-> Option<u128> {
+    if self.mag.len() > 4 {
+      return None;
+    }
+    Some(self.mag.iter().rev().fold(0u128, |n, x|(n << 32) | *x as u128))
+  }
+
+  pub fn to_isize(&self)-> Option<isize> {
+    let n = self.to_u128()?;
+    if self.neg {
+      isize::try_from(-i128::try_from(n).ok()?).ok()
+    }else {
+      isize::try_from(n).ok()
+    }
+  }
+
+  pub fn to_usize(&self)-> Option<usize> {
+    if self.neg {
+      return None;
+    }
+    usize::try_from(self.to_u128()?).ok()
+  }
+
+  pub fn to_f64(&self)-> f64 {
+    let n = self.mag.iter().rev().fold(0f64, |n, x|n * 4294967296.0 + *x as f64);
+    if self.neg {-n}else {n}
+  }
+
+  /// 截断小数部分, 非有限数会报错
+  pub fn from_f64(n:f64)-> BigInt {
+    assert!(n.is_finite(), "无法将{}转为BigInt", n);
+    let neg = n < 0.0;
+    let mut n = n.abs().trunc();
+    let mut mag = Vec::new();
+    while n >= 1.0 {
+      mag.push((n % 4294967296.0) as u32);
+      n = (n / 4294967296.0).trunc();
+    }
+    BigInt::new(neg, mag)
+  }
+
+  pub fn neg(&self)-> BigInt {
+    BigInt::new(!self.neg, self.mag.clone())
+  }
+
+  pub fn abs(&self)-> BigInt {
+    BigInt::new(false, self.mag.clone())
+  }
+
+  pub fn add(&self, b:&BigInt)-> BigInt {
+    if self.neg == b.neg {
+      return BigInt::new(self.neg, mag_add(&self.mag, &b.mag));
+    }
+    match mag_cmp(&self.mag, &b.mag) {
+      Ordering::Less=> BigInt::new(b.neg, mag_sub(&b.mag, &self.mag)),
+      _=> BigInt::new(self.neg, mag_sub(&self.mag, &b.mag))
+    }
+  }
+
+  pub fn sub(&self, b:&BigInt)-> BigInt {
+    self.add(&b.neg())
+  }
+
+  pub fn mul(&self, b:&BigInt)-> BigInt {
+    BigInt::new(self.neg != b.neg, mag_mul(&self.mag, &b.mag))
+  }
+
+  /// 向0取整的除法, 余数和被除数同号
+  pub fn divrem(&self, b:&BigInt)-> (BigInt, BigInt) {
+    let (q, r) = mag_divrem(&self.mag, &b.mag);
+    (BigInt::new(self.neg != b.neg, q), BigInt::new(self.neg, r))
+  }
+
+  pub fn pow(&self, mut e:usize)-> BigInt {
+    let mut base = self.clone();
+    let mut out = BigInt::from_u128(1);
+    while e > 0 {
+      if e & 1 == 1 {
+        out = out.mul(&base);
+      }
+      e >>= 1;
+      if e > 0 {
+        base = base.mul(&base);
+      }
+    }
+    out
+  }
+
+  /// 模幂, 结果在[0, m)之间
+  pub fn modpow(&self, e:&BigInt, m:&BigInt)-> BigInt {
+    assert!(!e.neg, "modpow的指数不能为负数");
+    assert!(!m.is_zero(), "modpow的模不能为0");
+    let m = m.abs();
+    let mut base = self.rem_euclid(&m);
+    let mut out = BigInt::from_u128(1).rem_euclid(&m);
+    for i in 0..e.bits() {
+      if (e.mag[i / 32] >> (i % 32)) & 1 == 1 {
+        out = out.mul(&base).divrem(&m).1;
+      }
+      base = base.mul(&base).divrem(&m).1;
+    }
+    out
+  }
+
+  /// 非负的余数
+  pub fn rem_euclid(&self, m:&BigInt)-> BigInt {
+    let r = self.divrem(m).1;
+    if r.neg {r.add(&m.abs())}else {r}
+  }
+
+  /// 绝对值的二进制位数
+  pub fn bits(&self)-> usize {
+    match self.mag.last() {
+      Some(top)=> self.mag.len() * 32 - top.leading_zeros() as usize,
+      None=> 0
+    }
+  }
+
+  pub fn shl(&self, n:usize)-> BigInt {
+    let mut mag = vec![0u32; n / 32];
+    mag.extend(shl_bits(&self.mag, (n % 32) as u32));
+    BigInt::new(self.neg, mag)
+  }
+
+  /// 算术右移, 负数向负无穷取整
+  pub fn shr(&self, n:usize)-> BigInt {
+    if self.neg {
+      let one = BigInt::from_u128(1);
+      return self.abs().sub(&one).shr(n).add(&one).neg();
+    }
+    if n / 32 >= self.mag.len() {
+      return BigInt::default();
+    }
+    BigInt::new(false, shr_bits(&self.mag[n / 32..], (n % 32) as u32))
+  }
+
+  /// 转为指定段数的补码
+  fn twos(&self, len:usize)-> Vec<u32> {
+    let mut v = self.mag.clone();
+    v.resize(len, 0);
+    if self.neg {
+      let mut carry = true;
+      for x in v.iter_mut() {
+        *x = !*x;
+        if carry {
+          let (s, c) = x.overflowing_add(1);
+          *x = s;
+          carry = c;
+        }
+      }
+    }
+    v
+  }
+
+  fn from_twos(mut v:Vec<u32>)-> BigInt {
+    let neg = v.last().map_or(false, |x|x >> 31 == 1);
+    if neg {
+      let mut carry = true;
+      for x in v.iter_mut() {
+        *x = !*x;
+        if carry {
+          let (s, c) = x.overflowing_add(1);
+          *x = s;
+          carry = c;
+        }
+      }
+    }
+    BigInt::new(neg, v)
+  }
+
+  /// 按补码进行的位运算
+  pub fn bitwise(&self, b:&BigInt, f:impl Fn(u32, u32)-> u32)-> BigInt {
+    let len = self.mag.len().max(b.mag.len()) + 1;
+    let (x, y) = (self.twos(len), b.twos(len));
+    BigInt::from_twos(x.iter().zip(y.iter()).map(|(x, y)|f(*x, *y)).collect())
+  }
+
+  pub fn not(&self)-> BigInt {
+    self.neg().sub(&BigInt::from_u128(1))
+  }
+
+  /// 按进制解析, 可以带有正负号
+  pub fn parse(s:&str, radix:u32)-> Option<BigInt> {
+    assert!((2..=36).contains(&radix), "进制必须在2到36之间");
+    let (neg, digits) = match s.as_bytes().first() {
+      Some(b'-')=> (true, &s[1..]),
+      Some(b'+')=> (false, &s[1..]),
+      _=> (false, s)
+    };
+    if digits.is_empty() {
+      return None;
+    }
+    let mut mag:Vec<u32> = Vec::new();
+    for c in digits.chars() {
+      if c == '_' {
+        continue;
+      }
+      let d = c.to_digit(radix)?;
+      // mag = mag * radix + d
+      let mut carry = d as u64;
+      for x in mag.iter_mut() {
+        let t = *x as u64 * radix as u64 + carry;
+        *x = t as u32;
+        carry = t >> 32;
+      }
+      if carry > 0 {
+        mag.push(carry as u32);
+      }
+    }
+    Some(BigInt::new(neg, mag))
+  }
+
+  pub fn to_str_radix(&self, radix:u32)-> String {
+    assert!((2..=36).contains(&radix), "进制必须在2到36之间");
+    if self.is_zero() {
+      return "0".to_string();
+    }
+    // 每次除以能放进u32的最大的radix的幂
+    let mut chunk = radix;
+    let mut digits = 1;
+    while let Some(n) = chunk.checked_mul(radix) {
+      chunk = n;
+      digits += 1;
+    }
+    let mut parts = Vec::new();
+    let mut mag = self.mag.clone();
+    while !mag.is_empty() {
+      let (q, r) = mag_divrem_small(&mag, chunk);
+      parts.push(r);
+      mag = q;
+    }
+    let mut s = String::new();
+    if self.neg {
+      s.push('-');
+    }
+    let to_str = |mut n:u32, pad:bool|{
+      let mut v = Vec::new();
+      while n > 0 || (pad && v.len() < digits) {
+        v.push(std::char::from_digit(n % radix, radix).unwrap());
+        n /= radix;
+      }
+      v.into_iter().rev().collect::<String>()
+    };
+    s.push_str(&to_str(parts.pop().unwrap(), false));
+    while let Some(n) = parts.pop() {
+      s.push_str(&to_str(n, true));
+    }
+    s
+  }
+
+  /// 绝对值的大端字节
+  pub fn to_bytes_be(&self)-> Vec<u8> {
+    let mut v:Vec<u8> = self.mag.iter().rev().flat_map(|x|x.to_be_bytes()).collect();
+    let zeros = v.iter().take_while(|b|**b == 0).count();
+    v.drain(..zeros);
+    v
+  }
+
+  /// 从大端字节读取非负数
+  pub fn from_bytes_be(b:&[u8])-> BigInt {
+    let mag = b.rchunks(4).map(|c|c.iter().fold(0u32, |n, x|(n << 8) | *x as u32)).collect();
+    BigInt::new(false, mag)
+  }
+}
+
+impl std::str::FromStr for BigInt {
+  type Err = &'static str;
+  fn from_str(s:&str)-> Result<BigInt, Self::Err> {
+    BigInt::parse(s, 10).ok_or("BigInt中含有无效的数字")
+  }
+}
+
+impl PartialOrd for BigInt {
+  fn partial_cmp(&self, other:&Self)-> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+impl Ord for BigInt {
+  fn cmp(&self, other:&Self)-> Ordering {
+    match (self.neg, other.neg) {
+      (false, true)=> Ordering::Greater,
+      (true, false)=> Ordering::Less,
+      (false, false)=> mag_cmp(&self.mag, &other.mag),
+      (true, true)=> mag_cmp(&other.mag, &self.mag)
+    }
+  }
+}
+
+/// 将整数转为BigInt, 其他类型返回None
+pub fn promote(v:&Litr)-> Option<BigInt> {
+  match v {
+    Litr::BigInt(n)=> Some(n.clone()),
+    Litr::Int(n)=> Some(BigInt::from_i128(*n as i128)),
+    Litr::Uint(n)=> Some(BigInt::from_u128(*n as u128)),
+    _=> None
+  }
+}
+
+fn to_shift(n:&BigInt)-> usize {
+  n.to_usize().unwrap_or_else(||panic!("BigInt的位移量必须是非负的Uint范围内的数, 实际为{:?}", n))
+}
+
+/// 二元运算中有一侧是BigInt时, 另一侧的整数会自动转为BigInt
+///
+/// 和Float运算时会得到Float. 两侧都不是BigInt时返回None
+pub fn binary(op:&[u8], l:&Litr, r:&Litr)-> Option<Litr> {
+  match (l, r) {
+    (Litr::BigInt(_), _)|(_, Litr::BigInt(_))=> (),
+    _=> return None
+  }
+  if let (Litr::Float(_), _)|(_, Litr::Float(_)) = (l, r) {
+    let f = |v:&Litr|match v {
+      Litr::BigInt(n)=> n.to_f64(),
+      Litr::Float(n)=> *n,
+      _=> unreachable!()
+    };
+    let (l, r) = (f(l), f(r));
+    return Some(Litr::Float(match op {
+      b"+"=> l + r,
+      b"-"=> l - r,
+      b"*"=> l * r,
+      b"/"=> l / r,
+      b"%"=> l % r,
+      _=> panic!("{}运算无法应用于BigInt和Float", String::from_utf8_lossy(op))
+    }));
+  }
+  let (l, r) = match (promote(l), promote(r)) {
+    (Some(l), Some(r))=> (l, r),
+    _=> panic!("{}运算无法应用于{:?}和{:?}", String::from_utf8_lossy(op), l, r)
+  };
+  Some(Litr::BigInt(match op {
+    b"+"=> l.add(&r),
+    b"-"=> l.sub(&r),
+    b"*"=> l.mul(&r),
+    b"/"=> l.divrem(&r).0,
+    b"%"=> l.divrem(&r).1,
+    b"&"=> l.bitwise(&r, |x, y|x & y),
+    b"|"=> l.bitwise(&r, |x, y|x | y),
+    b"^"=> l.bitwise(&r, |x, y|x ^ y),
+    b"<<"=> l.shl(to_shift(&r)),
+    b">>"=> l.shr(to_shift(&r)),
+    _=> panic!("{}运算无法应用于BigInt", String::from_utf8_lossy(op))
+  }))
+}
+
+/// BigInt::静态方法
+pub fn statics()-> Vec<(Interned, NativeFn)> {
+  vec![
+    (intern(b"from"), s_from),
+    (intern(b"parse"), s_parse),
+    (intern(b"from_buf"), s_from_buf)
+  ]
+}
+
+/// 将整数, Float或十进制字符串转为BigInt
+fn s_from(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let v = args.get(0).map_or(&Litr::Uninit, |v|&**v);
+  Litr::BigInt(match v {
+    Litr::Float(n)=> BigInt::from_f64(*n),
+    Litr::Str(s)=> BigInt::parse(s.trim(), 10).unwrap_or_else(||panic!("无法将'{}'解析为BigInt", s)),
+    v=> promote(v).unwrap_or_else(||panic!("无法将{:?}转为BigInt", v))
+  })
+}
+
+/// BigInt::parse(str, 进制), 进制默认为10
+fn s_parse(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let s = match args.get(0).map(|v|&**v) {
+    Some(Litr::Str(s))=> s,
+    _=> panic!("BigInt::parse第一个参数必须是Str")
+  };
+  let radix = match args.get(1).map(|v|&**v) {
+    Some(Litr::Int(n))=> *n as u32,
+    Some(Litr::Uint(n))=> *n as u32,
+    _=> 10
+  };
+  BigInt::parse(s.trim(), radix).map_or(Litr::Uninit, Litr::BigInt)
+}
+
+/// BigInt::from_buf(buf, 是否小端), 默认按大端读取非负数
+fn s_from_buf(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let b = match args.get(0).map(|v|&**v) {
+    Some(Litr::Buf(b))=> b,
+    _=> panic!("BigInt::from_buf第一个参数必须是Buf")
+  };
+  if let Some(Litr::Bool(true)) = args.get(1).map(|v|&**v) {
+    let mut b = b.clone();
+    b.reverse();
+    return Litr::BigInt(BigInt::from_bytes_be(&b));
+  }
+  Litr::BigInt(BigInt::from_bytes_be(b))
+}
+
+/// 第i个参数转为BigInt, 不是整数时报错
+fn big_arg(args:&[CalcRef], i:usize, name:&str)-> BigInt {
+  args.get(i).and_then(|v|promote(v))
+    .unwrap_or_else(||panic!("BigInt.{}第{}个参数必须是整数", name, i + 1))
+}
+
+/// BigInt的方法表
+static METHODS:LazyLock<Vec<(Interned, Method<BigInt>)>> = LazyLock::new(||method_table(&[
+  (b"abs", |n, _, _|Litr::BigInt(n.abs())),
+  (b"pow", |n, args, _|Litr::BigInt(n.pow(match args.get(0).map(|v|&**v) {
+    Some(Litr::Uint(e))=> *e,
+    Some(Litr::Int(e)) if *e >= 0=> *e as usize,
+    _=> panic!("BigInt.pow需要一个非负整数")
+  }))),
+  (b"modpow", |n, args, _|Litr::BigInt(n.modpow(&big_arg(&args, 0, "modpow"), &big_arg(&args, 1, "modpow")))),
+  (b"bits", |n, _, _|Litr::Uint(n.bits())),
+  (b"min", |n, args, _|Litr::BigInt(n.clone().min(big_arg(&args, 0, "min")))),
+  (b"max", |n, args, _|Litr::BigInt(n.clone().max(big_arg(&args, 0, "max")))),
+  (b"to_str", |n, args, _|Litr::Str(n.to_str_radix(match args.get(0).map(|v|&**v) {
+    Some(Litr::Int(r))=> *r as u32,
+    Some(Litr::Uint(r))=> *r as u32,
+    _=> 10
+  }).into())),
+  (b"to_hex", |n, _, _|Litr::Str(n.to_str_radix(16).to_uppercase().into())),
+  // 绝对值的字节, 默认大端
+  (b"to_buf", |n, args, _|{
+    let mut b = n.to_bytes_be();
+    if let Some(Litr::Bool(true)) = args.get(0).map(|v|&**v) {
+      b.reverse();
+    }
+    Litr::Buf(b.into())
+  }),
+  (b"to_int", |n, _, _|Litr::Int(n.to_isize().unwrap_or_else(||panic!("{:?}超出了Int的范围", n)))),
+  (b"to_uint", |n, _, _|Litr::Uint(n.to_usize().unwrap_or_else(||panic!("{:?}超出了Uint的范围", n)))),
+  (b"to_float", |n, _, _|Litr::Float(n.to_f64())),
+]));
+pub fn methods()-> &'static [(Interned, Method<BigInt>)] {
+  &METHODS
+}
@@ -3,57 +3,65 @@
 //! 同时包含了一些mem的函数
 
 use super::*;
-
-pub fn method(v:&mut Vec<u8>, scope:Scope, name:Interned, args:Vec<CalcRef>)-> Litr {
-  match name.vec() {
-    // binary edit
-    b"read"=> read(v, args),
-    b"read_float"=> read_float(v, args),
-    b"write"=> write(v, args),
-
-    // edit
-    b"push"=> push(v, args),
-    b"push_front"=> push_front(v, args),
-    b"pop"=> pop(v, args),
-    b"pop_front"=> pop_front(v, args),
-    b"copy_within"=> copy_within(v, args),
-    b"repeat"=> repeat_clone(v, args),
-    b"insert"=> insert(v, args),
-    b"remove"=> remove(v, args),
-    b"splice"=> splice(v, args),
-    b"fill"=> fill(v, args),
-    b"rotate"=> rotate(v, args),
-    b"rev"=> rev(v),
-    b"slice"=> slice_clone(v, args),
-
-    // iter
-    b"for_each"=> for_each(v, args, scope),
-    b"map"=> map_clone(v, args, scope),
-    b"fold"=> fold(v, args, scope),
-    b"dedup"=> dedup(v, args, scope),
-    b"sort"=> sort(v, args, scope),
-    b"filter"=> filter_clone(v, args, scope),
-    b"part"=> part(v, args, scope),
-
-    // find
-    b"replace"=> replace_clone(v, args),
-    b"includes"=> includes(v, args),
-    b"index_of"=> index_of(v, args, scope),
-    b"r_index_of"=> r_index_of(v, args, scope),
-    b"min"=> min(v),
-    b"max"=> max(v),
-    b"all"=> all(v, args, scope),
-
-    // transmute
-    b"as_utf8"=> as_utf8(v),
-    b"as_utf16"=> as_utf16(v),
-    b"to_list"=> Litr::List(v.iter().map(|n|Litr::Uint(*n as usize)).collect()),
-    b"join"=> join(v, args),
-
-    b"last"=> last(v),
-    b"expand"=> expand(v, args),
-    _=> panic!("Buf没有{}方法",name)
-  }
+use std::sync::LazyLock;
+
+/// Buf的方法表
+static METHODS:LazyLock<Vec<(Interned, Method<Shared<Vec<u8>>>)>> = LazyLock::new(||method_table(&[
+  // binary edit
+  (b"read", |v, args, _|read(v, args)),
+  (b"read_float", |v, args, _|read_float(v, args)),
+  (b"write", |v, args, _|write(v, args)),
+
+  // edit
+  (b"push", |v, args, _|push(v, args)),
+  (b"push_front", |v, args, _|push_front(v, args)),
+  (b"pop", |v, args, _|pop(v, args)),
+  (b"pop_front", |v, args, _|pop_front(v, args)),
+  (b"copy_within", |v, args, _|copy_within(v, args)),
+  (b"repeat", |v, args, _|repeat_clone(v, args)),
+  (b"insert", |v, args, _|insert(v, args)),
+  (b"remove", |v, args, _|remove(v, args)),
+  (b"splice", |v, args, _|splice(v, args)),
+  (b"fill", |v, args, _|fill(v, args)),
+  (b"rotate", |v, args, _|rotate(v, args)),
+  (b"rev", |v, _, _|rev(v)),
+  (b"slice", |v, args, _|slice_clone(v, args)),
+
+  // iter
+  (b"for_each", |v, args, scope|for_each(v, args, scope)),
+  (b"map", |v, args, scope|map_clone(v, args, scope)),
+  (b"fold", |v, args, scope|fold(v, args, scope)),
+  (b"dedup", |v, args, scope|dedup(v, args, scope)),
+  (b"sort", |v, args, scope|sort(v, args, scope)),
+  (b"filter", |v, args, scope|filter_clone(v, args, scope)),
+  (b"part", |v, args, scope|part(v, args, scope)),
+
+  // find
+  (b"replace", |v, args, _|replace_clone(v, args)),
+  (b"includes", |v, args, _|includes(v, args)),
+  (b"index_of", |v, args, scope|index_of(v, args, scope)),
+  (b"r_index_of", |v, args, scope|r_index_of(v, args, scope)),
+  (b"min", |v, _, _|min(v)),
+  (b"max", |v, _, _|max(v)),
+  (b"all", |v, args, scope|all(v, args, scope)),
+
+  // transmute
+  (b"as_utf8", |v, _, _|as_utf8(v)),
+  (b"as_utf16", |v, _, _|as_utf16(v)),
+  (b"to_list", |v, _, _|Litr::List(v.iter().map(|n|Litr::Uint(*n as usize)).collect())),
+  (b"join", |v, args, _|join(v, args)),
+
+  // codec
+  (b"to_base64", |v, args, _|codec::to_base64(v, args)),
+  (b"to_hex", |v, args, _|codec::to_hex(v, args)),
+  (b"to_percent", |v, args, _|codec::to_percent(v, args)),
+
+  (b"last", |v, _, _|last(v)),
+  (b"expand", |v, args, _|expand(v, args)),
+]));
+
+pub fn methods()-> &'static [(Interned, Method<Shared<Vec<u8>>>)] {
+  &METHODS
 }
 
 const fn to_u8(v:&Litr)-> u8 {
@@ -90,7 +98,7 @@ fn push(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
 /// 像是js的unshift
 fn push_front(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
   match &**args.get(0).expect("'push_front'方法需要一个数字,列表或数组作为参数") {
-    Litr::Buf(right)=> *v = [&**right, v].concat(),
+    Litr::Buf(right)=> *v = [&right[..], v].concat(),
     Litr::List(right)=> *v = [
       &*right.iter().map(|litr|to_u8(litr)).collect::<Box<[u8]>>(), v].concat(),
     n=> v.insert(0, to_u8(n))
@@ -143,7 +151,7 @@ fn sort(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
 }
 
 /// 循环调用
-fn for_each(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
+fn for_each(v:&Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
   let f = match &**args.get(0).expect("buf.foreach需要一个函数作为参数") {
     Litr::Func(f)=> f,
     _=> panic!("buf.foreach第一个参数只能传函数")
@@ -155,7 +163,7 @@ fn for_each(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
 }
 
 /// 映射重构新Buf
-fn map_clone(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
+fn map_clone(v:&Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
   let f = match &**args.get(0).expect("buf.map需要一个函数作为参数") {
     Litr::Func(f)=> f,
     _=> panic!("buf.map第一个参数只能传函数")
@@ -181,7 +189,7 @@ fn pop(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
       panic!("分界线索引{at}大于数组长度{}", v.len());
     }
 
-    Litr::Buf(v.split_off(v.len() - at))
+    Litr::Buf(v.split_off(v.len() - at).into())
   }else {
     match v.pop() {
       Some(n)=> Litr::Uint(n as usize),
@@ -204,7 +212,7 @@ fn pop_front(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
 
     let mut part = v.split_off(at);
     std::mem::swap(v, &mut part);
-    Litr::Buf(part)
+    Litr::Buf(part.into())
   }else {
     if v.len()==0 {return Litr::Uninit;}
     Litr::Uint(v.remove(0) as usize)
@@ -218,18 +226,18 @@ fn rev(v:&mut Vec<u8>)-> Litr {
 }
 
 /// filter的复制版本
-fn filter_clone(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
+fn filter_clone(v:&Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
   let f = match &**args.get(0).expect("buf.filter需要一个函数作为参数") {
     Litr::Func(f)=> f,
     _=> panic!("buf.map第一个参数只能传函数")
   };
 
-  Litr::Buf(v.iter().filter_map(|&a|match scope.call(
+  Litr::Buf((v.iter().filter_map(|&a|match scope.call(
     vec![CalcRef::Own(Litr::Uint(a as usize))], f
   ) {
     Litr::Bool(b)=> b.then(||a),
     _=> None
-  }).collect::<Vec<u8>>())
+  }).collect::<Vec<u8>>()).into())
 }
 
 /// 在数组范围内进行就地复制
@@ -299,14 +307,14 @@ fn write(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
 }
 
 /// 获取最后一个数字
-fn last(v:&mut Vec<u8>)-> Litr {
+fn last(v:&Vec<u8>)-> Litr {
   v.last().map_or(Litr::Uninit, |v|Litr::Uint(*v as usize))
 }
 
 /// repeat的复制版
-fn repeat_clone(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
+fn repeat_clone(v:&Vec<u8>, args:Vec<CalcRef>)-> Litr {
   let n = to_usize(args.get(0).expect("buf.repeat需要传入整数作为重复次数"));
-  Litr::Buf(v.repeat(n))
+  Litr::Buf(v.repeat(n).into())
 }
 
 /// 插入单数字或数组
@@ -408,8 +416,8 @@ fn rotate(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
 }
 
 /// 将十六进制数以字符的格式渲染, 传入一个分隔符
-fn join(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
-  if v.len()==0 {return Litr::Str(String::new());}
+fn join(v:&Vec<u8>, args:Vec<CalcRef>)-> Litr {
+  if v.len()==0 {return Litr::Str(String::new().into());}
 
   let sep = if let Some(s) = args.get(0) {
     if let Litr::Str(s) = &**s {s}else {
@@ -424,11 +432,11 @@ fn join(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
   for n in &v[1..] {
     s.write_fmt(format_args!("{sep}{n:02X}")).expect(WRITE_ERR);
   }
-  Litr::Str(s)
+  Litr::Str(s.into())
 }
 
 /// 嘎嘎复制和计算, 将整个数组折叠成一个值
-fn fold(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
+fn fold(v:&Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
   let init = args.get(0).expect("buf.fold需要一个初始值").clone().own();
   let f = match &**args.get(1).expect("buf.fold需要第二个参数的函数来处理数据") {
     Litr::Func(f)=> f,
@@ -440,7 +448,7 @@ fn fold(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
 }
 
 /// slice的复制版本
-fn slice_clone(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
+fn slice_clone(v:&Vec<u8>, args:Vec<CalcRef>)-> Litr {
   let len = v.len();
   let start = args.get(0).map_or(0, |n|to_usize(n));
   let end = args.get(0).map_or(len, |n|to_usize(n));
@@ -448,13 +456,13 @@ fn slice_clone(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
   assert!(start<=end, "切片起始索引{start}不可大于结束索引{end}");
   assert!(end<=len, "切片结束索引{end}不可大于数组长度{len}");
 
-  Litr::Buf(v[start..end].to_vec())
+  Litr::Buf(v[start..end].to_vec().into())
 }
 
 /// 是否存在一个数
-fn includes(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
+fn includes(v:&Vec<u8>, args:Vec<CalcRef>)-> Litr {
   Litr::Bool(match &**args.get(0).expect("buf.includes需要知道你要找啥") {
-    Litr::Str(s)=> to_str(v).contains(s),
+    Litr::Str(s)=> to_str(v).contains(s.as_str()),
     Litr::Buf(s)=> to_str(v).contains(to_str(s)),
     n=> {
       let find = to_u8(n);
@@ -467,7 +475,7 @@ fn includes(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
 }
 
 /// 找数组中第一个所指数字, 也可以传函数来自定义判断
-fn index_of(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
+fn index_of(v:&Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
   let res = match &**args.get(0).expect("buf.index_of需要传入一个数字或判断函数") {
     Litr::Func(f)=> {
       v.iter().position(|n|
@@ -488,7 +496,7 @@ fn index_of(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
 }
 
 /// index_of的反向版本
-fn r_index_of(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
+fn r_index_of(v:&Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
   let res = match &**args.get(0).expect("buf.r_index_of需要知道你要找啥") {
     Litr::Func(f)=> {
       v.iter().rev().position(|n|
@@ -509,7 +517,7 @@ fn r_index_of(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
 }
 
 /// 测试所有元素是否都能让传入函数返回true
-fn all(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
+fn all(v:&Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
   let f = match &**args.get(0).expect("buf.all需要传入一个函数来判断元素是否所需") {
     Litr::Func(f)=> f,
     _=> panic!("buf.all第一个参数必须是函数")
@@ -523,12 +531,12 @@ fn all(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
 }
 
 /// 找最小值
-fn min(v:&mut Vec<u8>)-> Litr {
+fn min(v:&Vec<u8>)-> Litr {
   Litr::Uint(v.iter().min().copied().unwrap_or(0) as _)
 }
 
 /// 找最大值
-fn max(v:&mut Vec<u8>)-> Litr {
+fn max(v:&Vec<u8>)-> Litr {
   Litr::Uint(v.iter().max().copied().unwrap_or(0) as _)
 }
 
@@ -544,11 +552,11 @@ fn part(v:&mut Vec<u8>, args:Vec<CalcRef>, scope:Scope)-> Litr {
       _=> false
     });
   *v = this;
-  Litr::Buf(ret)
+  Litr::Buf(ret.into())
 }
 
 /// 在指定偏移读取一个Uint, 第三个参数取决于机器的大小端, true不一定指大端序
-fn read(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
+fn read(v:&Vec<u8>, args:Vec<CalcRef>)-> Litr {
   let index = args.get(0).map_or(0, |n|to_usize(n));
   let sz = args.get(1).map_or(8, |n|to_usize(n));
   let big_endian = args.get(2).map_or(false, |n|
@@ -584,7 +592,7 @@ fn read(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
 }
 
 /// 在指定偏移读取一个Float, 第二个参数取决于机器的大小端, true不一定指大端序
-fn read_float(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr { 
+fn read_float(v:&Vec<u8>, args:Vec<CalcRef>)-> Litr { 
   let index = args.get(0).map_or(0, |n|to_usize(n));
   if index + 8 > v.len() {
     return Litr::Float(0.0);
@@ -604,41 +612,41 @@ fn read_float(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
 }
 
 /// 以utf8解码 错误编码会转成U+FFFD不会报错
-fn as_utf8(v:&mut Vec<u8>)-> Litr {
-  Litr::Str(String::from_utf8_lossy(v).into_owned())
+fn as_utf8(v:&Vec<u8>)-> Litr {
+  Litr::Str(String::from_utf8_lossy(v).into_owned().into())
 }
 
 /// 以utf16解码 错误编码会转成U+FFFD不会报错
-fn as_utf16(v:&mut Vec<u8>)-> Litr {
+fn as_utf16(v:&Vec<u8>)-> Litr {
   let s = unsafe {
     let ptr = v.as_ptr() as *const u16;
     // 无符号数除2会自动向下取整
     let len = v.len() / 2;
     std::slice::from_raw_parts(ptr, len)
   };
-  Litr::Str(String::from_utf16_lossy(s))
+  Litr::Str(String::from_utf16_lossy(s).into())
 }
 
 /// replace
-fn replace_clone(v:&mut Vec<u8>, args:Vec<CalcRef>)-> Litr {
+fn replace_clone(v:&Vec<u8>, args:Vec<CalcRef>)-> Litr {
   let s = to_str(v);
 
   let from = match &** args.get(0).expect("buf.replace需要一个搜索Buf") {
-    Litr::Str(s)=> s,
+    Litr::Str(s)=> s.as_str(),
     Litr::Buf(s)=> to_str(s),
     _=> panic!("buf.replace第一个参数必须是Buf或Str")
   };
   let to = args.get(1).map_or("", |n| match &**n {
-    Litr::Str(s)=> s,
+    Litr::Str(s)=> s.as_str(),
     Litr::Buf(s)=> to_str(s),
     _=> panic!("buf.replace第二个参数必须是Buf或Str")
   });
 
-  Litr::Buf(if let Some(n) = args.get(2) {
+  Litr::Buf((if let Some(n) = args.get(2) {
     s.replacen(from, to, to_usize(n))
   }else {
     s.replace(from, to)
-  }.into_bytes())
+  }.into_bytes()).into())
 }
 
 // - statics -
@@ -649,7 +657,10 @@ pub fn statics()-> Vec<(Interned, NativeFn)> {
     (intern(b"from_list"), s_from_list),
     (intern(b"from_iter"), s_from_iter),
     (intern(b"from_ptr"), s_from_ptr),
-    (intern(b"concat"), s_concat)
+    (intern(b"concat"), s_concat),
+    (intern(b"from_base64"), codec::s_from_base64),
+    (intern(b"from_hex"), codec::s_from_hex),
+    (intern(b"from_percent"), codec::s_buf_from_percent)
   ]
 }
 
@@ -662,10 +673,10 @@ fn s_new(args:Vec<CalcRef>, _cx:Scope)-> Litr {
     unsafe {
       let layout = std::alloc::Layout::from_size_align_unchecked(n, 1);
       let alc = std::alloc::alloc_zeroed(layout);
-      Litr::Buf(Vec::from_raw_parts(alc, n, n))
+      Litr::Buf(Vec::from_raw_parts(alc, n, n).into())
     }
   }else {
-    Litr::Buf(Vec::new())
+    Litr::Buf(Vec::new().into())
   }
 }
 
@@ -678,10 +689,10 @@ fn s_new_uninit(args:Vec<CalcRef>, _cx:Scope)-> Litr {
     unsafe {
       let layout = std::alloc::Layout::from_size_align_unchecked(n, 1);
       let alc = std::alloc::alloc(layout);
-      Litr::Buf(Vec::from_raw_parts(alc, n, n))
+      Litr::Buf(Vec::from_raw_parts(alc, n, n).into())
     }
   }else {
-    Litr::Buf(Vec::new())
+    Litr::Buf(Vec::new().into())
   }
 }
 
@@ -714,7 +725,7 @@ fn s_from_ptr(args:Vec<CalcRef>, _cx:Scope)-> Litr {
   };
   let len = to_usize(&*args[1]);
   unsafe {
-    Litr::Buf(std::slice::from_raw_parts(from as *const u8, len).to_vec())
+    Litr::Buf(std::slice::from_raw_parts(from as *const u8, len).to_vec().into())
   }
 }
 
@@ -724,7 +735,7 @@ fn s_concat(args:Vec<CalcRef>, _cx:Scope)-> Litr {
   let mut left = match &**args.get(0).unwrap() {
     Litr::List(v)=> v.iter().map(|n|to_u8(n)).collect(),
     Litr::Buf(v)=> v.clone(),
-    n=> vec![to_u8(n)]
+    n=> vec![to_u8(n)].into()
   };
   match &**args.get(1).unwrap() {
     Litr::List(v)=> left.extend(v.iter().map(|n|to_u8(n))),
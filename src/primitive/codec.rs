@@ -0,0 +1,240 @@
+//! Base64, 十六进制和百分号编码
+//!
+//! 编码为Buf和Str的`to_base64`, `to_hex`, `to_percent`方法,
+//! 解码为`Buf::from_base64`等静态函数, 表单格式为`Obj::to_query`和`Obj::from_query`
+
+use super::*;
+use super::objmap::ObjMap;
+
+const STD:&[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const URL:&[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+pub fn base64(b:&[u8], url:bool, pad:bool)-> String {
+  let table = if url {URL}else {STD};
+  let mut s = String::with_capacity((b.len() + 2) / 3 * 4);
+  for c in b.chunks(3) {
+    let n = (c[0] as u32) << 16 | (*c.get(1).unwrap_or(&0) as u32) << 8 | *c.get(2).unwrap_or(&0) as u32;
+    for i in 0..c.len() + 1 {
+      s.push(table[(n >> (18 - 6 * i) & 63) as usize] as char);
+    }
+    if pad {
+      for _ in c.len()..3 {
+        s.push('=');
+      }
+    }
+  }
+  s
+}
+
+/// 同时接受标准和URL两种字母表, 填充可有可无, 忽略空白
+pub fn from_base64(s:&[u8])-> Vec<u8> {
+  let mut out = Vec::with_capacity(s.len() / 4 * 3);
+  let (mut acc, mut n) = (0u32, 0);
+  let mut end = false;
+  for &c in s {
+    let v = match c {
+      b'A'..=b'Z'=> c - b'A',
+      b'a'..=b'z'=> c - b'a' + 26,
+      b'0'..=b'9'=> c - b'0' + 52,
+      b'+'|b'-'=> 62,
+      b'/'|b'_'=> 63,
+      b'='=> {
+        end = true;
+        continue;
+      }
+      b' '|b'\t'|b'\r'|b'\n'=> continue,
+      _=> panic!("Base64解码失败: 无效的字符'{}'", c as char)
+    };
+    assert!(!end, "Base64解码失败: 填充符后不能再有数据");
+    acc = acc << 6 | v as u32;
+    n += 1;
+    if n == 4 {
+      out.extend_from_slice(&acc.to_be_bytes()[1..]);
+      acc = 0;
+      n = 0;
+    }
+  }
+  match n {
+    0=> (),
+    2=> out.push((acc >> 4) as u8),
+    3=> out.extend_from_slice(&((acc >> 2) as u16).to_be_bytes()),
+    _=> panic!("Base64解码失败: 长度不正确")
+  }
+  out
+}
+
+pub fn hex(b:&[u8], upper:bool)-> String {
+  let table = if upper {b"0123456789ABCDEF"}else {b"0123456789abcdef"};
+  let mut s = String::with_capacity(b.len() * 2);
+  for n in b {
+    s.push(table[(n >> 4) as usize] as char);
+    s.push(table[(n & 15) as usize] as char);
+  }
+  s
+}
+
+fn hex_digit(c:u8)-> Option<u8> {
+  match c {
+    b'0'..=b'9'=> Some(c - b'0'),
+    b'a'..=b'f'=> Some(c - b'a' + 10),
+    b'A'..=b'F'=> Some(c - b'A' + 10),
+    _=> None
+  }
+}
+
+/// 忽略空白, 大小写均可
+pub fn from_hex(s:&[u8])-> Vec<u8> {
+  let digits:Vec<u8> = s.iter().filter(|c|!c.is_ascii_whitespace()).map(|c|
+    hex_digit(*c).unwrap_or_else(||panic!("十六进制解码失败: 无效的字符'{}'", *c as char))
+  ).collect();
+  assert!(digits.len() % 2 == 0, "十六进制解码失败: 长度必须为偶数");
+  digits.chunks(2).map(|d|d[0] << 4 | d[1]).collect()
+}
+
+/// 只保留RFC 3986中的非保留字符, 表单格式下空格写作'+'
+pub fn percent(b:&[u8], form:bool)-> String {
+  let mut s = String::with_capacity(b.len());
+  for &c in b {
+    match c {
+      b'A'..=b'Z'|b'a'..=b'z'|b'0'..=b'9'|b'-'|b'.'|b'_'|b'~'=> s.push(c as char),
+      b' ' if form=> s.push('+'),
+      _=> {
+        s.push('%');
+        s.push_str(&hex(&[c], true));
+      }
+    }
+  }
+  s
+}
+
+/// 不完整的%序列保持原样, 表单格式下'+'解码为空格
+pub fn from_percent(s:&[u8], form:bool)-> Vec<u8> {
+  let mut out = Vec::with_capacity(s.len());
+  let mut i = 0;
+  while i < s.len() {
+    let c = s[i];
+    if c == b'%' && i + 2 < s.len() {
+      if let (Some(h), Some(l)) = (hex_digit(s[i + 1]), hex_digit(s[i + 2])) {
+        out.push(h << 4 | l);
+        i += 3;
+        continue;
+      }
+    }
+    out.push(if form && c == b'+' {b' '}else {c});
+    i += 1;
+  }
+  out
+}
+
+fn utf8(b:Vec<u8>, f:&str)-> String {
+  String::from_utf8(b).unwrap_or_else(|_|panic!("{}: 解码结果不是合法的utf8", f))
+}
+
+/// 将Obj写成a=1&b=2, List会展开成重复的键, uninit会被跳过
+pub fn to_query(o:&ObjMap)-> String {
+  let mut pairs = Vec::new();
+  let mut push = |k:&Interned, v:&Litr|{
+    let v = match v {
+      Litr::Uninit=> return,
+      Litr::Str(s)=> percent(s.as_bytes(), true),
+      Litr::Buf(b)=> percent(b, true),
+      v=> percent(v.str().as_bytes(), true)
+    };
+    pairs.push(format!("{}={}", percent(k.vec(), true), v));
+  };
+  for (k, v) in o {
+    match v {
+      Litr::List(l)=> for v in l {
+        push(k, v);
+      }
+      v=> push(k, v)
+    }
+  }
+  pairs.join("&")
+}
+
+/// 值均为Str, 重复的键会合并为List
+pub fn from_query(s:&str)-> ObjMap {
+  let mut o = ObjMap::new();
+  for pair in s.trim_start_matches('?').split('&').filter(|p|!p.is_empty()) {
+    let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+    let k = intern(&from_percent(k.as_bytes(), true));
+    let v = Litr::Str(utf8(from_percent(v.as_bytes(), true), "Obj::from_query").into());
+    match o.get_mut(&k) {
+      Some(Litr::List(l))=> l.push(v),
+      Some(old)=> {
+        let first = std::mem::take(old);
+        *old = Litr::List(vec![first, v].into());
+      }
+      None=> {
+        o.insert(k, v);
+      }
+    }
+  }
+  o
+}
+
+// - Key接口 -
+
+fn flag(v:Option<&CalcRef>, default:bool)-> bool {
+  match v.map(|v|&**v) {
+    Some(Litr::Bool(b))=> *b,
+    None|Some(Litr::Uninit)=> default,
+    _=> panic!("编码选项必须是Bool")
+  }
+}
+
+fn input<'a>(v:Option<&'a CalcRef>, f:&str)-> &'a [u8] {
+  match v.map(|v|&**v) {
+    Some(Litr::Str(s))=> s.as_bytes(),
+    Some(Litr::Buf(b))=> b,
+    _=> panic!("{}第一个参数必须是Str或Buf", f)
+  }
+}
+
+/// to_base64(url, pad), url默认为false, pad默认与url相反
+pub fn to_base64(b:&[u8], args:Vec<CalcRef>)-> Litr {
+  let url = flag(args.get(0), false);
+  Litr::Str(base64(b, url, flag(args.get(1), !url)).into())
+}
+
+/// to_hex(upper), 默认小写
+pub fn to_hex(b:&[u8], args:Vec<CalcRef>)-> Litr {
+  Litr::Str(hex(b, flag(args.get(0), false)).into())
+}
+
+/// to_percent(form), form为true时按表单格式把空格写作'+'
+pub fn to_percent(b:&[u8], args:Vec<CalcRef>)-> Litr {
+  Litr::Str(percent(b, flag(args.get(0), false)).into())
+}
+
+pub fn s_from_base64(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  Litr::Buf(from_base64(input(args.get(0), "Buf::from_base64")).into())
+}
+
+pub fn s_from_hex(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  Litr::Buf(from_hex(input(args.get(0), "Buf::from_hex")).into())
+}
+
+pub fn s_buf_from_percent(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  Litr::Buf(from_percent(input(args.get(0), "Buf::from_percent"), flag(args.get(1), false)).into())
+}
+
+pub fn s_str_from_percent(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let b = from_percent(input(args.get(0), "Str::from_percent"), flag(args.get(1), false));
+  Litr::Str(utf8(b, "Str::from_percent").into())
+}
+
+pub fn s_to_query(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  match args.get(0).map(|v|&**v) {
+    Some(Litr::Obj(o))=> Litr::Str(to_query(o).into()),
+    _=> panic!("Obj::to_query第一个参数必须是Obj")
+  }
+}
+
+pub fn s_from_query(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  match args.get(0).map(|v|&**v) {
+    Some(Litr::Str(s))=> Litr::Obj(from_query(s).into()),
+    _=> panic!("Obj::from_query第一个参数必须是Str")
+  }
+}
@@ -0,0 +1,322 @@
+//! 可以用任意值作键的Set和Map
+//!
+//! 键可以是Int, Uint, BigInt, Bool, Str, Buf和由它们组成的List, 比较时类型也要相同.
+//! 和Obj一样保持插入顺序, `for`遍历Set得到元素, 遍历Map得到[键, 值]
+
+use super::*;
+use super::objmap::OrdMap;
+use super::iter::LitrIterator;
+use std::hash::{Hash, Hasher};
+
+pub static mut SET_CLASS: *mut NativeClassDef = std::ptr::null_mut();
+pub static mut MAP_CLASS: *mut NativeClassDef = std::ptr::null_mut();
+
+/// 可作为Set和Map键的值
+#[derive(Clone)]
+pub struct Key(pub Litr);
+
+impl Key {
+  pub fn new(v:Litr)-> Key {
+    fn check(v:&Litr) {
+      match v {
+        Litr::Int(_)|Litr::Uint(_)|Litr::BigInt(_)|Litr::Bool(_)|Litr::Str(_)|Litr::Buf(_)=> (),
+        Litr::List(l)=> l.iter().for_each(check),
+        _=> panic!("{}不能作为Set或Map的键", v.str())
+      }
+    }
+    check(&v);
+    Key(v)
+  }
+}
+
+fn key_eq(a:&Litr, b:&Litr)-> bool {
+  match (a, b) {
+    (Litr::Int(a), Litr::Int(b))=> a == b,
+    (Litr::Uint(a), Litr::Uint(b))=> a == b,
+    (Litr::BigInt(a), Litr::BigInt(b))=> a == b,
+    (Litr::Bool(a), Litr::Bool(b))=> a == b,
+    (Litr::Str(a), Litr::Str(b))=> a == b,
+    (Litr::Buf(a), Litr::Buf(b))=> a == b,
+    (Litr::List(a), Litr::List(b))=> a.len() == b.len() && a.iter().zip(b).all(|(a, b)|key_eq(a, b)),
+    _=> false
+  }
+}
+
+impl PartialEq for Key {
+  fn eq(&self, other:&Self)-> bool {
+    key_eq(&self.0, &other.0)
+  }
+}
+impl Eq for Key {}
+
+impl Hash for Key {
+  fn hash<H:Hasher>(&self, h:&mut H) {
+    fn hash<H:Hasher>(v:&Litr, h:&mut H) {
+      std::mem::discriminant(v).hash(h);
+      match v {
+        Litr::Int(n)=> n.hash(h),
+        Litr::Uint(n)=> n.hash(h),
+        Litr::BigInt(n)=> n.hash(h),
+        Litr::Bool(b)=> b.hash(h),
+        Litr::Str(s)=> s.hash(h),
+        Litr::Buf(b)=> b.hash(h),
+        Litr::List(l)=> {
+          l.len().hash(h);
+          l.iter().for_each(|v|hash(v, h));
+        }
+        _=> ()
+      }
+    }
+    hash(&self.0, h)
+  }
+}
+
+type Set = OrdMap<Key, ()>;
+type Map = OrdMap<Key, Litr>;
+
+fn set(v:usize)-> &'static mut Set {
+  unsafe {&mut *(v as *mut Set)}
+}
+fn map(v:usize)-> &'static mut Map {
+  unsafe {&mut *(v as *mut Map)}
+}
+
+fn new_set(s:Set)-> Litr {
+  Litr::Ninst(NativeInstance {cls: unsafe{SET_CLASS}, v: Box::into_raw(Box::new(s)) as usize, w: 0})
+}
+fn new_map(m:Map)-> Litr {
+  Litr::Ninst(NativeInstance {cls: unsafe{MAP_CLASS}, v: Box::into_raw(Box::new(m)) as usize, w: 0})
+}
+
+fn is_set(inst:&NativeInstance)-> bool {
+  inst.cls == unsafe{SET_CLASS}
+}
+fn is_map(inst:&NativeInstance)-> bool {
+  inst.cls == unsafe{MAP_CLASS}
+}
+
+/// 两个Set或Map按内容比较, 不是Set和Map时返回None
+pub fn eq(l:&NativeInstance, r:&NativeInstance)-> Option<bool> {
+  if l.cls != r.cls {
+    return None;
+  }
+  if is_set(l) {
+    Some(set(l.v) == set(r.v))
+  }else if is_map(l) {
+    Some(map(l.v) == map(r.v))
+  }else {None}
+}
+
+/// for遍历Set和Map, 按槽位下标前进
+/// 
+/// 遍历期间表不会压缩, 因此遍历中增删元素也不会跳过或重复已有的元素
+struct CollectionIter<'a> {
+  inst: &'a NativeInstance,
+  i: usize
+}
+impl<'a> CollectionIter<'a> {
+  fn new(inst:&'a NativeInstance)-> Self {
+    if is_set(inst) {set(inst.v).freeze()}else {map(inst.v).freeze()}
+    CollectionIter {inst, i: 0}
+  }
+}
+impl Drop for CollectionIter<'_> {
+  fn drop(&mut self) {
+    if is_set(self.inst) {set(self.inst.v).unfreeze()}else {map(self.inst.v).unfreeze()}
+  }
+}
+impl Iterator for CollectionIter<'_> {
+  type Item = Litr;
+  fn next(&mut self)-> Option<Litr> {
+    if is_set(self.inst) {
+      let (i, (k, _)) = set(self.inst.v).slot_from(self.i)?;
+      self.i = i + 1;
+      Some(k.0.clone())
+    }else {
+      let (i, (k, v)) = map(self.inst.v).slot_from(self.i)?;
+      self.i = i + 1;
+      Some(Litr::List(vec![k.0.clone(), v.clone()].into()))
+    }
+  }
+}
+
+pub fn is_collection(inst:&NativeInstance)-> bool {
+  is_set(inst) || is_map(inst)
+}
+
+/// Set和Map的迭代器
+pub fn iter(inst:&NativeInstance)-> Box<dyn Iterator<Item = Litr> + '_> {
+  Box::new(CollectionIter::new(inst))
+}
+
+fn key(v:Option<&CalcRef>, f:&str)-> Key {
+  Key::new(v.map(|v|(**v).clone()).unwrap_or_else(||panic!("{}需要传入键", f)))
+}
+
+/// 将Set或任意可迭代的值转为Set
+fn to_set(v:Option<&mut CalcRef>, f:&str)-> Set {
+  let v = &mut **v.unwrap_or_else(||panic!("{}需要传入Set或可迭代的值", f));
+  if let Litr::Ninst(inst) = v {
+    if is_set(inst) {
+      return set(inst.v).clone();
+    }
+  }
+  LitrIterator::new(v).map(|v|(Key::new(v), ())).collect()
+}
+
+/// 将Map, Obj或由[键, 值]组成的可迭代值转为Map
+fn to_map(v:Option<&mut CalcRef>, f:&str)-> Map {
+  let v = &mut **v.unwrap_or_else(||panic!("{}需要传入Map, Obj或[键, 值]的列表", f));
+  match v {
+    Litr::Ninst(inst) if is_map(inst)=> map(inst.v).clone(),
+    Litr::Obj(o)=> o.iter().map(|(k, v)|(Key(Litr::Str(k.str().into())), v.clone())).collect(),
+    v=> LitrIterator::new(v).map(|e|match e {
+      Litr::List(mut e) if e.len() == 2=> {
+        let v = e.pop().unwrap();
+        (Key::new(e.pop().unwrap()), v)
+      }
+      _=> panic!("{}的元素必须是[键, 值]", f)
+    }).collect()
+  }
+}
+
+fn func(v:Option<&CalcRef>, f:&str)-> Function {
+  match v.map(|v|&**v) {
+    Some(Litr::Func(func))=> func.clone(),
+    _=> panic!("{}需要传入一个函数", f)
+  }
+}
+
+pub fn init_set()-> (Interned, *mut NativeClassDef) {
+  unsafe {
+    let s = new_static_class(b"Set", vec![
+      (intern(b"new"), |_, _|new_set(Set::new())),
+      (intern(b"from"), |mut a, _|new_set(to_set(a.get_mut(0), "Set::from")))
+    ]);
+    SET_CLASS = s.1;
+    let c = &mut *SET_CLASS;
+    c.methods.push((intern(b"add"), |inst, args, _|
+      Litr::Bool(set(inst.v).insert(key(args.get(0), "set.add"), ()).is_none())));
+    c.methods.push((intern(b"remove"), |inst, args, _|
+      Litr::Bool(set(inst.v).remove(&key(args.get(0), "set.remove")).is_some())));
+    c.methods.push((intern(b"has"), |inst, args, _|
+      Litr::Bool(set(inst.v).contains_key(&key(args.get(0), "set.has")))));
+    c.methods.push((intern(b"clear"), |inst, _, _|{
+      set(inst.v).clear();
+      Litr::Uninit
+    }));
+    c.methods.push((intern(b"union"), |inst, mut args, _|{
+      let mut s = set(inst.v).clone();
+      s.extend(to_set(args.get_mut(0), "set.union"));
+      new_set(s)
+    }));
+    c.methods.push((intern(b"intersection"), |inst, mut args, _|{
+      let other = to_set(args.get_mut(0), "set.intersection");
+      new_set(set(inst.v).iter().filter(|(k, _)|other.contains_key(k)).cloned().collect())
+    }));
+    c.methods.push((intern(b"difference"), |inst, mut args, _|{
+      let other = to_set(args.get_mut(0), "set.difference");
+      new_set(set(inst.v).iter().filter(|(k, _)|!other.contains_key(k)).cloned().collect())
+    }));
+    c.methods.push((intern(b"is_subset"), |inst, mut args, _|{
+      let other = to_set(args.get_mut(0), "set.is_subset");
+      Litr::Bool(set(inst.v).keys().all(|k|other.contains_key(k)))
+    }));
+    c.methods.push((intern(b"for_each"), |inst, args, cx|{
+      let f = func(args.get(0), "set.for_each");
+      for v in (CollectionIter {inst, i: 0}) {
+        cx.call(vec![CalcRef::Own(v)], &f);
+      }
+      Litr::Uninit
+    }));
+    c.methods.push((intern(b"to_list"), |inst, _, _|
+      Litr::List(set(inst.v).keys().map(|k|k.0.clone()).collect())));
+    c.getter = |inst, get|match get.vec() {
+      b"len"=> Litr::Uint(set(inst.v).len()),
+      _=> Litr::Uninit
+    };
+    c.to_str = |inst|format!("Set {{ {} }}",
+      set(inst.v).keys().map(|k|k.0.str()).collect::<Vec<_>>().join(", "));
+    c.onclone = |inst|NativeInstance {
+      cls: inst.cls, v: Box::into_raw(Box::new(set(inst.v).clone())) as usize, w: 0
+    };
+    c.ondrop = |inst|drop(Box::from_raw(inst.v as *mut Set));
+    s
+  }
+}
+
+pub fn init_map()-> (Interned, *mut NativeClassDef) {
+  unsafe {
+    let m = new_static_class(b"Map", vec![
+      (intern(b"new"), |_, _|new_map(Map::new())),
+      (intern(b"from"), |mut a, _|new_map(to_map(a.get_mut(0), "Map::from")))
+    ]);
+    MAP_CLASS = m.1;
+    let c = &mut *MAP_CLASS;
+    c.methods.push((intern(b"set"), |inst, args, _|{
+      let v = args.get(1).map_or(Litr::Uninit, |v|(**v).clone());
+      map(inst.v).insert(key(args.get(0), "map.set"), v).unwrap_or(Litr::Uninit)
+    }));
+    c.methods.push((intern(b"get"), |inst, args, _|
+      map(inst.v).get(&key(args.get(0), "map.get")).cloned()
+        .unwrap_or_else(||args.get(1).map_or(Litr::Uninit, |v|(**v).clone()))));
+    c.methods.push((intern(b"remove"), |inst, args, _|
+      map(inst.v).remove(&key(args.get(0), "map.remove")).unwrap_or(Litr::Uninit)));
+    c.methods.push((intern(b"has"), |inst, args, _|
+      Litr::Bool(map(inst.v).contains_key(&key(args.get(0), "map.has")))));
+    c.methods.push((intern(b"clear"), |inst, _, _|{
+      map(inst.v).clear();
+      Litr::Uninit
+    }));
+    c.methods.push((intern(b"keys"), |inst, _, _|
+      Litr::List(map(inst.v).keys().map(|k|k.0.clone()).collect())));
+    c.methods.push((intern(b"values"), |inst, _, _|
+      Litr::List(map(inst.v).values().cloned().collect())));
+    c.methods.push((intern(b"entries"), |inst, _, _|
+      Litr::List(CollectionIter::new(inst).collect())));
+    c.methods.push((intern(b"union"), |inst, mut args, _|{
+      let mut m = map(inst.v).clone();
+      m.extend(to_map(args.get_mut(0), "map.union"));
+      new_map(m)
+    }));
+    c.methods.push((intern(b"intersection"), |inst, mut args, _|{
+      let other = to_map(args.get_mut(0), "map.intersection");
+      new_map(map(inst.v).iter().filter(|(k, _)|other.contains_key(k)).cloned().collect())
+    }));
+    c.methods.push((intern(b"difference"), |inst, mut args, _|{
+      let other = to_map(args.get_mut(0), "map.difference");
+      new_map(map(inst.v).iter().filter(|(k, _)|!other.contains_key(k)).cloned().collect())
+    }));
+    c.methods.push((intern(b"for_each"), |inst, args, cx|{
+      let f = func(args.get(0), "map.for_each");
+      // 借用遍历器的计数, 回调出错时也能恢复
+      let mut itr = CollectionIter::new(inst);
+      while let Some((n, (k, v))) = map(inst.v).slot_from(itr.i) {
+        itr.i = n + 1;
+        let k = k.clone();
+        // 回调中可能插入元素使槽位重新分配, 因此传入副本, 结束后再写回
+        let mut v = v.clone();
+        cx.call(vec![CalcRef::Own(k.0.clone()), CalcRef::Ref(&mut v)], &f);
+        if let Some(slot) = map(inst.v).get_mut(&k) {
+          *slot = v;
+        }
+      }
+      Litr::Uninit
+    }));
+    c.getter = |inst, get|match get.vec() {
+      b"len"=> Litr::Uint(map(inst.v).len()),
+      _=> Litr::Uninit
+    };
+    c.index_get = |inst, i|map(inst.v).get(&Key::new(i.own())).cloned().unwrap_or(Litr::Uninit);
+    c.index_set = |inst, i, v|{
+      map(inst.v).insert(Key::new(i.own()), v);
+    };
+    c.to_str = |inst|format!("Map {{ {} }}", map(inst.v).iter()
+      .map(|(k, v)|format!("{}: {}", k.0.str(), v.str())).collect::<Vec<_>>().join(", "));
+    c.onclone = |inst|NativeInstance {
+      cls: inst.cls, v: Box::into_raw(Box::new(map(inst.v).clone())) as usize, w: 0
+    };
+    c.ondrop = |inst|drop(Box::from_raw(inst.v as *mut Map));
+    m
+  }
+}
@@ -0,0 +1,245 @@
+//! fmt和模板字符串使用的格式化
+//!
+//! 占位符写作`{参数:格式}`, 参数可以省略(按顺序取), 写数字(按位置取)或写名字(在最后一个Obj参数中取)
+//!
+//! 格式为`[[填充]对齐][+][#][0][宽度][.精度][类型]`:
+//! - 对齐: `<`左对齐, `>`右对齐, `^`居中. 数字默认右对齐, 其他默认左对齐
+//! - `+`: 为非负数加上正号
+//! - `#`: 为x, o, b类型加上0x, 0o, 0b前缀
+//! - `0`: 在符号和数字之间用0补齐宽度
+//! - 精度: Float保留的小数位数, Str保留的字符数
+//! - 类型: `x`/`X`十六进制, `o`八进制, `b`二进制, `e`科学计数法, `?`调试输出
+
+use super::*;
+
+/// 解析后的格式
+#[derive(Debug, Clone)]
+pub struct Spec {
+  fill: char,
+  align: Option<u8>,
+  sign: bool,
+  alt: bool,
+  zero: bool,
+  width: usize,
+  prec: Option<usize>,
+  kind: Option<u8>
+}
+
+impl Spec {
+  pub fn parse(s:&str)-> Spec {
+    let mut spec = Spec {
+      fill: ' ', align: None, sign: false, alt: false, zero: false,
+      width: 0, prec: None, kind: None
+    };
+    let chars:Vec<char> = s.chars().collect();
+    let mut i = 0;
+
+    // 填充字符只能和对齐一起出现
+    let is_align = |c:Option<&char>|matches!(c, Some('<'|'>'|'^'));
+    if is_align(chars.get(1)) {
+      spec.fill = chars[0];
+      spec.align = Some(chars[1] as u8);
+      i = 2;
+    }else if is_align(chars.get(0)) {
+      spec.align = Some(chars[0] as u8);
+      i = 1;
+    }
+
+    if chars.get(i) == Some(&'+') {
+      spec.sign = true;
+      i += 1;
+    }
+    if chars.get(i) == Some(&'#') {
+      spec.alt = true;
+      i += 1;
+    }
+    if chars.get(i) == Some(&'0') {
+      spec.zero = true;
+      i += 1;
+    }
+
+    let num = |i:&mut usize|-> Option<usize> {
+      let start = *i;
+      while chars.get(*i).map_or(false, |c|c.is_ascii_digit()) {
+        *i += 1;
+      }
+      if start == *i {
+        return None;
+      }
+      chars[start..*i].iter().collect::<String>().parse().ok()
+    };
+    spec.width = num(&mut i).unwrap_or(0);
+    if chars.get(i) == Some(&'.') {
+      i += 1;
+      spec.prec = Some(num(&mut i).unwrap_or_else(||panic!("格式'{}'的'.'后需要精度", s)));
+    }
+
+    match chars.get(i) {
+      Some(c@('x'|'X'|'o'|'b'|'e'|'?'))=> {
+        spec.kind = Some(*c as u8);
+        i += 1;
+      }
+      _=> ()
+    }
+    if i != chars.len() {
+      panic!("无法识别的格式'{}'", s)
+    }
+    spec
+  }
+
+  /// 按格式将值转为字符串
+  pub fn apply(&self, v:&Litr)-> String {
+    // (符号, 前缀, 内容), 数字才有符号和前缀
+    let (neg, prefix, body, numeric) = match (v, self.kind) {
+      (Litr::Int(n), Some(k@(b'x'|b'X'|b'o'|b'b')))=> (*n < 0, prefix(k), radix(n.unsigned_abs(), k), true),
+      (Litr::Uint(n), Some(k@(b'x'|b'X'|b'o'|b'b')))=> (false, prefix(k), radix(*n, k), true),
+      (Litr::BigInt(n), Some(k@(b'x'|b'X'|b'o'|b'b')))=> (n.is_neg(), prefix(k), big_radix(n, k), true),
+      (_, Some(k@(b'x'|b'X'|b'o'|b'b')))=> panic!("只有整数可以使用'{}'格式", k as char),
+      (Litr::Int(n), Some(b'e'))=> (*n < 0, "", format!("{:e}", n.unsigned_abs() as f64), true),
+      (Litr::Uint(n), Some(b'e'))=> (false, "", format!("{:e}", *n as f64), true),
+      (Litr::BigInt(n), Some(b'e'))=> (n.is_neg(), "", format!("{:e}", n.to_f64().abs()), true),
+      (Litr::Float(n), Some(b'e'))=> (n.is_sign_negative(), "", match self.prec {
+        Some(p)=> format!("{:.*e}", p, n.abs()),
+        None=> format!("{:e}", n.abs())
+      }, true),
+      (_, Some(b'e'))=> panic!("只有数字可以使用'e'格式"),
+      (v, Some(b'?'))=> (false, "", format!("{:?}", v), false),
+      (Litr::Int(n), _)=> (*n < 0, "", n.unsigned_abs().to_string(), true),
+      (Litr::Uint(n), _)=> (false, "", n.to_string(), true),
+      (Litr::BigInt(n), _)=> (n.is_neg(), "", n.abs().to_str_radix(10), true),
+      (Litr::Float(n), _)=> (n.is_sign_negative() && *n != 0.0, "", match self.prec {
+        Some(p)=> format!("{:.*}", p, n.abs()),
+        None=> Litr::Float(n.abs()).str()
+      }, true),
+      (v, _)=> {
+        let s = v.str();
+        let s = match self.prec {
+          Some(p)=> s.chars().take(p).collect(),
+          None=> s
+        };
+        (false, "", s, false)
+      }
+    };
+
+    let sign = if neg {"-"}else if self.sign && numeric {"+"}else {""};
+    let prefix = if self.alt {prefix}else {""};
+    let len = sign.chars().count() + prefix.len() + body.chars().count();
+    if len >= self.width {
+      return format!("{}{}{}", sign, prefix, body);
+    }
+    let pad = self.width - len;
+
+    // 0补齐在符号和前缀之后
+    if self.zero && numeric {
+      return format!("{}{}{}{}", sign, prefix, "0".repeat(pad), body);
+    }
+    let fill = |n:usize|std::iter::repeat(self.fill).take(n).collect::<String>();
+    let s = format!("{}{}{}", sign, prefix, body);
+    match self.align.unwrap_or(if numeric {b'>'}else {b'<'}) {
+      b'<'=> s + &fill(pad),
+      b'^'=> format!("{}{}{}", fill(pad / 2), s, fill(pad - pad / 2)),
+      _=> fill(pad) + &s
+    }
+  }
+}
+
+fn prefix(kind:u8)-> &'static str {
+  match kind {
+    b'x'|b'X'=> "0x",
+    b'o'=> "0o",
+    _=> "0b"
+  }
+}
+
+fn radix(n:usize, kind:u8)-> String {
+  match kind {
+    b'x'=> format!("{:x}", n),
+    b'X'=> format!("{:X}", n),
+    b'o'=> format!("{:o}", n),
+    _=> format!("{:b}", n)
+  }
+}
+
+fn big_radix(n:&super::bigint::BigInt, kind:u8)-> String {
+  let n = n.abs();
+  match kind {
+    b'x'=> n.to_str_radix(16),
+    b'X'=> n.to_str_radix(16).to_uppercase(),
+    b'o'=> n.to_str_radix(8),
+    _=> n.to_str_radix(2)
+  }
+}
+
+/// 按格式字符串拼接参数, `{{`和`}}`是转义的大括号
+/// 
+/// `{名称}`只在最后一个参数是Obj时才是具名参数, 否则和无法识别的大括号一样原样输出.
+/// 找不到对应参数的占位符也原样输出
+pub fn format(fmtr:&str, args:&[CalcRef])-> String {
+  let mut out = String::new();
+  let named = match args.last().map(|v|&**v) {
+    Some(Litr::Obj(o))=> Some(o),
+    _=> None
+  };
+  // 按顺序取的参数序号
+  let mut next = 0;
+  let mut i = 0;
+  let b = fmtr.as_bytes();
+  while let Some(n) = fmtr[i..].find(['{', '}']) {
+    out.push_str(&fmtr[i..i + n]);
+    i += n;
+    if b.get(i + 1) == Some(&b[i]) {
+      out.push(b[i] as char);
+      i += 2;
+      continue;
+    }
+    if b[i] == b'}' {
+      out.push('}');
+      i += 1;
+      continue;
+    }
+
+    let end = match fmtr[i + 1..].find('}') {
+      Some(n)=> i + 1 + n,
+      None=> {
+        out.push('{');
+        i += 1;
+        continue;
+      }
+    };
+    let inner = &fmtr[i + 1..end];
+    let (arg, spec) = match inner.find(':') {
+      Some(n)=> (&inner[..n], &inner[n+1..]),
+      None=> (inner, "")
+    };
+    let arg = arg.trim();
+    let v = if arg.is_empty() {
+      next += 1;
+      args.get(next - 1).map(|v|&**v)
+    }else if let Ok(n) = arg.parse::<usize>() {
+      args.get(n).map(|v|&**v)
+    }else {
+      let is_ident = arg.chars().all(|c|c.is_alphanumeric() || c == '_');
+      match named {
+        Some(o) if is_ident=> o.get(&intern(arg.as_bytes())),
+        _=> {
+          out.push('{');
+          i += 1;
+          continue;
+        }
+      }
+    };
+    // 参数不足时占位符原样保留
+    let v = match v {
+      Some(v)=> v,
+      None=> {
+        out.push_str(&fmtr[i..=end]);
+        i = end + 1;
+        continue;
+      }
+    };
+    out.push_str(&Spec::parse(spec).apply(v));
+    i = end + 1;
+  }
+  out.push_str(&fmtr[i..]);
+  out
+}
@@ -2,17 +2,19 @@ use crate::{
   intern::{intern, Interned}, native::NativeFn, primitive::litr::{ArgDecl, Function, KsType, Litr, LocalFunc, LocalFuncRaw}, runtime::{calc::CalcRef, Scope}, scan::{expr::Expr, stmt::Statements}
 };
 
-use super::litr::LocalFuncRawArg;
+use super::{litr::LocalFuncRawArg, Method, method_table};
+use std::sync::LazyLock;
 
-pub fn method(f:&Function, name:Interned, cx: Scope, args:Vec<CalcRef>)-> Litr {
-  match name.vec() {
-    b"call"=> kcall(f, args, cx),
-    b"clone_here"=> clone_here(f, args, cx),
-    b"call_here"=> call_here(f, args, cx),
-    b"clone_top"=> clone_top(f, args, cx),
-    b"unzip"=> unzip(f, cx),
-    _=> panic!("func没有{}方法",name)
-  }
+/// Func的方法表
+static METHODS:LazyLock<Vec<(Interned, Method<Function>)>> = LazyLock::new(||method_table(&[
+  (b"call", |f, args, cx|kcall(f, args, cx)),
+  (b"clone_here", |f, args, cx|clone_here(f, args, cx)),
+  (b"call_here", |f, args, cx|call_here(f, args, cx)),
+  (b"clone_top", |f, args, cx|clone_top(f, args, cx)),
+  (b"unzip", |f, _, cx|unzip(f, cx)),
+]));
+pub fn methods()-> &'static [(Interned, Method<Function>)] {
+  &METHODS
 }
 
 /// 传入self并调用
@@ -82,7 +84,7 @@ pub fn unzip(f:&Function, mut cx:Scope)-> Litr {
   cx.return_to = &mut unzip_return_to;
   
   for (l, sm) in &codes.v {
-    unsafe{crate::LINE = *l;}
+    unsafe{crate::LINE = l.end;}
     cx.evil(sm);
 
     // unzip过程中的return作为unzip返回值
@@ -105,6 +107,7 @@ pub fn statics()-> Vec<(Interned, NativeFn)> {
 
 fn s_new(mut s:Vec<CalcRef>, cx:Scope)-> Litr {
   let mut itr = s.iter_mut();
+  unsafe {crate::scan::YIELDED = false;}
   let stmts = match itr.next() {
     Some(arg)=> crate::scan::scan(match &**arg {
       Litr::Str(s)=> s.as_bytes(),
@@ -113,6 +116,8 @@ fn s_new(mut s:Vec<CalcRef>, cx:Scope)-> Litr {
     }),
     None=> Statements::default()
   };
+  // 函数体中有yield就作为生成器函数
+  let generator = unsafe {std::ptr::replace(std::ptr::addr_of_mut!(crate::scan::YIELDED), false)};
 
   let mut argdecl = Vec::new();
   while let Some(s) = itr.next() {
@@ -120,10 +125,10 @@ fn s_new(mut s:Vec<CalcRef>, cx:Scope)-> Litr {
       Litr::Str(s)=> intern(s.as_bytes()),
       _=> continue
     };
-    argdecl.push(ArgDecl {default: Expr::Literal(Litr::Uninit), name, t:KsType::Any});
+    argdecl.push(ArgDecl {default: Expr::Literal(Litr::Uninit), name, t:KsType::Any, pat:None});
   }
 
   Litr::Func(Function::Local(LocalFunc::new(Box::into_raw(Box::new(
-    LocalFuncRaw {argdecl:LocalFuncRawArg::Normal(argdecl), stmts, name: intern(b"unnamed")}
+    LocalFuncRaw {argdecl:LocalFuncRawArg::Normal(argdecl), stmts, name: intern(b"unnamed"), ret:KsType::Any, generator}
   )),cx)))
 }
@@ -0,0 +1,260 @@
+//! 带有yield的函数被调用时会返回一个生成器
+//!
+//! 函数体在单独的线程中运行, 但同一时间只有一个线程在工作,
+//! 因此和主线程共用解释器的全局状态也不会产生竞争.
+//! 线程在第一次next时才创建, 运行结束后退出
+
+use super::*;
+use crate::runtime::{call::{call_stack, CallStackElem}, Variant};
+use crate::primitive::litr::LocalFunc;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread::JoinHandle;
+
+pub static mut GENERATOR_CLASS: *mut NativeClassDef = std::ptr::null_mut();
+
+/// 生成器线程名, 报错钩子以此判断是否由主线程代为报错
+pub const THREAD_NAME:&str = "ks-generator";
+
+/// 生成器线程的栈大小
+/// 
+/// 同时挂起的生成器可能很多, 不使用--stack的大小. 栈不足时stack_guard会报错
+const STACK_SIZE:usize = 8 << 20;
+
+/// 同时存在的生成器线程上限
+/// 
+/// 线程过多时系统资源耗尽, 进程会直接崩溃而来不及报错
+const MAX_THREADS:usize = 8192;
+
+/// 当前存在的生成器线程数
+static THREADS:AtomicUsize = AtomicUsize::new(0);
+
+/// 生成器线程退出时减少THREADS
+struct ThreadCount;
+impl Drop for ThreadCount {
+  fn drop(&mut self) {
+    THREADS.fetch_sub(1, Ordering::Relaxed);
+  }
+}
+
+/// 允许在线程间传递解释器的值
+///
+/// 生成器线程和主线程交替运行, 不会同时访问同一个值
+struct Handoff<T>(T);
+unsafe impl<T> Send for Handoff<T> {}
+
+/// 生成器线程传回主线程的消息
+enum GenMsg {
+  Yield(Litr),
+  Done,
+  Panic(String)
+}
+
+/// 生成器函数和调用时传入的参数, self
+type Task = (LocalFunc, Vec<Variant>, *mut Litr);
+
+/// 主线程持有的生成器
+pub struct Generator {
+  /// 还没开始运行的函数体, 第一次next时交给新线程
+  task: Option<Task>,
+  /// 通知生成器线程继续运行, drop时会让未结束的生成器线程退出
+  resume: Option<SyncSender<()>>,
+  /// 接收yield的值
+  yielded: Option<Receiver<Handoff<GenMsg>>>,
+  thread: Option<JoinHandle<()>>,
+  /// 保证函数定义处的作用域在生成器存活时不被回收
+  _f: LocalFunc,
+  done: bool
+}
+
+impl Drop for Generator {
+  fn drop(&mut self) {
+    // 先断开通道让挂起的线程退出, 再等待其释放完毕
+    self.resume = None;
+    if let Some(t) = self.thread.take() {
+      let _ = t.join();
+    }
+  }
+}
+
+/// 生成器线程中用于yield的上下文
+struct GenCx {
+  yielded: SyncSender<Handoff<GenMsg>>,
+  resume: Receiver<()>,
+  /// 生成器自身的调用栈起始位置
+  stack_base: usize
+}
+thread_local! {
+  static GEN_CX: RefCell<Option<GenCx>> = RefCell::new(None);
+}
+
+/// 生成器被丢弃时用于退出线程的标记
+/// 
+/// try不能捕获它, 否则线程无法退出
+pub struct Abandoned;
+
+pub fn init()-> (Interned, *mut NativeClassDef) {
+  unsafe {
+    let s = new_static_class(b"Generator", vec![]);
+    GENERATOR_CLASS = s.1;
+    let gen = &mut *GENERATOR_CLASS;
+    gen.methods.push((intern(b"next"), |inst, _, _|resume(inst)));
+    gen.next = resume;
+    gen.getter = getter;
+    gen.onclone = |_|panic!("无法复制生成器. 请考虑用take函数代替");
+    gen.ondrop = |inst|drop(Box::from_raw(inst.v as *mut Generator));
+    s
+  }
+}
+
+fn getter(inst:&NativeInstance, get:Interned)-> Litr {
+  let gen = unsafe {&*(inst.v as *const Generator)};
+  match get.vec() {
+    b"done"=> Litr::Bool(gen.done),
+    _=> Litr::Uninit
+  }
+}
+
+/// 为生成器函数创建生成器, 此时函数体还未开始运行
+pub fn new(f:&LocalFunc, vars:Vec<Variant>, kself:*mut Litr)-> Litr {
+  let gen = Box::new(Generator {
+    task: Some((f.clone(), vars, kself)),
+    resume: None,
+    yielded: None,
+    thread: None,
+    _f: f.clone(),
+    done: false
+  });
+  Litr::Ninst(NativeInstance {
+    cls: unsafe{GENERATOR_CLASS},
+    v: Box::into_raw(gen) as usize, w: 0
+  })
+}
+
+/// 创建运行函数体的线程, 线程会先等待resume
+fn spawn(gen:&mut Generator, task:Task) {
+  if THREADS.fetch_add(1, Ordering::Relaxed) >= MAX_THREADS {
+    THREADS.fetch_sub(1, Ordering::Relaxed);
+    gen.done = true;
+    panic!("同时运行中的生成器不能超过{}个", MAX_THREADS)
+  }
+  let count = ThreadCount;
+
+  let (resume_tx, resume_rx) = sync_channel::<()>(0);
+  let (yield_tx, yield_rx) = sync_channel::<Handoff<GenMsg>>(0);
+
+  let task = Handoff(task);
+  let thread = std::thread::Builder::new()
+    .name(THREAD_NAME.to_string())
+    .stack_size(STACK_SIZE)
+    .spawn(move || {
+      crate::runtime::call::set_stack_size(STACK_SIZE);
+      let _count = count;
+      let task = task;
+      if resume_rx.recv().is_err() {
+        return;
+      }
+      let (f, vars, kself) = task.0;
+      let yielded = yield_tx.clone();
+      GEN_CX.with(|cx|*cx.borrow_mut() = Some(GenCx {
+        yielded: yield_tx, resume: resume_rx, stack_base: call_stack().len()
+      }));
+
+      let res = std::panic::catch_unwind(move || {
+        let mut ret = Litr::Uninit;
+        let mut scope = f.scope.subscope();
+        scope.return_to = &mut ret;
+        scope.vars = vars;
+        scope.kself = kself;
+        unsafe {
+          crate::FILE_PATH = (*scope.exports).modpath;
+          call_stack().push(CallStackElem {file: crate::FILE_PATH, line: crate::LINE, start: crate::debugger::START, fname: f.name});
+          scope.run(&f.stmts);
+          call_stack().pop();
+        }
+      });
+
+      let msg = match res {
+        Ok(_)=> GenMsg::Done,
+        Err(err)=> {
+          if err.is::<Abandoned>() {
+            return;
+          }
+          GenMsg::Panic(if let Some(mes) = err.downcast_ref::<&'static str>() {
+            mes.to_string()
+          }else if let Some(mes) = err.downcast_ref::<String>() {
+            mes.clone()
+          }else {"错误".to_string()})
+        }
+      };
+      let _ = yielded.send(Handoff(msg));
+    })
+    .unwrap_or_else(|e|{
+      gen.done = true;
+      panic!("无法为生成器创建线程: {}", e)
+    });
+
+  gen.resume = Some(resume_tx);
+  gen.yielded = Some(yield_rx);
+  gen.thread = Some(thread);
+}
+
+/// 让生成器运行到下一个yield, 结束时返回Sym::iter_end()
+fn resume(inst:&mut NativeInstance)-> Litr {
+  let gen = unsafe {&mut *(inst.v as *mut Generator)};
+  if gen.done {
+    return sym::iter_end();
+  }
+
+  if let Some(task) = gen.task.take() {
+    spawn(gen, task);
+  }
+
+  unsafe {
+    let line = crate::LINE;
+    let file_path = crate::FILE_PATH;
+    gen.resume.as_ref().unwrap().send(()).expect("生成器线程意外退出");
+    let msg = gen.yielded.as_ref().unwrap().recv().expect("生成器线程意外退出").0;
+    match msg {
+      GenMsg::Yield(v)=> {
+        crate::LINE = line;
+        crate::FILE_PATH = file_path;
+        v
+      }
+      GenMsg::Done=> {
+        crate::LINE = line;
+        crate::FILE_PATH = file_path;
+        gen.done = true;
+        sym::iter_end()
+      }
+      // 报错行号和调用栈保留生成器内的位置
+      GenMsg::Panic(mes)=> {
+        gen.done = true;
+        panic!("{}", mes)
+      }
+    }
+  }
+}
+
+/// 在生成器线程中交出一个值, 并等待下一次next
+pub fn yielding(v:Litr) {
+  GEN_CX.with(|cx| {
+    let mut cx = cx.borrow_mut();
+    let cx = cx.as_mut().expect("yield只能在生成器函数中使用");
+    unsafe {
+      // 交出控制权前把自己的调用栈和位置收起来
+      let frames = call_stack().split_off(cx.stack_base.min(call_stack().len()));
+      let line = crate::LINE;
+      let file_path = crate::FILE_PATH;
+      cx.yielded.send(Handoff(GenMsg::Yield(v))).expect("生成器已被丢弃");
+      if cx.resume.recv().is_err() {
+        std::panic::resume_unwind(Box::new(Abandoned));
+      }
+      crate::LINE = line;
+      crate::FILE_PATH = file_path;
+      cx.stack_base = call_stack().len();
+      call_stack().extend(frames);
+    }
+  })
+}
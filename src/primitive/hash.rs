@@ -0,0 +1,486 @@
+//! 哈希和校验和
+//!
+//! `Hash::sha256(data)`等一次性计算, `Hash::new("sha256")`得到可以分段`update`的哈希实例.
+//! 数据可以是Str或Buf, 结果均为Buf, 可用`Hash::hex`转为十六进制
+
+use super::*;
+
+pub static mut HASH_CLASS: *mut NativeClassDef = std::ptr::null_mut();
+
+const MD5_K:[u32; 64] = [
+  0xd76aa478, 0xe8c7b756, 0x242070db, 0xc1bdceee, 0xf57c0faf, 0x4787c62a, 0xa8304613, 0xfd469501,
+  0x698098d8, 0x8b44f7af, 0xffff5bb1, 0x895cd7be, 0x6b901122, 0xfd987193, 0xa679438e, 0x49b40821,
+  0xf61e2562, 0xc040b340, 0x265e5a51, 0xe9b6c7aa, 0xd62f105d, 0x02441453, 0xd8a1e681, 0xe7d3fbc8,
+  0x21e1cde6, 0xc33707d6, 0xf4d50d87, 0x455a14ed, 0xa9e3e905, 0xfcefa3f8, 0x676f02d9, 0x8d2a4c8a,
+  0xfffa3942, 0x8771f681, 0x6d9d6122, 0xfde5380c, 0xa4beea44, 0x4bdecfa9, 0xf6bb4b60, 0xbebfbc70,
+  0x289b7ec6, 0xeaa127fa, 0xd4ef3085, 0x04881d05, 0xd9d4d039, 0xe6db99e5, 0x1fa27cf8, 0xc4ac5665,
+  0xf4292244, 0x432aff97, 0xab9423a7, 0xfc93a039, 0x655b59c3, 0x8f0ccc92, 0xffeff47d, 0x85845dd1,
+  0x6fa87e4f, 0xfe2ce6e0, 0xa3014314, 0x4e0811a1, 0xf7537e82, 0xbd3af235, 0x2ad7d2bb, 0xeb86d391
+];
+const MD5_S:[u32; 16] = [7, 12, 17, 22, 5, 9, 14, 20, 4, 11, 16, 23, 6, 10, 15, 21];
+
+const SHA256_K:[u32; 64] = [
+  0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+  0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+  0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+  0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+  0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+  0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+  0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+  0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2
+];
+
+const SHA512_K:[u64; 80] = [
+  0x428a2f98d728ae22, 0x7137449123ef65cd, 0xb5c0fbcfec4d3b2f, 0xe9b5dba58189dbbc,
+  0x3956c25bf348b538, 0x59f111f1b605d019, 0x923f82a4af194f9b, 0xab1c5ed5da6d8118,
+  0xd807aa98a3030242, 0x12835b0145706fbe, 0x243185be4ee4b28c, 0x550c7dc3d5ffb4e2,
+  0x72be5d74f27b896f, 0x80deb1fe3b1696b1, 0x9bdc06a725c71235, 0xc19bf174cf692694,
+  0xe49b69c19ef14ad2, 0xefbe4786384f25e3, 0x0fc19dc68b8cd5b5, 0x240ca1cc77ac9c65,
+  0x2de92c6f592b0275, 0x4a7484aa6ea6e483, 0x5cb0a9dcbd41fbd4, 0x76f988da831153b5,
+  0x983e5152ee66dfab, 0xa831c66d2db43210, 0xb00327c898fb213f, 0xbf597fc7beef0ee4,
+  0xc6e00bf33da88fc2, 0xd5a79147930aa725, 0x06ca6351e003826f, 0x142929670a0e6e70,
+  0x27b70a8546d22ffc, 0x2e1b21385c26c926, 0x4d2c6dfc5ac42aed, 0x53380d139d95b3df,
+  0x650a73548baf63de, 0x766a0abb3c77b2a8, 0x81c2c92e47edaee6, 0x92722c851482353b,
+  0xa2bfe8a14cf10364, 0xa81a664bbc423001, 0xc24b8b70d0f89791, 0xc76c51a30654be30,
+  0xd192e819d6ef5218, 0xd69906245565a910, 0xf40e35855771202a, 0x106aa07032bbd1b8,
+  0x19a4c116b8d2d0c8, 0x1e376c085141ab53, 0x2748774cdf8eeb99, 0x34b0bcb5e19b48a8,
+  0x391c0cb3c5c95a63, 0x4ed8aa4ae3418acb, 0x5b9cca4f7763e373, 0x682e6ff3d6b2b8a3,
+  0x748f82ee5defb2fc, 0x78a5636f43172f60, 0x84c87814a1f0ab72, 0x8cc702081a6439ec,
+  0x90befffa23631e28, 0xa4506cebde82bde9, 0xbef9a3f7b2c67915, 0xc67178f2e372532b,
+  0xca273eceea26619c, 0xd186b8c721c0c207, 0xeada7dd6cde0eb1e, 0xf57d4f7fee6ed178,
+  0x06f067aa72176fba, 0x0a637dc5a2c898a6, 0x113f9804bef90dae, 0x1b710b35131c471b,
+  0x28db77f523047d84, 0x32caab7b40c72493, 0x3c9ebe0a15c9bebc, 0x431d67c49c100d4c,
+  0x4cc5d4becb3e42b6, 0x597f299cfc657e2a, 0x5fcb6fab3ad6faec, 0x6c44198c4a475817
+];
+
+/// CRC32(IEEE)查询表
+const CRC_TABLE:[u32; 256] = {
+  let mut t = [0u32; 256];
+  let mut i = 0;
+  while i < 256 {
+    let mut c = i as u32;
+    let mut k = 0;
+    while k < 8 {
+      c = if c & 1 == 1 {0xedb88320 ^ (c >> 1)}else {c >> 1};
+      k += 1;
+    }
+    t[i] = c;
+    i += 1;
+  }
+  t
+};
+
+/// 各算法的中间状态
+#[derive(Clone)]
+enum State {
+  Crc32(u32),
+  Adler32(u32, u32),
+  Md5([u32; 4]),
+  Sha1([u32; 5]),
+  Sha256([u32; 8]),
+  Sha512([u64; 8])
+}
+
+/// 可以分段输入的哈希
+#[derive(Clone)]
+pub struct Hasher {
+  st: State,
+  /// 还未凑满一个分组的数据
+  pending: Vec<u8>,
+  /// 已输入的总字节数
+  len: u128
+}
+
+impl Hasher {
+  pub fn new(algo:&str)-> Hasher {
+    let st = match algo.to_ascii_lowercase().replace('-', "").as_str() {
+      "crc32"=> State::Crc32(0xffffffff),
+      "adler32"=> State::Adler32(1, 0),
+      "md5"=> State::Md5([0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476]),
+      "sha1"=> State::Sha1([0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0]),
+      "sha256"=> State::Sha256([
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab, 0x5be0cd19
+      ]),
+      "sha512"=> State::Sha512([
+        0x6a09e667f3bcc908, 0xbb67ae8584caa73b, 0x3c6ef372fe94f82b, 0xa54ff53a5f1d36f1,
+        0x510e527fade682d1, 0x9b05688c2b3e6c1f, 0x1f83d9abfb41bd6b, 0x5be0cd19137e2179
+      ]),
+      _=> panic!("不支持的哈希算法'{}', 可用crc32, adler32, md5, sha1, sha256, sha512", algo)
+    };
+    Hasher {st, pending: Vec::new(), len: 0}
+  }
+
+  pub fn name(&self)-> &'static str {
+    match self.st {
+      State::Crc32(_)=> "crc32",
+      State::Adler32(..)=> "adler32",
+      State::Md5(_)=> "md5",
+      State::Sha1(_)=> "sha1",
+      State::Sha256(_)=> "sha256",
+      State::Sha512(_)=> "sha512"
+    }
+  }
+
+  /// 分组长度, 校验和没有分组
+  fn block(&self)-> usize {
+    match self.st {
+      State::Crc32(_)|State::Adler32(..)=> 0,
+      State::Sha512(_)=> 128,
+      _=> 64
+    }
+  }
+
+  /// 结果的字节数
+  pub fn size(&self)-> usize {
+    match self.st {
+      State::Crc32(_)|State::Adler32(..)=> 4,
+      State::Md5(_)=> 16,
+      State::Sha1(_)=> 20,
+      State::Sha256(_)=> 32,
+      State::Sha512(_)=> 64
+    }
+  }
+
+  pub fn update(&mut self, data:&[u8]) {
+    self.len += data.len() as u128;
+    match &mut self.st {
+      State::Crc32(c)=> {
+        for b in data {
+          *c = CRC_TABLE[((*c ^ *b as u32) & 0xff) as usize] ^ (*c >> 8);
+        }
+        return;
+      }
+      State::Adler32(a, b)=> {
+        // 5552是保证u32不溢出的最大分段
+        for chunk in data.chunks(5552) {
+          for x in chunk {
+            *a += *x as u32;
+            *b += *a;
+          }
+          *a %= 65521;
+          *b %= 65521;
+        }
+        return;
+      }
+      _=> ()
+    }
+
+    let block = self.block();
+    let mut data = data;
+    if !self.pending.is_empty() {
+      let need = block - self.pending.len();
+      if data.len() < need {
+        self.pending.extend_from_slice(data);
+        return;
+      }
+      let mut first = std::mem::take(&mut self.pending);
+      first.extend_from_slice(&data[..need]);
+      self.compress(&first);
+      data = &data[need..];
+    }
+    let full = data.len() / block * block;
+    for chunk in data[..full].chunks(block) {
+      self.compress(chunk);
+    }
+    self.pending.extend_from_slice(&data[full..]);
+  }
+
+  /// 在副本上补齐并输出结果, 不影响继续输入
+  pub fn finish(&self)-> Vec<u8> {
+    match &self.st {
+      State::Crc32(c)=> return (!c).to_be_bytes().to_vec(),
+      State::Adler32(a, b)=> return ((b << 16) | a).to_be_bytes().to_vec(),
+      _=> ()
+    }
+    let mut h = self.clone();
+    let block = h.block();
+    // 长度字段: MD5为64位小端, SHA-1和SHA-256为64位大端, SHA-512为128位大端
+    let len_bytes = if block == 128 {16}else {8};
+    let bits = self.len.wrapping_mul(8);
+    let mut tail = vec![0x80u8];
+    while (h.pending.len() + tail.len()) % block != block - len_bytes {
+      tail.push(0);
+    }
+    match h.st {
+      State::Md5(_)=> tail.extend_from_slice(&(bits as u64).to_le_bytes()),
+      State::Sha512(_)=> tail.extend_from_slice(&bits.to_be_bytes()),
+      _=> tail.extend_from_slice(&(bits as u64).to_be_bytes())
+    }
+    let mut data = std::mem::take(&mut h.pending);
+    data.extend_from_slice(&tail);
+    for chunk in data.chunks(block) {
+      h.compress(chunk);
+    }
+    match h.st {
+      State::Md5(s)=> s.iter().flat_map(|x|x.to_le_bytes()).collect(),
+      State::Sha1(s)=> s.iter().flat_map(|x|x.to_be_bytes()).collect(),
+      State::Sha256(s)=> s.iter().flat_map(|x|x.to_be_bytes()).collect(),
+      State::Sha512(s)=> s.iter().flat_map(|x|x.to_be_bytes()).collect(),
+      _=> unreachable!()
+    }
+  }
+
+  fn compress(&mut self, chunk:&[u8]) {
+    match &mut self.st {
+      State::Md5(s)=> {
+        let m:Vec<u32> = chunk.chunks(4).map(|b|u32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+        let [mut a, mut b, mut c, mut d] = *s;
+        for i in 0..64 {
+          let (f, g) = match i / 16 {
+            0=> ((b & c) | (!b & d), i),
+            1=> ((d & b) | (!d & c), (5 * i + 1) % 16),
+            2=> (b ^ c ^ d, (3 * i + 5) % 16),
+            _=> (c ^ (b | !d), (7 * i) % 16)
+          };
+          let f = f.wrapping_add(a).wrapping_add(MD5_K[i]).wrapping_add(m[g]);
+          a = d;
+          d = c;
+          c = b;
+          b = b.wrapping_add(f.rotate_left(MD5_S[i / 16 * 4 + i % 4]));
+        }
+        for (x, y) in s.iter_mut().zip([a, b, c, d]) {
+          *x = x.wrapping_add(y);
+        }
+      }
+      State::Sha1(s)=> {
+        let mut w = [0u32; 80];
+        for (i, b) in chunk.chunks(4).enumerate() {
+          w[i] = u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+        }
+        for i in 16..80 {
+          w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e] = *s;
+        for i in 0..80 {
+          let (f, k) = match i / 20 {
+            0=> ((b & c) | (!b & d), 0x5a827999),
+            1=> (b ^ c ^ d, 0x6ed9eba1),
+            2=> ((b & c) | (b & d) | (c & d), 0x8f1bbcdc),
+            _=> (b ^ c ^ d, 0xca62c1d6)
+          };
+          let t = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(w[i]);
+          e = d;
+          d = c;
+          c = b.rotate_left(30);
+          b = a;
+          a = t;
+        }
+        for (x, y) in s.iter_mut().zip([a, b, c, d, e]) {
+          *x = x.wrapping_add(y);
+        }
+      }
+      State::Sha256(s)=> {
+        let mut w = [0u32; 64];
+        for (i, b) in chunk.chunks(4).enumerate() {
+          w[i] = u32::from_be_bytes([b[0], b[1], b[2], b[3]]);
+        }
+        for i in 16..64 {
+          let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+          let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+          w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+        let mut v = *s;
+        for i in 0..64 {
+          let [a, b, c, d, e, f, g, h] = v;
+          let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+          let ch = (e & f) ^ (!e & g);
+          let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(SHA256_K[i]).wrapping_add(w[i]);
+          let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+          let maj = (a & b) ^ (a & c) ^ (b & c);
+          let t2 = s0.wrapping_add(maj);
+          v = [t1.wrapping_add(t2), a, b, c, d.wrapping_add(t1), e, f, g];
+        }
+        for (x, y) in s.iter_mut().zip(v) {
+          *x = x.wrapping_add(y);
+        }
+      }
+      State::Sha512(s)=> {
+        let mut w = [0u64; 80];
+        for (i, b) in chunk.chunks(8).enumerate() {
+          w[i] = u64::from_be_bytes(b.try_into().unwrap());
+        }
+        for i in 16..80 {
+          let s0 = w[i - 15].rotate_right(1) ^ w[i - 15].rotate_right(8) ^ (w[i - 15] >> 7);
+          let s1 = w[i - 2].rotate_right(19) ^ w[i - 2].rotate_right(61) ^ (w[i - 2] >> 6);
+          w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+        let mut v = *s;
+        for i in 0..80 {
+          let [a, b, c, d, e, f, g, h] = v;
+          let s1 = e.rotate_right(14) ^ e.rotate_right(18) ^ e.rotate_right(41);
+          let ch = (e & f) ^ (!e & g);
+          let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(SHA512_K[i]).wrapping_add(w[i]);
+          let s0 = a.rotate_right(28) ^ a.rotate_right(34) ^ a.rotate_right(39);
+          let maj = (a & b) ^ (a & c) ^ (b & c);
+          let t2 = s0.wrapping_add(maj);
+          v = [t1.wrapping_add(t2), a, b, c, d.wrapping_add(t1), e, f, g];
+        }
+        for (x, y) in s.iter_mut().zip(v) {
+          *x = x.wrapping_add(y);
+        }
+      }
+      _=> unreachable!()
+    }
+  }
+}
+
+/// HMAC, 只支持分组哈希
+#[derive(Clone)]
+pub struct Hmac {
+  inner: Hasher,
+  /// 外层使用的key ^ opad
+  outer: Vec<u8>
+}
+
+impl Hmac {
+  pub fn new(algo:&str, key:&[u8])-> Hmac {
+    let h = Hasher::new(algo);
+    let block = h.block();
+    assert!(block > 0, "HMAC不支持{}", h.name());
+    let mut key = if key.len() > block {
+      let mut k = h.clone();
+      k.update(key);
+      k.finish()
+    }else {key.to_vec()};
+    key.resize(block, 0);
+    let mut inner = h;
+    inner.update(&key.iter().map(|b|b ^ 0x36).collect::<Vec<_>>());
+    Hmac {inner, outer: key.iter().map(|b|b ^ 0x5c).collect()}
+  }
+
+  pub fn finish(&self)-> Vec<u8> {
+    let mut h = Hasher::new(self.inner.name());
+    h.update(&self.outer);
+    h.update(&self.inner.finish());
+    h.finish()
+  }
+}
+
+/// 实例中保存的哈希, 带key时为HMAC
+#[derive(Clone)]
+enum Digest {
+  Plain(Hasher),
+  Hmac(Hmac)
+}
+impl Digest {
+  fn hasher(&mut self)-> &mut Hasher {
+    match self {
+      Digest::Plain(h)=> h,
+      Digest::Hmac(h)=> &mut h.inner
+    }
+  }
+  fn finish(&self)-> Vec<u8> {
+    match self {
+      Digest::Plain(h)=> h.finish(),
+      Digest::Hmac(h)=> h.finish()
+    }
+  }
+}
+
+/// 哈希实例和用于reset的初始状态
+#[derive(Clone)]
+struct HashInst {
+  cur: Digest,
+  init: Digest
+}
+
+pub fn init()-> (Interned, *mut NativeClassDef) {
+  unsafe {
+    let s = new_static_class(b"Hash", vec![
+      (intern(b"new"), s_new),
+      (intern(b"crc32"), |a, _|one_shot("crc32", a)),
+      (intern(b"adler32"), |a, _|one_shot("adler32", a)),
+      (intern(b"md5"), |a, _|one_shot("md5", a)),
+      (intern(b"sha1"), |a, _|one_shot("sha1", a)),
+      (intern(b"sha256"), |a, _|one_shot("sha256", a)),
+      (intern(b"sha512"), |a, _|one_shot("sha512", a)),
+      (intern(b"hmac"), s_hmac),
+      (intern(b"hex"), s_hex)
+    ]);
+    HASH_CLASS = s.1;
+    let h = &mut *HASH_CLASS;
+    h.methods.push((intern(b"update"), update));
+    h.methods.push((intern(b"finish"), |inst, _, _|Litr::Buf(this(inst.v).cur.finish().into())));
+    h.methods.push((intern(b"hex"), |inst, _, _|Litr::Str(hex(&this(inst.v).cur.finish()).into())));
+    h.methods.push((intern(b"reset"), |inst, _, _|{
+      let h = this(inst.v);
+      h.cur = h.init.clone();
+      Litr::Uninit
+    }));
+    h.getter = getter;
+    h.to_str = |inst|format!("Hash {{ {} }}", this(inst.v).cur.hasher().name());
+    h.onclone = |inst|NativeInstance {
+      cls: inst.cls, v: Box::into_raw(Box::new(this(inst.v).clone())) as usize, w: 0
+    };
+    h.ondrop = |inst|drop(Box::from_raw(inst.v as *mut HashInst));
+    s
+  }
+}
+
+fn this(v:usize)-> &'static mut HashInst {
+  unsafe {&mut *(v as *mut HashInst)}
+}
+
+/// 取出Str或Buf的字节
+fn bytes<'a>(v:Option<&'a CalcRef>, f:&str)-> &'a [u8] {
+  match v.map(|v|&**v) {
+    Some(Litr::Str(s))=> s.as_bytes(),
+    Some(Litr::Buf(b))=> b,
+    _=> panic!("{}需要Str或Buf", f)
+  }
+}
+
+fn algo<'a>(v:Option<&'a CalcRef>, f:&str)-> &'a str {
+  match v.map(|v|&**v) {
+    Some(Litr::Str(s))=> s,
+    _=> panic!("{}第一个参数必须是算法名", f)
+  }
+}
+
+pub fn hex(b:&[u8])-> String {
+  super::codec::hex(b, false)
+}
+
+fn one_shot(name:&str, args:Vec<CalcRef>)-> Litr {
+  let mut h = Hasher::new(name);
+  h.update(bytes(args.get(0), &format!("Hash::{}", name)));
+  Litr::Buf(h.finish().into())
+}
+
+/// Hash::new(算法, key), 传入key时为HMAC
+fn s_new(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let name = algo(args.get(0), "Hash::new");
+  let d = match args.get(1).map(|v|&**v) {
+    None|Some(Litr::Uninit)=> Digest::Plain(Hasher::new(name)),
+    _=> Digest::Hmac(Hmac::new(name, bytes(args.get(1), "Hash::new的key")))
+  };
+  let inst = Box::new(HashInst {cur: d.clone(), init: d});
+  Litr::Ninst(NativeInstance {cls: unsafe{HASH_CLASS}, v: Box::into_raw(inst) as usize, w: 0})
+}
+
+/// Hash::hmac(算法, key, 数据)
+fn s_hmac(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let mut h = Hmac::new(algo(args.get(0), "Hash::hmac"), bytes(args.get(1), "Hash::hmac的key"));
+  h.inner.update(bytes(args.get(2), "Hash::hmac的数据"));
+  Litr::Buf(h.finish().into())
+}
+
+/// 将Buf转为小写十六进制
+fn s_hex(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  Litr::Str(hex(bytes(args.get(0), "Hash::hex")).into())
+}
+
+fn update(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let data = bytes(args.get(0), "Hash.update");
+  this(inst.v).cur.hasher().update(data);
+  Litr::Uninit
+}
+
+fn getter(inst:&NativeInstance, get:Interned)-> Litr {
+  let h = this(inst.v).cur.hasher();
+  match get.vec() {
+    b"algo"=> Litr::Str(h.name().to_string().into()),
+    b"size"=> Litr::Uint(h.size()),
+    _=> Litr::Uninit
+  }
+}
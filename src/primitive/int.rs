@@ -1,6 +1,7 @@
 
 use crate::{intern::{intern, Interned}, native::NativeFn, runtime::{calc::CalcRef, Scope}};
-use super::litr::Litr;
+use super::{litr::Litr, Method, method_table};
+use std::sync::LazyLock;
 
 fn to_u32(n:&Litr)-> u32 {
   match n {
@@ -24,72 +25,167 @@ fn to_usize(n:&Litr)-> usize {
   }
 }
 
-pub fn method_int(n:isize, name:Interned, args:Vec<CalcRef>)-> Litr {
-  macro_rules! get_arg0 {($deal:ident)=> {
-    args.get(0).map_or(0, |n|$deal(n))
-  }}
-  match name.vec() {
-    b"pow"=> args.get(0).map_or(Litr::Int(1), |val|match &**val{
-      Litr::Uint(r)=> Litr::Int(n.pow(*r as _)),
-      Litr::Int(r)=> Litr::Int(n.pow(*r as _)),
-      Litr::Float(r)=> Litr::Float((n as f64).powf(*r)),
-      _=> Litr::Int(1)
-    }),
-    b"log"=> Litr::Uint(n.ilog(get_arg0!(to_isize)) as _),
-    b"log2"=> Litr::Uint(n.ilog2() as _),
-    b"log10"=> Litr::Uint(n.ilog10() as _),
-    
-    b"abs"=> Litr::Uint(n.unsigned_abs()),
-    b"as_buf"=> Litr::Buf(n.to_ne_bytes().to_vec()),
-    b"to_str"=> Litr::Str(n.to_string()),
-    b"to_oct"=> Litr::Str(format!("{:o}", n)),
-    b"to_hex"=> Litr::Str(format!("{:X}", n)),
-    
-    b"min"=> Litr::Int(n.min(get_arg0!(to_isize))),
-    b"max"=> Litr::Int(n.max(get_arg0!(to_isize))),
-    b"rev"=> Litr::Int(n.swap_bytes()),
-    _=> panic!("{}上没有{}方法","Int",name)
+/// 整数溢出时的报错, 可以被try捕获
+fn overflow(op:&str, l:impl std::fmt::Display, r:impl std::fmt::Display, t:&str)-> ! {
+  panic!("整数溢出: {} {} {}的结果超出了{}的范围", l, op, r, t)
+}
+
+/// 整数四则运算, 用i128计算后再检查结果是否在范围内
+fn arith(op:&str, l:i128, r:i128)-> Option<i128> {
+  match op {
+    "+"=> l.checked_add(r),
+    "-"=> l.checked_sub(r),
+    "*"=> l.checked_mul(r),
+    "/"=> {
+      assert!(r != 0, "除数不能为0");
+      l.checked_div(r)
+    }
+    "%"=> {
+      assert!(r != 0, "除数不能为0");
+      l.checked_rem(r)
+    }
+    _=> panic!("{}运算无法应用于整数", op)
   }
 }
 
-pub fn method_uint(n:usize, name:Interned, args:Vec<CalcRef>)-> Litr {
-  macro_rules! get_arg0 {($deal:ident)=> {
-    args.get(0).map_or(0, |n|$deal(n))
-  }}
-  match name.vec() {
-    b"pow"=> args.get(0).map_or(Litr::Uint(1), |val|match &**val{
-      Litr::Uint(r)=> Litr::Uint(n.pow(*r as _)),
-      Litr::Int(r)=> Litr::Uint(n.pow(*r as _)),
-      Litr::Float(r)=> Litr::Float((n as f64).powf(*r)),
-      _=> Litr::Uint(1)
-    }),
-    b"log"=> Litr::Uint(n.ilog(get_arg0!(to_usize)) as _),
-    b"log2"=> Litr::Uint(n.ilog2() as _),
-    b"log10"=> Litr::Uint(n.ilog10() as _),
-
-    b"as_buf"=> Litr::Buf(n.to_ne_bytes().to_vec()),
-    b"as8"=> Litr::Buf(vec![n as u8]),
-    b"as16"=> Litr::Buf(unsafe{std::mem::transmute::<_,[u8;2]>(n as u16)}.to_vec()),
-    b"as32"=> Litr::Buf(unsafe{std::mem::transmute::<_,[u8;4]>(n as u32)}.to_vec()),
-    b"as64"=> Litr::Buf(n.to_ne_bytes().to_vec()),
-    b"to_oct"=> Litr::Str(format!("{:o}", n)),
-    b"to_str"=> Litr::Str(n.to_string()),
-    b"to_hex"=> Litr::Str(format!("{:X}", n)),
-    
-    // bin
-    b"popcnt"=> Litr::Uint(n.count_ones() as _),
-    b"rev"=> Litr::Uint(n.swap_bytes()),
-    b"leading"=> Litr::Uint(n.leading_zeros() as _),
-    b"ending"=> Litr::Uint(n.trailing_zeros() as _),
-    b"rotate_left"=> Litr::Uint(n.rotate_left(get_arg0!(to_u32))),
-    b"rotate_right"=> Litr::Uint(n.rotate_right(get_arg0!(to_u32))),
-    
-    b"min"=> Litr::Uint(n.min(get_arg0!(to_usize))),
-    b"max"=> Litr::Uint(n.max(get_arg0!(to_usize))),
-    _=> panic!("{}上没有{}方法","Uint",name)
+/// 结果为Int的运算, 溢出时报错, debug和release下行为一致
+pub fn calc_int(op:&str, l:i128, r:i128)-> isize {
+  arith(op, l, r).and_then(|n|isize::try_from(n).ok())
+    .unwrap_or_else(||overflow(op, l, r, "Int"))
+}
+
+/// 结果为Uint的运算, 溢出时报错, debug和release下行为一致
+pub fn calc_uint(op:&str, l:i128, r:i128)-> usize {
+  arith(op, l, r).and_then(|n|usize::try_from(n).ok())
+    .unwrap_or_else(||overflow(op, l, r, "Uint"))
+}
+
+/// Uint的位运算, 位移量超出位数时报错
+pub fn bits_uint(op:&str, l:usize, r:usize)-> usize {
+  let shift = ||u32::try_from(r).ok().filter(|r|*r < usize::BITS)
+    .unwrap_or_else(||panic!("位移量{}超出了Uint的位数", r));
+  match op {
+    "<<"=> l << shift(),
+    ">>"=> l >> shift(),
+    "&"=> l & r,
+    "|"=> l | r,
+    "^"=> l ^ r,
+    _=> panic!("{}运算无法应用于Uint", op)
   }
 }
 
+fn arg_isize(args:&[CalcRef])-> isize {
+  args.get(0).map_or(0, |n|to_isize(n))
+}
+fn arg_usize(args:&[CalcRef])-> usize {
+  args.get(0).map_or(0, |n|to_usize(n))
+}
+fn arg_u32(args:&[CalcRef])-> u32 {
+  args.get(0).map_or(0, |n|to_u32(n))
+}
+
+/// 明确溢出行为的方法: wrapping回绕, saturating取边界, checked溢出时返回uninit
+macro_rules! overflow_methods {($t:ident, $r:ident)=> {[
+  (b"wrapping_add", |n, args, _|Litr::$t(n.wrapping_add($r(&args)))),
+  (b"wrapping_sub", |n, args, _|Litr::$t(n.wrapping_sub($r(&args)))),
+  (b"wrapping_mul", |n, args, _|Litr::$t(n.wrapping_mul($r(&args)))),
+  (b"wrapping_pow", |n, args, _|Litr::$t(n.wrapping_pow(arg_u32(&args)))),
+  (b"wrapping_shl", |n, args, _|Litr::$t(n.wrapping_shl(arg_u32(&args)))),
+  (b"wrapping_shr", |n, args, _|Litr::$t(n.wrapping_shr(arg_u32(&args)))),
+  (b"saturating_add", |n, args, _|Litr::$t(n.saturating_add($r(&args)))),
+  (b"saturating_sub", |n, args, _|Litr::$t(n.saturating_sub($r(&args)))),
+  (b"saturating_mul", |n, args, _|Litr::$t(n.saturating_mul($r(&args)))),
+  (b"saturating_pow", |n, args, _|Litr::$t(n.saturating_pow(arg_u32(&args)))),
+  (b"checked_add", |n, args, _|n.checked_add($r(&args)).map_or(Litr::Uninit, Litr::$t)),
+  (b"checked_sub", |n, args, _|n.checked_sub($r(&args)).map_or(Litr::Uninit, Litr::$t)),
+  (b"checked_mul", |n, args, _|n.checked_mul($r(&args)).map_or(Litr::Uninit, Litr::$t)),
+  (b"checked_div", |n, args, _|n.checked_div($r(&args)).map_or(Litr::Uninit, Litr::$t)),
+  (b"checked_rem", |n, args, _|n.checked_rem($r(&args)).map_or(Litr::Uninit, Litr::$t)),
+  (b"checked_pow", |n, args, _|n.checked_pow(arg_u32(&args)).map_or(Litr::Uninit, Litr::$t)),
+  (b"checked_shl", |n, args, _|n.checked_shl(arg_u32(&args)).map_or(Litr::Uninit, Litr::$t)),
+  (b"checked_shr", |n, args, _|n.checked_shr(arg_u32(&args)).map_or(Litr::Uninit, Litr::$t)),
+]}}
+
+/// Int的方法表
+static INT_METHODS:LazyLock<Vec<(Interned, Method<isize>)>> = LazyLock::new(||{
+  let mut v = method_table(&[
+    (b"pow", |n, args, _|pow_int(*n, args)),
+    (b"log", |n, args, _|Litr::Uint(n.ilog(arg_isize(&args)) as _)),
+    (b"log2", |n, _, _|Litr::Uint(n.ilog2() as _)),
+    (b"log10", |n, _, _|Litr::Uint(n.ilog10() as _)),
+
+    (b"abs", |n, _, _|Litr::Uint(n.unsigned_abs())),
+    (b"as_buf", |n, _, _|Litr::Buf(n.to_ne_bytes().to_vec().into())),
+    (b"to_str", |n, _, _|Litr::Str(n.to_string().into())),
+    (b"to_oct", |n, _, _|Litr::Str(format!("{:o}", n).into())),
+    (b"to_hex", |n, _, _|Litr::Str(format!("{:X}", n).into())),
+
+    (b"min", |n, args, _|Litr::Int((*n).min(arg_isize(&args)))),
+    (b"max", |n, args, _|Litr::Int((*n).max(arg_isize(&args)))),
+    (b"rev", |n, _, _|Litr::Int(n.swap_bytes())),
+    (b"big", |n, _, _|Litr::BigInt(super::bigint::BigInt::from_i128(*n as i128))),
+  ]);
+  v.extend(method_table::<isize>(&overflow_methods!(Int, arg_isize)));
+  v
+});
+pub fn int_methods()-> &'static [(Interned, Method<isize>)] {
+  &INT_METHODS
+}
+
+fn pow_int(n:isize, args:Vec<CalcRef>)-> Litr {
+  args.get(0).map_or(Litr::Int(1), |val|match &**val{
+    Litr::Uint(r)=> Litr::Int(n.checked_pow(*r as _).unwrap_or_else(||overflow("pow", n, r, "Int"))),
+    Litr::Int(r)=> Litr::Int(n.checked_pow(*r as _).unwrap_or_else(||overflow("pow", n, r, "Int"))),
+    Litr::Float(r)=> Litr::Float((n as f64).powf(*r)),
+    _=> Litr::Int(1)
+  })
+}
+
+/// Uint的方法表
+static UINT_METHODS:LazyLock<Vec<(Interned, Method<usize>)>> = LazyLock::new(||{
+  let mut v = method_table(&[
+    (b"pow", |n, args, _|pow_uint(*n, args)),
+    (b"log", |n, args, _|Litr::Uint(n.ilog(arg_usize(&args)) as _)),
+    (b"log2", |n, _, _|Litr::Uint(n.ilog2() as _)),
+    (b"log10", |n, _, _|Litr::Uint(n.ilog10() as _)),
+
+    (b"as_buf", |n, _, _|Litr::Buf(n.to_ne_bytes().to_vec().into())),
+    (b"as8", |n, _, _|Litr::Buf(vec![*n as u8].into())),
+    (b"as16", |n, _, _|Litr::Buf((*n as u16).to_ne_bytes().to_vec().into())),
+    (b"as32", |n, _, _|Litr::Buf((*n as u32).to_ne_bytes().to_vec().into())),
+    (b"as64", |n, _, _|Litr::Buf(n.to_ne_bytes().to_vec().into())),
+    (b"to_oct", |n, _, _|Litr::Str(format!("{:o}", n).into())),
+    (b"to_str", |n, _, _|Litr::Str(n.to_string().into())),
+    (b"to_hex", |n, _, _|Litr::Str(format!("{:X}", n).into())),
+
+    // bin
+    (b"popcnt", |n, _, _|Litr::Uint(n.count_ones() as _)),
+    (b"rev", |n, _, _|Litr::Uint(n.swap_bytes())),
+    (b"leading", |n, _, _|Litr::Uint(n.leading_zeros() as _)),
+    (b"ending", |n, _, _|Litr::Uint(n.trailing_zeros() as _)),
+    (b"rotate_left", |n, args, _|Litr::Uint(n.rotate_left(arg_u32(&args)))),
+    (b"rotate_right", |n, args, _|Litr::Uint(n.rotate_right(arg_u32(&args)))),
+
+    (b"min", |n, args, _|Litr::Uint((*n).min(arg_usize(&args)))),
+    (b"max", |n, args, _|Litr::Uint((*n).max(arg_usize(&args)))),
+    (b"big", |n, _, _|Litr::BigInt(super::bigint::BigInt::from_u128(*n as u128))),
+  ]);
+  v.extend(method_table::<usize>(&overflow_methods!(Uint, arg_usize)));
+  v
+});
+pub fn uint_methods()-> &'static [(Interned, Method<usize>)] {
+  &UINT_METHODS
+}
+
+fn pow_uint(n:usize, args:Vec<CalcRef>)-> Litr {
+  args.get(0).map_or(Litr::Uint(1), |val|match &**val{
+    Litr::Uint(r)=> Litr::Uint(n.checked_pow(*r as _).unwrap_or_else(||overflow("pow", n, r, "Uint"))),
+    Litr::Int(r)=> Litr::Uint(n.checked_pow(*r as _).unwrap_or_else(||overflow("pow", n, r, "Uint"))),
+    Litr::Float(r)=> Litr::Float((n as f64).powf(*r)),
+    _=> Litr::Uint(1)
+  })
+}
+
 // - statics int -
 pub fn statics_int()-> Vec<(Interned, NativeFn)> {
   vec![
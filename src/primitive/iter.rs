@@ -1,7 +1,7 @@
 use crate::{
   intern::intern, 
   native::NativeInstance, 
-  primitive::litr::{Litr, LocalFunc},
+  primitive::litr::{Litr, LocalFunc, Shared},
   runtime::Scope
 };
 
@@ -41,17 +41,38 @@ impl Iterator for NativeInstanceIter<'_> {
   }
 }
 
+/// 持有Str, Buf, List或Obj的副本按下标遍历
+/// 
+/// 副本只增加引用计数, 遍历中修改原值会先复制一份, 不会影响正在遍历的数据
+fn snapshot<T:Clone + 'static>(v:&Shared<T>, mut next:impl FnMut(&T, &mut usize)-> Option<Litr> + 'static)-> Box<dyn Iterator<Item = Litr>> {
+  let v = v.clone();
+  let mut i = 0;
+  Box::new(std::iter::from_fn(move ||next(&v, &mut i)))
+}
+
 pub struct LitrIterator<'a> {
   inner: Box<dyn Iterator<Item = Litr> + 'a>
 }
 impl<'a> LitrIterator<'a> {
   pub fn new(v:&'a mut Litr)-> Self {
     let inner:Box<dyn Iterator<Item = Litr>> = match v {
-      Litr::Str(s)=> Box::new(s.chars().map(|c|Litr::Str(c.to_string()))),
-      Litr::Buf(v)=> Box::new(v.iter().map(|n|Litr::Uint((*n) as usize))),
+      Litr::Str(s)=> snapshot(s, |s, i|{
+        let c = s[*i..].chars().next()?;
+        *i += c.len_utf8();
+        Some(Litr::Str(c.to_string().into()))
+      }),
+      Litr::Buf(v)=> snapshot(v, |v, i|{
+        let n = *v.get(*i)?;
+        *i += 1;
+        Some(Litr::Uint(n as usize))
+      }),
       Litr::Uint(n)=> Box::new((0..*n).into_iter().map(|n|Litr::Uint(n))),
       Litr::Int(n)=> Box::new((0..*n).into_iter().map(|n|Litr::Int(n))),
-      Litr::List(v)=> Box::new(v.iter().cloned()),
+      Litr::List(v)=> snapshot(v, |v, i|{
+        let n = v.get(*i)?.clone();
+        *i += 1;
+        Some(n)
+      }),
       Litr::Inst(inst)=> {
         let f = & unsafe{&*inst.cls}.methods.iter()
           .find(|f|f.f.name == intern(b"@next"))
@@ -59,16 +80,23 @@ impl<'a> LitrIterator<'a> {
         let f = LocalFunc::new(f, unsafe{&*inst.cls}.cx);
         Box::new(InstanceIter { f, kself:v })
       }
-      Litr::Ninst(inst) => {
+      Litr::Ninst(inst) => if super::collection::is_collection(inst) {
+        super::collection::iter(inst)
+      }else {
         let f = unsafe {&*inst.cls}.next;
         Box::new(NativeInstanceIter {f, kself:inst})
       },
-      Litr::Obj(o) => Box::new(o.keys()
-        .map(|n|Litr::Str(unsafe{String::from_utf8_unchecked(n.vec().to_vec())}))),
+      Litr::Obj(o) => snapshot(o, |o, i|{
+        let (n, (k, _)) = o.slot_from(*i)?;
+        *i = n + 1;
+        Some(Litr::Str(unsafe{String::from_utf8_unchecked(k.vec().to_vec()).into()}))
+      }),
       Litr::Bool(_) => panic!("Bool无法迭代"),
       Litr::Func(_) => panic!("Func无法迭代"),
       Litr::Float(_) => panic!("Float无法迭代"),
+      Litr::BigInt(_) => panic!("BigInt无法迭代"),
       Litr::Uninit => panic!("给uninit迭代?死刑!"),
+      Litr::Enum(_) => panic!("Enum无法迭代"),
     };
     LitrIterator { inner }
   }
@@ -80,3 +108,284 @@ impl<'a> Iterator for LitrIterator<'a> {
     self.inner.next()
   }
 }
+
+
+// - Iter类 -
+use std::collections::VecDeque;
+use std::mem::ManuallyDrop;
+use crate::{
+  intern::Interned,
+  native::NativeClassDef,
+  primitive::litr::Function,
+  runtime::{calc::CalcRef, outlive}
+};
+
+pub static mut ITER_CLASS: *mut NativeClassDef = std::ptr::null_mut();
+
+/// Iter实例中保存的惰性迭代器
+type Inner = Box<dyn Iterator<Item = Litr>>;
+
+/// 持有被迭代值所有权的迭代器
+struct OwnedIter {
+  /// 借用了src, 必须先于src释放
+  itr: ManuallyDrop<LitrIterator<'static>>,
+  src: *mut Litr
+}
+impl Iterator for OwnedIter {
+  type Item = Litr;
+  fn next(&mut self) -> Option<Self::Item> {
+    self.itr.next()
+  }
+}
+impl Drop for OwnedIter {
+  fn drop(&mut self) {
+    unsafe {
+      ManuallyDrop::drop(&mut self.itr);
+      drop(Box::from_raw(self.src));
+    }
+  }
+}
+
+/// 将任意可迭代的值转为惰性迭代器, Iter实例会直接取走其内部迭代器
+fn to_inner(mut v:Litr)-> Inner {
+  if let Litr::Ninst(inst) = &mut v {
+    if inst.cls == unsafe{ITER_CLASS} {
+      return take_inner(inst);
+    }
+  }
+  let src = Box::into_raw(Box::new(v));
+  let itr = ManuallyDrop::new(LitrIterator::new(unsafe{&mut *src}));
+  Box::new(OwnedIter {itr, src})
+}
+
+/// 取走Iter实例的迭代器, 原实例变为空迭代器
+fn take_inner(inst:&mut NativeInstance)-> Inner {
+  let inner = unsafe {&mut *(inst.v as *mut Inner)};
+  std::mem::replace(inner, Box::new(std::iter::empty()))
+}
+
+fn new_iter(inner:Inner)-> Litr {
+  Litr::Ninst(NativeInstance {
+    cls: unsafe{ITER_CLASS},
+    v: Box::into_raw(Box::new(inner)) as usize, w: 0
+  })
+}
+
+/// 惰性适配器中保存的函数
+/// 
+/// 迭代发生时调用处的作用域可能已经结束, 因此需要为其增加一层引用计数
+struct Callback {
+  f: Function,
+  cx: Scope
+}
+impl Callback {
+  fn new(args:&Vec<CalcRef>, cx:Scope, name:&str)-> Self {
+    let f = match &**args.get(0).unwrap_or_else(||panic!("iter.{}需要一个函数作为参数", name)) {
+      Litr::Func(f)=> f.clone(),
+      _=> panic!("iter.{}第一个参数只能传函数", name)
+    };
+    outlive::increase_scope_count(cx);
+    Callback {f, cx}
+  }
+  fn call(&self, v:&mut Litr)-> Litr {
+    self.cx.call(vec![CalcRef::Ref(v)], &self.f)
+  }
+}
+impl Drop for Callback {
+  fn drop(&mut self) {
+    outlive::decrease_scope_count(self.cx)
+  }
+}
+
+/// 以Callback的返回值判断元素去留
+fn test(f:&Callback, v:&mut Litr)-> bool {
+  match f.call(v) {
+    Litr::Bool(b)=> b,
+    _=> false
+  }
+}
+
+fn to_usize(args:&Vec<CalcRef>, name:&str)-> usize {
+  match args.get(0).map(|n|&**n) {
+    Some(Litr::Uint(n))=> *n,
+    Some(Litr::Int(n))=> *n as usize,
+    _=> panic!("iter.{}需要一个整数作为参数", name)
+  }
+}
+
+/// 每次产出最近n个元素的列表
+struct Windows {
+  itr: Inner,
+  buf: VecDeque<Litr>,
+  n: usize
+}
+impl Iterator for Windows {
+  type Item = Litr;
+  fn next(&mut self) -> Option<Self::Item> {
+    if self.buf.len() == self.n {
+      self.buf.pop_front();
+    }
+    while self.buf.len() < self.n {
+      self.buf.push_back(self.itr.next()?);
+    }
+    Some(Litr::List(self.buf.iter().cloned().collect()))
+  }
+}
+
+pub fn init()-> (Interned, *mut NativeClassDef) {
+  unsafe {
+    let s = super::new_static_class(b"Iter", vec![
+      (intern(b"from"), s_from)
+    ]);
+    ITER_CLASS = s.1;
+    let cls = &mut *ITER_CLASS;
+    cls.methods = vec![
+      // 适配器
+      (intern(b"map"), map),
+      (intern(b"filter"), filter),
+      (intern(b"take"), take),
+      (intern(b"skip"), skip),
+      (intern(b"step_by"), step_by),
+      (intern(b"zip"), zip),
+      (intern(b"enumerate"), enumerate),
+      (intern(b"chain"), chain),
+      (intern(b"flat_map"), flat_map),
+      (intern(b"windows"), windows),
+      // 消耗
+      (intern(b"next"), |inst,_,_|next(inst)),
+      (intern(b"collect"), collect),
+      (intern(b"collect_buf"), collect_buf),
+      (intern(b"collect_obj"), collect_obj),
+      (intern(b"fold"), fold),
+      (intern(b"count"), |inst,_,_|Litr::Uint(take_inner(inst).count())),
+      (intern(b"any"), any),
+      (intern(b"all"), all),
+    ];
+    cls.next = next;
+    cls.onclone = |_|panic!("无法复制Iter. 请考虑用take函数代替");
+    cls.ondrop = |inst|drop(Box::from_raw(inst.v as *mut Inner));
+    s
+  }
+}
+
+/// 将可迭代的值包装为Iter
+fn s_from(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let v = args.into_iter().next().expect("Iter::from需要一个可迭代的值").own();
+  new_iter(to_inner(v))
+}
+
+fn next(inst:&mut NativeInstance)-> Litr {
+  let inner = unsafe {&mut *(inst.v as *mut Inner)};
+  inner.next().unwrap_or_else(super::sym::iter_end)
+}
+
+fn map(inst:&mut NativeInstance, args:Vec<CalcRef>, cx:Scope)-> Litr {
+  let f = Callback::new(&args, cx, "map");
+  new_iter(Box::new(take_inner(inst).map(move |mut v|f.call(&mut v))))
+}
+
+fn filter(inst:&mut NativeInstance, args:Vec<CalcRef>, cx:Scope)-> Litr {
+  let f = Callback::new(&args, cx, "filter");
+  new_iter(Box::new(take_inner(inst).filter_map(move |mut v|test(&f, &mut v).then_some(v))))
+}
+
+fn take(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let n = to_usize(&args, "take");
+  new_iter(Box::new(take_inner(inst).take(n)))
+}
+
+fn skip(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let n = to_usize(&args, "skip");
+  new_iter(Box::new(take_inner(inst).skip(n)))
+}
+
+fn step_by(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let n = to_usize(&args, "step_by");
+  assert!(n > 0, "iter.step_by的步长不可为0");
+  new_iter(Box::new(take_inner(inst).step_by(n)))
+}
+
+/// 和另一个可迭代值一一配对为[a, b]
+fn zip(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let other = args.into_iter().next().expect("iter.zip需要一个可迭代的值").own();
+  new_iter(Box::new(take_inner(inst).zip(to_inner(other))
+    .map(|(a, b)|Litr::List(vec![a, b].into()))))
+}
+
+/// 产出[序号, 值]
+fn enumerate(inst:&mut NativeInstance, _args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  new_iter(Box::new(take_inner(inst).enumerate()
+    .map(|(i, v)|Litr::List(vec![Litr::Uint(i), v].into()))))
+}
+
+fn chain(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let mut itr = take_inner(inst);
+  for other in args {
+    itr = Box::new(itr.chain(to_inner(other.own())));
+  }
+  new_iter(itr)
+}
+
+/// 函数的返回值会被展开迭代
+fn flat_map(inst:&mut NativeInstance, args:Vec<CalcRef>, cx:Scope)-> Litr {
+  let f = Callback::new(&args, cx, "flat_map");
+  new_iter(Box::new(take_inner(inst).flat_map(move |mut v|to_inner(f.call(&mut v)))))
+}
+
+fn windows(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let n = to_usize(&args, "windows");
+  assert!(n > 0, "iter.windows的窗口大小不可为0");
+  new_iter(Box::new(Windows {itr: take_inner(inst), buf: VecDeque::with_capacity(n), n}))
+}
+
+fn collect(inst:&mut NativeInstance, _args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  Litr::List(take_inner(inst).collect())
+}
+
+/// 元素需要是0到255之间的整数
+fn collect_buf(inst:&mut NativeInstance, _args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  Litr::Buf(take_inner(inst).map(|n|match n {
+    Litr::Int(n) if (0..256).contains(&n)=> n as u8,
+    Litr::Uint(n) if n < 256=> n as u8,
+    n=> panic!("iter.collect_buf的元素需要是0到255的整数, 但遇到了{}", n.str())
+  }).collect())
+}
+
+/// 元素需要是[key, value]的列表, 和Obj::from_list一样会跳过键不是Str的元素
+fn collect_obj(inst:&mut NativeInstance, _args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let mut o = super::objmap::ObjMap::new();
+  for v in take_inner(inst) {
+    if let Litr::List(v) = v {
+      let mut v = v.into_iter();
+      let key = match v.next() {
+        Some(Litr::Str(s))=> intern(s.as_bytes()),
+        _=> continue
+      };
+      o.insert(key, v.next().unwrap_or(Litr::Uninit));
+    }
+  }
+  Litr::Obj(o.into())
+}
+
+fn fold(inst:&mut NativeInstance, args:Vec<CalcRef>, cx:Scope)-> Litr {
+  let mut args = args.into_iter();
+  let init = args.next().expect("iter.fold需要一个初始值").own();
+  let f_ = args.next().expect("iter.fold需要第二个参数的函数来处理数据");
+  let f = match &*f_ {
+    Litr::Func(f)=> f,
+    _=> panic!("iter.fold第二个参数只能是函数")
+  };
+  take_inner(inst).fold(init, |a, b|
+    cx.call(vec![CalcRef::Own(a), CalcRef::Own(b)], f)
+  )
+}
+
+fn any(inst:&mut NativeInstance, args:Vec<CalcRef>, cx:Scope)-> Litr {
+  let f = Callback::new(&args, cx, "any");
+  Litr::Bool(take_inner(inst).any(|mut v|test(&f, &mut v)))
+}
+
+fn all(inst:&mut NativeInstance, args:Vec<CalcRef>, cx:Scope)-> Litr {
+  let f = Callback::new(&args, cx, "all");
+  Litr::Bool(take_inner(inst).all(|mut v|test(&f, &mut v)))
+}
@@ -0,0 +1,29 @@
+//! Bool类型的方法
+
+use super::*;
+use std::sync::LazyLock;
+
+/// Bool的方法表
+static METHODS:LazyLock<Vec<(Interned, Method<bool>)>> = LazyLock::new(||method_table(&[
+  (b"rev", |v, _, _|Litr::Bool(!*v)),
+  (b"then", |v, args, cx|then(*v, args, cx)),
+]));
+pub fn methods()-> &'static [(Interned, Method<bool>)] {
+  &METHODS
+}
+
+/// 为true时调用传入的函数
+fn then(v:bool, args:Vec<CalcRef>, cx:Scope)-> Litr {
+  let f = match args.get(0) {
+    Some(f)=> match &**f {
+      Litr::Func(f)=> f,
+      _=> panic!("bool.then第一个参数必须是函数")
+    },
+    None=> return Litr::Uninit
+  };
+  if v {
+    cx.call(vec![], f)
+  }else {
+    Litr::Uninit
+  }
+}
@@ -31,8 +31,14 @@ fn log(args:Vec<CalcRef>, _cx:Scope)-> Litr {
 }
 
 /// debug的格式输出
+/// 
+/// 实例会使用@debug方法, 原生实例会使用to_str
 fn debug(args:Vec<CalcRef>, _cx:Scope)-> Litr {
-  args.iter().for_each(|v|println!("{:?}", &**v));
+  args.iter().for_each(|v|match &**v {
+    Litr::Inst(inst)=> println!("{:?}", inst),
+    Litr::Ninst(inst)=> println!("{:?}", inst),
+    v=> println!("{:?}", v)
+  });
   Litr::Uninit
 }
 
@@ -55,7 +61,7 @@ fn run_ks(args:Vec<CalcRef>, mut cx:Scope)-> Litr {
     // 解析并运行
     let scanned = crate::scan::scan(s);
     for (l, sm) in &scanned.v {
-      crate::LINE = *l;
+      crate::LINE = l.end;
       cx.evil(sm);
       // 如果evil到return或break就在这停下
       if cx.ended {
@@ -77,7 +83,7 @@ fn version(_a:Vec<CalcRef>, _c:Scope)-> Litr {
 
 /// 获取发行者
 fn distribution(_a:Vec<CalcRef>, _c:Scope)-> Litr {
-  Litr::Str(crate::DISTRIBUTION.to_string())
+  Litr::Str(crate::DISTRIBUTION.to_string().into())
 }
 
 /// 无分配的直接交互数值
@@ -98,16 +104,11 @@ fn take(mut args:Vec<CalcRef>, _c:Scope)-> Litr {
 
 /// format
 /// 
-/// 用`"{}", "{}"`打印`{}`
-fn fmt(args:Vec<CalcRef>, _cx:Scope)-> Litr {
-  let mut args = args.into_iter();
-  let mut fmtr = match args.next() {
+/// 用`"{}", "{}"`打印`{}`, 格式的写法见[super::format]
+pub fn fmt(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let fmtr = match args.get(0) {
     Some(s)=> s.str(),
-    _=> return Litr::Str(String::new())
+    _=> return Litr::Str(String::new().into())
   };
-
-  while let Some(s) = args.next() {
-    fmtr = fmtr.replacen("{}", &s.str(), 1);
-  }
-  Litr::Str(fmtr)
+  Litr::Str(super::format::format(&fmtr, &args[1..]).into())
 }
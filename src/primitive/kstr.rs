@@ -1,6 +1,7 @@
 //! key的str实现
 
 use super::*;
+use std::sync::LazyLock;
 
 fn to_usize(n:&Litr)-> usize {
   match n {
@@ -12,69 +13,76 @@ fn to_usize(n:&Litr)-> usize {
 
 static mut ITER_LINES: *mut NativeClassDef = std::ptr::null_mut();
 
-pub fn method(s:&mut String, _scope:Scope, name:Interned, args:Vec<CalcRef>)-> Litr {
-  macro_rules! get_arg0 {
-    // 解析为usize
-    (usize)=> {
-      args.get(0).map_or(0, |n|to_usize(n))
-    };
-    // 解析为该字符索引
-    (index)=> {{
-      let n = get_arg0!(usize);
-      s.char_indices().nth(n).unwrap_or_else(||panic!("索引{}超出字符范围", n)).0
-    }};
-    // 解析为字符
-    (str $err:literal)=> {
-      match &**args.get(0).unwrap_or_else(||panic!("str.{}第一个参数必须是Str",$err)) {
-        Litr::Str(s)=> s,
-        _=> panic!("str.{}第一个参数必须是Str",$err)
-      }
+macro_rules! get_arg0 {
+  // 解析为usize
+  ($args:ident usize)=> {
+    $args.get(0).map_or(0, |n|to_usize(n))
+  };
+  // 解析为该字符索引
+  ($s:ident $args:ident index)=> {{
+    let n = get_arg0!($args usize);
+    $s.char_indices().nth(n).unwrap_or_else(||panic!("索引{}超出字符范围", n)).0
+  }};
+  // 解析为字符
+  ($args:ident str $err:literal)=> {
+    match &**$args.get(0).unwrap_or_else(||panic!("str.{}第一个参数必须是Str",$err)) {
+      Litr::Str(s)=> s.as_str(),
+      _=> panic!("str.{}第一个参数必须是Str",$err)
     }
   }
+}
 
-  /// 直接返回Litr::Uninit, 用小括号去减少花括号
-  macro_rules! void {($v:expr)=>{{
-    $v;
-    Litr::Uninit
-  }}}
-
-  match name.vec() {
-    // search
-    b"includes"=> Litr::Bool(s.contains(get_arg0!(str "includes"))),
-    b"start_with"=> Litr::Bool(s.starts_with(get_arg0!(str "start_with"))),
-    b"ends_with"=> Litr::Bool(s.ends_with(get_arg0!(str "ends_with"))),
-    b"index_of"=> index_of(s, args),
-    b"r_index_of"=> r_index_of(s, args),
-
-    // edit
-    b"cut"=> Litr::Str(s.split_off(get_arg0!(index))),
-    b"insert"=> insert(s, args),
-    b"remove"=> remove(s, args),
-    b"push"=> void!(s.push_str(get_arg0!(str "push"))),
-    b"slice"=> Litr::Str(_slice(s, args)),
-    b"to_lcase"=> Litr::Str({let mut s=s.clone();s.make_ascii_lowercase();s}),
-    b"to_ucase"=> Litr::Str({let mut s=s.clone();s.make_ascii_lowercase();s}),
-    b"rev"=> Litr::Str(s.chars().rev().collect()),
-    b"repeat"=> Litr::Str(s.repeat(get_arg0!(usize))),
-    b"replace"=> Litr::Str(_replace(s, args)),
-    b"splice"=> splice(s, args),
-    b"trim"=> Litr::Str(s.trim().to_string()),
-
-    // transmute
-    b"to_buf"=> Litr::Buf(s.as_bytes().to_vec()),
-    b"to_utf16"=> to_utf16(s),
-    b"split"=> split(s, args),
-
-    b"case_eq"=> Litr::Bool(s.eq_ignore_ascii_case(get_arg0!(str "englisheq"))),
-    b"lines"=> lines(s),
-    
-    _=> panic!("Str上没有{}方法", name)
-  }
+/// 直接返回Litr::Uninit, 用小括号去减少花括号
+macro_rules! void {($v:expr)=>{{
+  $v;
+  Litr::Uninit
+}}}
+
+/// Str的方法表
+static METHODS:LazyLock<Vec<(Interned, Method<Shared<String>>)>> = LazyLock::new(||method_table(&[
+  // search
+  (b"includes", |s, args, _|Litr::Bool(s.contains(get_arg0!(args str "includes")))),
+  (b"start_with", |s, args, _|Litr::Bool(s.starts_with(get_arg0!(args str "start_with")))),
+  (b"ends_with", |s, args, _|Litr::Bool(s.ends_with(get_arg0!(args str "ends_with")))),
+  (b"index_of", |s, args, _|index_of(s, args)),
+  (b"r_index_of", |s, args, _|r_index_of(s, args)),
+
+  // edit
+  (b"cut", |s, args, _|{
+    let i = get_arg0!(s args index);
+    Litr::Str(s.split_off(i).into())
+  }),
+  (b"insert", |s, args, _|insert(s, args)),
+  (b"remove", |s, args, _|remove(s, args)),
+  (b"push", |s, args, _|void!(s.push_str(get_arg0!(args str "push")))),
+  (b"slice", |s, args, _|Litr::Str(_slice(s, args).into())),
+  (b"to_lcase", |s, _, _|Litr::Str({let mut s=s.clone();s.make_ascii_lowercase();s})),
+  (b"to_ucase", |s, _, _|Litr::Str({let mut s=s.clone();s.make_ascii_lowercase();s})),
+  (b"rev", |s, _, _|Litr::Str(s.chars().rev().collect())),
+  (b"repeat", |s, args, _|Litr::Str(s.repeat(get_arg0!(args usize)).into())),
+  (b"replace", |s, args, _|Litr::Str(_replace(s, args).into())),
+  (b"splice", |s, args, _|splice(s, args)),
+  (b"trim", |s, _, _|Litr::Str(s.trim().to_string().into())),
+
+  // transmute
+  (b"to_buf", |s, _, _|Litr::Buf(s.as_bytes().to_vec().into())),
+  (b"to_utf16", |s, _, _|to_utf16(s)),
+  (b"split", |s, args, _|split(s, args)),
+  (b"to_base64", |s, args, _|codec::to_base64(s.as_bytes(), args)),
+  (b"to_hex", |s, args, _|codec::to_hex(s.as_bytes(), args)),
+  (b"to_percent", |s, args, _|codec::to_percent(s.as_bytes(), args)),
+
+  (b"case_eq", |s, args, _|Litr::Bool(s.eq_ignore_ascii_case(get_arg0!(args str "englisheq")))),
+  (b"lines", |s, _, _|lines(s)),
+]));
+
+pub fn methods()-> &'static [(Interned, Method<Shared<String>>)] {
+  &METHODS
 }
 
 macro_rules! _index_of {($s:ident,$args:ident,$id:ident)=> {{
   let find = match &**$args.get(0).expect("str.index_of需要知道你找的字符串") {
-    Litr::Str(s)=> s,
+    Litr::Str(s)=> s.as_str(),
     _=> panic!("str.index_of第一个参数必须是Str",)
   };
   Litr::Int(
@@ -95,12 +103,12 @@ macro_rules! _index_of {($s:ident,$args:ident,$id:ident)=> {{
 }}}
 
 /// 寻找第一个该字符的索引
-fn index_of(s:&mut String, args:Vec<CalcRef>)-> Litr {
+fn index_of(s:&String, args:Vec<CalcRef>)-> Litr {
   _index_of!(s,args,find)
 }
 
 /// 寻找倒数第一个该字符的索引
-fn r_index_of(s:&mut String, args:Vec<CalcRef>)-> Litr {
+fn r_index_of(s:&String, args:Vec<CalcRef>)-> Litr {
   _index_of!(s,args,rfind)
 }
 
@@ -116,7 +124,7 @@ fn remove(s:&mut String, args:Vec<CalcRef>)-> Litr {
     let len = to_usize(len) - 1;
     let len = indice.nth(len).unwrap_or((s.len(),'\x00')).0;
     s.drain(index..len).collect()
-  }else {s.remove(index).to_string()})
+  }else {s.remove(index).to_string().into()})
 }
 
 /// 在索引处插入一段字符
@@ -126,7 +134,7 @@ fn insert(s:&mut String, args:Vec<CalcRef>)-> Litr {
   let index = indice.nth(to_usize(args.get(0).unwrap())).unwrap_or_else(||panic!("字符索引超出字符范围")).0;
 
   let to_insert = match &**args.get(1).unwrap() {
-    Litr::Str(s)=> s,
+    Litr::Str(s)=> s.as_str(),
     _=> panic!("str.insert第二个参数必须是Str")
   };
 
@@ -135,7 +143,7 @@ fn insert(s:&mut String, args:Vec<CalcRef>)-> Litr {
 }
 
 /// slice函数的内部函数
-fn _slice(s:&mut String, args:Vec<CalcRef>)-> String {
+fn _slice(s:&String, args:Vec<CalcRef>)-> String {
   let mut indice = s.char_indices();
   let start = args.get(0).map_or(0, |n|to_usize(n));
   let end = args.get(1).map_or(s.len(), |n|to_usize(n));
@@ -148,30 +156,30 @@ fn _slice(s:&mut String, args:Vec<CalcRef>)-> String {
 }
 
 /// str转utf16 buf
-fn to_utf16(s:&mut String)-> Litr {
+fn to_utf16(s:&String)-> Litr {
   let v16:Vec<u16> = s.encode_utf16().collect();
   unsafe {
     Litr::Buf(std::slice::from_raw_parts(
       v16.as_ptr() as *const u8, v16.len() * 2
-    ).to_vec())
+    ).to_vec().into())
   }
 }
 
 /// 得到一个按行的迭代器
-fn lines(s:&mut String)-> Litr {
+fn lines(s:&String)-> Litr {
   let v = Box::into_raw(Box::new(s.lines())) as usize;
   Litr::Ninst(NativeInstance {cls:unsafe{ITER_LINES},v,w:0})
 }
 
 /// 替换所有匹配字符 可传入第三个参数代表替换次数
-fn _replace(s:&mut String, args:Vec<CalcRef>)-> String {
+fn _replace(s:&String, args:Vec<CalcRef>)-> String {
   assert!(args.len()>=2, "str.replace需要传入匹配字符串和替换字符串");
   let from = match &**args.get(0).unwrap() {
-    Litr::Str(s)=> s,
+    Litr::Str(s)=> s.as_str(),
     _=> panic!("str.replace第一个参数必须是Str")
   };
   let to = match &**args.get(1).unwrap() {
-    Litr::Str(s)=> s,
+    Litr::Str(s)=> s.as_str(),
     _=> panic!("str.replace第二个参数必须是Str")
   };
 
@@ -185,9 +193,9 @@ fn _replace(s:&mut String, args:Vec<CalcRef>)-> String {
 
 /// 把字符串以一个分隔符分割成字符串列表
 /// 第二个参数可以传true,让分割后的字符串保留分隔符
-fn split(s:&mut String, args:Vec<CalcRef>)-> Litr {
+fn split(s:&String, args:Vec<CalcRef>)-> Litr {
   let with = args.get(0).map_or("", |s|match &**s {
-    Litr::Str(s)=> s,
+    Litr::Str(s)=> s.as_str(),
     _=> panic!("str.split第一个参数必须是字符串")
   });
 
@@ -196,14 +204,14 @@ fn split(s:&mut String, args:Vec<CalcRef>)-> Litr {
     if let Litr::Bool(b) = &**n {
       if *b {
         return Litr::List(
-          s.split_inclusive(with).map(|s|Litr::Str(s.to_string())).collect()
+          s.split_inclusive(with).map(|s|Litr::Str(s.to_string().into())).collect()
         );
       }
     }
   }
   
   Litr::List(
-    s.split(with).map(|s|Litr::Str(s.to_string())).collect()
+    s.split(with).map(|s|Litr::Str(s.to_string().into())).collect()
   )
 }
 
@@ -218,7 +226,7 @@ fn splice(s:&mut String, args:Vec<CalcRef>)-> Litr {
   let slice_end = indice.nth(end - start - 1).unwrap_or((s.len(), '\x00')).0;
 
   let with = args.get(2).map_or("", |s|match &**s {
-    Litr::Str(s)=> s,
+    Litr::Str(s)=> s.as_str(),
     _=> panic!("str.splice第三个参数必须是Str")
   });
 
@@ -236,7 +244,7 @@ pub fn statics()-> Vec<(Interned, NativeFn)> {
       |v| {
         let itr = v.v as *mut Lines;
         (*itr).next().map_or(sym::iter_end(), 
-        |v|Litr::Str(v.to_string()))
+        |v|Litr::Str(v.to_string().into()))
       }, 
       |v| {
         drop(Box::from_raw(v.v as *mut Lines))
@@ -248,20 +256,21 @@ pub fn statics()-> Vec<(Interned, NativeFn)> {
     (intern(b"from"), s_from),
     (intern(b"from_utf8"), s_from_utf8),
     (intern(b"from_utf16"), s_from_utf16),
+    (intern(b"from_percent"), codec::s_str_from_percent),
   ]
 }
 
 /// 调用Litr::str
 fn s_from(args:Vec<CalcRef>, _cx:Scope)-> Litr {
   let s = args.get(0).map_or(&Litr::Uninit, |s|&**s);
-  Litr::Str(s.str())
+  Litr::Str(s.str().into())
 }
 
 /// utf8 buf to str 强检查版
 fn s_from_utf8(args:Vec<CalcRef>, _cx:Scope)-> Litr {
   Litr::Str(
-    args.get(0).map_or(String::new(), |s|match &**s {
-      Litr::Buf(s)=> String::from_utf8(s.clone()).expect("Str解析错误 非法utf8字符"),
+    args.get(0).map_or(String::new().into(), |s|match &**s {
+      Litr::Buf(s)=> String::from_utf8(s.to_vec()).expect("Str解析错误 非法utf8字符").into(),
       _=> panic!("Str::from_utf8第一个参数必须是Buf")
     })
   )
@@ -270,10 +279,10 @@ fn s_from_utf8(args:Vec<CalcRef>, _cx:Scope)-> Litr {
 /// utf16 buf to str 强检查版
 fn s_from_utf16(args:Vec<CalcRef>, _cx:Scope)-> Litr {
   Litr::Str(
-    args.get(0).map_or(String::new(), |s|match &**s {
+    args.get(0).map_or(String::new().into(), |s|match &**s {
       Litr::Buf(s)=> String::from_utf16(
         unsafe {std::slice::from_raw_parts(s.as_ptr() as *const u16, s.len() / 2)}
-      ).expect("Str解析错误 非法utf16字符"),
+      ).expect("Str解析错误 非法utf16字符").into(),
       _=> panic!("Str::from_utf16第一个参数必须是Buf")
     })
   )
@@ -1,50 +1,53 @@
 //! list类型的方法(不就是buf的阉割版么)
 use super::*;
-
-pub fn method(v:&mut Vec<Litr>, scope:Scope, name:Interned, args:Vec<CalcRef>)-> Litr {
-  match name.vec() {
-    // 增删
-    b"push"=> push(v, args),
-    b"push_front"=> push_front(v, args),
-    b"pop"=> pop(v, args),
-    b"pop_front"=> pop_front(v, args),
-    b"insert"=> insert(v, args),
-    b"insert_many"=> insert_many(v, args),
-    b"remove"=> remove(v, args),
-    b"splice"=> splice(v, args),
-    b"slice"=> slice_clone(v, args),
-    b"concat"=> concat(v, args),
-
-    // 遍历
-    b"for_each"=> for_each(v, args, scope),
-    b"map"=> map_clone(v, args, scope),
-    b"filter"=> filter_clone(v, args, scope),
-    b"dedup"=> dedup(v, args, scope),
-    b"fold"=> fold(v, args, scope),
-    b"sort"=> sort(v, args, scope),
-
-    // 搜索
-    b"last"=> last(v),
-    b"includes"=> includes(v, args),
-    b"index_of"=> index_of(v, args, scope),
-    b"r_index_of"=> r_index_of(v, args, scope),
-    b"find"=> find(v, args, scope),
-    b"r_find"=> r_find(v, args, scope),
-    b"all"=> all(v, args, scope),
-    b"min"=> min(v),
-    b"max"=> max(v),
-
-    // 转换
-    b"join"=> join(v, args),
-    b"to_buf"=> to_buf(v),
-
-    // 本征
-    b"rev"=> rev(v),
-    b"fill"=> fill(v, args),
-    b"rotate"=> rotate(v, args),
-    b"expand"=> expand(v, args),
-    _=> panic!("List没有{}方法",name)
-  }
+use std::sync::LazyLock;
+
+/// List的方法表
+static METHODS:LazyLock<Vec<(Interned, Method<Shared<Vec<Litr>>>)>> = LazyLock::new(||method_table(&[
+  // 增删
+  (b"push", |v, args, _|push(v, args)),
+  (b"push_front", |v, args, _|push_front(v, args)),
+  (b"pop", |v, args, _|pop(v, args)),
+  (b"pop_front", |v, args, _|pop_front(v, args)),
+  (b"insert", |v, args, _|insert(v, args)),
+  (b"insert_many", |v, args, _|insert_many(v, args)),
+  (b"remove", |v, args, _|remove(v, args)),
+  (b"splice", |v, args, _|splice(v, args)),
+  (b"slice", |v, args, _|slice_clone(v, args)),
+  (b"concat", |v, args, _|concat(v, args)),
+
+  // 遍历
+  (b"for_each", |v, args, scope|for_each(v, args, scope)),
+  (b"map", |v, args, scope|map_clone(v, args, scope)),
+  (b"filter", |v, args, scope|filter_clone(v, args, scope)),
+  (b"dedup", |v, args, scope|dedup(v, args, scope)),
+  (b"fold", |v, args, scope|fold(v, args, scope)),
+  (b"sort", |v, args, scope|sort(v, args, scope)),
+
+  // 搜索
+  (b"last", |v, _, _|last(v)),
+  (b"includes", |v, args, _|includes(v, args)),
+  (b"index_of", |v, args, scope|index_of(v, args, scope)),
+  (b"r_index_of", |v, args, scope|r_index_of(v, args, scope)),
+  (b"find", |v, args, scope|find(v, args, scope)),
+  (b"r_find", |v, args, scope|r_find(v, args, scope)),
+  (b"all", |v, args, scope|all(v, args, scope)),
+  (b"min", |v, _, _|min(v)),
+  (b"max", |v, _, _|max(v)),
+
+  // 转换
+  (b"join", |v, args, _|join(v, args)),
+  (b"to_buf", |v, _, _|to_buf(v)),
+
+  // 本征
+  (b"rev", |v, _, _|rev(v)),
+  (b"fill", |v, args, _|fill(v, args)),
+  (b"rotate", |v, args, _|rotate(v, args)),
+  (b"expand", |v, args, _|expand(v, args)),
+]));
+
+pub fn methods()-> &'static [(Interned, Method<Shared<Vec<Litr>>>)] {
+  &METHODS
 }
 
 const fn to_usize(v:&Litr)-> usize {
@@ -150,7 +153,7 @@ fn pop(v:&mut Vec<Litr>, args:Vec<CalcRef>)-> Litr {
       panic!("分界线索引{at}大于数组长度{}", v.len());
     }
 
-    Litr::List(v.split_off(v.len() - at))
+    Litr::List(v.split_off(v.len() - at).into())
   }else {
     match v.pop() {
       Some(n)=> n,
@@ -173,7 +176,7 @@ fn pop_front(v:&mut Vec<Litr>, args:Vec<CalcRef>)-> Litr {
 
     let mut part = v.split_off(at);
     std::mem::swap(v, &mut part);
-    Litr::List(part)
+    Litr::List(part.into())
   }else {
     if v.len()==0 {return Litr::Uninit;}
     v.remove(0)
@@ -202,7 +205,7 @@ fn filter_clone(v:&mut Vec<Litr>, args:Vec<CalcRef>, scope:Scope)-> Litr {
 }
 
 /// 获取最后一个数字
-fn last(v:&mut Vec<Litr>)-> Litr {
+fn last(v:&Vec<Litr>)-> Litr {
   v.last().map_or(Litr::Uninit, |v|v.clone())
 }
 
@@ -317,7 +320,7 @@ fn rotate(v:&mut Vec<Litr>, args:Vec<CalcRef>)-> Litr {
 fn concat(v:&mut Vec<Litr>, args:Vec<CalcRef>)-> Litr {
   let mut args = args.into_iter();
   let other:Vec<Litr> = match args.next().expect("list.concat需要传入另一个Buf或数组").own() {
-    Litr::List(b)=> b,
+    Litr::List(b)=> b.into_inner(),
     Litr::Buf(b)=> b.into_iter().map(|n|Litr::Uint(n as usize)).collect(),
     n=> {
       v.push(n);
@@ -330,7 +333,7 @@ fn concat(v:&mut Vec<Litr>, args:Vec<CalcRef>)-> Litr {
 
 /// 将十六进制数以字符的格式渲染, 传入一个分隔符
 fn join(v:&mut Vec<Litr>, args:Vec<CalcRef>)-> Litr {
-  if v.len()==0 {return Litr::Str(String::new());}
+  if v.len()==0 {return Litr::Str(String::new().into());}
 
   let sep = if let Some(s) = args.get(0) {
     if let Litr::Str(s) = &**s {s}else {
@@ -349,7 +352,7 @@ fn join(v:&mut Vec<Litr>, args:Vec<CalcRef>)-> Litr {
       res.write_fmt(format_args!("{sep}{}",n.str())).expect(WRITE_ERR);
     }
   }
-  Litr::Str(res)
+  Litr::Str(res.into())
 }
 
 /// 嘎嘎复制和计算, 将整个数组折叠成一个值
@@ -367,7 +370,7 @@ fn fold(v:&mut Vec<Litr>, args:Vec<CalcRef>, scope:Scope)-> Litr {
 }
 
 /// slice
-fn slice_clone(v:&mut Vec<Litr>, args:Vec<CalcRef>)-> Litr {
+fn slice_clone(v:&Vec<Litr>, args:Vec<CalcRef>)-> Litr {
   let len = v.len();
   let start = args.get(0).map_or(0, |n|to_usize(n));
   let end = args.get(0).map_or(len, |n|to_usize(n));
@@ -375,11 +378,11 @@ fn slice_clone(v:&mut Vec<Litr>, args:Vec<CalcRef>)-> Litr {
   assert!(start<=end, "切片起始索引{start}不可大于结束索引{end}");
   assert!(end<=len, "切片结束索引{end}不可大于数组长度{len}");
 
-  Litr::List(v[start..end].to_vec())
+  Litr::List(v[start..end].to_vec().into())
 }
 
 /// 是否存在一个数
-fn includes(v:&mut Vec<Litr>, args:Vec<CalcRef>)-> Litr {
+fn includes(v:&Vec<Litr>, args:Vec<CalcRef>)-> Litr {
   let find = args.get(0).expect("list.includes需要知道你要找啥");
   Litr::Bool(match v.iter().find(|&n|n==&**find) {
     Some(_)=> true,
@@ -388,7 +391,7 @@ fn includes(v:&mut Vec<Litr>, args:Vec<CalcRef>)-> Litr {
 }
 
 /// 找数组中第一个所指数字, 也可以传函数来自定义判断
-fn index_of(v:&mut Vec<Litr>, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+fn index_of(v:&Vec<Litr>, args:Vec<CalcRef>, _cx:Scope)-> Litr {
   let find = &**args.get(0).expect("list.index_of需要传入一个值");
   let res = v.iter().position(|n|n==find);
   match res {
@@ -398,7 +401,7 @@ fn index_of(v:&mut Vec<Litr>, args:Vec<CalcRef>, _cx:Scope)-> Litr {
 }
 
 /// index_of反向版
-fn r_index_of(v:&mut Vec<Litr>, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+fn r_index_of(v:&Vec<Litr>, args:Vec<CalcRef>, _cx:Scope)-> Litr {
   let find = &**args.get(0).expect("list.r_index_of需要传入一个值");
   let res = v.iter().rev().position(|n|n==find);
   match res {
@@ -450,17 +453,17 @@ fn all(v:&mut Vec<Litr>, args:Vec<CalcRef>, scope:Scope)-> Litr {
 }
 
 /// 找最小值
-fn min(v:&mut Vec<Litr>)-> Litr {
+fn min(v:&Vec<Litr>)-> Litr {
   v.iter().cloned().min_by(|a,b|a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)).unwrap_or(Litr::Uninit)
 }
 
 /// 找最大值
-fn max(v:&mut Vec<Litr>)-> Litr {
+fn max(v:&Vec<Litr>)-> Litr {
   v.iter().cloned().max_by(|a,b|a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)).unwrap_or(Litr::Uninit)
 }
 
 /// List转Buf
-fn to_buf(v:&mut Vec<Litr>)-> Litr {
+fn to_buf(v:&Vec<Litr>)-> Litr {
   Litr::Buf(v.iter().map(|n|match n {
     Litr::Int(n)=> *n as u8,
     Litr::Uint(n)=> *n as u8,
@@ -493,9 +496,9 @@ fn s_new(args:Vec<CalcRef>, _cx:Scope)-> Litr {
       space.write(init.clone());
     }
     unsafe {v.set_len(n)}
-    Litr::List(v)
+    Litr::List(v.into())
   }else {
-    Litr::Buf(Vec::new())
+    Litr::Buf(Vec::new().into())
   }
 }
 
@@ -521,7 +524,7 @@ fn s_concat(args:Vec<CalcRef>, _cx:Scope)-> Litr {
   let mut left = match &*first {
     Litr::List(v)=> v.clone(),
     Litr::Buf(v)=> v.iter().map(|n|Litr::Uint(*n as usize)).collect(),
-    n=> vec![n.clone()]
+    n=> vec![n.clone()].into()
   };
   while let Some(v) = args.next() {
     match &*v {
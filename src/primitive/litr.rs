@@ -1,11 +1,11 @@
 //! Ks值 Litr的定义和运算符
 
-use std::collections::HashMap;
 use crate::{
-  intern::{intern, Interned}, native::NativeInstance, runtime::Scope, scan::{expr::Expr, stmt::{ClassDef, Statements}}
+  intern::{intern, Interned}, native::NativeInstance, runtime::{call::MethodCache, Scope}, scan::{expr::Expr, stmt::{AssignTo, ClassDef, EnumDef, Statements}}
 };
 
 pub use crate::runtime::outlive::LocalFunc;
+pub use super::shared::Shared;
 
 #[derive(Clone, Debug)]
 pub enum Litr {
@@ -15,14 +15,16 @@ pub enum Litr {
   Uint   (usize),
   Float  (f64),
   Bool   (bool),
+  BigInt (super::bigint::BigInt),
 
   Func   (Function), 
-  Str    (String),
-  Buf    (Vec<u8>),
-  List   (Vec<Litr>),
-  Obj    (HashMap<Interned, Litr>),
+  Str    (Shared<String>),
+  Buf    (Shared<Vec<u8>>),
+  List   (Shared<Vec<Litr>>),
+  Obj    (Shared<super::objmap::ObjMap>),
   Inst   (Instance),
-  Ninst  (NativeInstance)
+  Ninst  (NativeInstance),
+  Enum   (EnumInstance)
 }
 
 impl Litr {
@@ -35,6 +37,7 @@ impl Litr {
       Uint(n)=> n.to_string(),
       Float(n)=> n.to_string(),
       Bool(n)=> n.to_string(),
+      BigInt(n)=> n.to_str_radix(10),
       Func(f)=> {
         match f {
           Function::Local(f)=> format!("<Function {}>", f.name),
@@ -42,7 +45,7 @@ impl Litr {
           Function::Native(_)=> "<Native Function>".to_string()
         }
       }
-      Str(s)=> s.clone(),
+      Str(s)=> (**s).clone(),
       List(a) => {
         let mut iter = a.iter();
         let mut str = String::new();
@@ -82,6 +85,16 @@ impl Litr {
       },
       Inst(i)=> {
         let cls = unsafe{&*i.cls};
+
+        // 定义了@str方法就使用自定义的转字符
+        thread_local! {static CACHE:MethodCache = const {MethodCache::new()};}
+        if let Some(res) = CACHE.with(|c|i.call_hook(c, intern(b"@str"))) {
+          return match res {
+            Str(s)=> s.into_inner(),
+            v=> v.str()
+          };
+        }
+
         let mut name = cls.props.iter();
         let mut val = i.v.iter();
         let mut s = String::new();
@@ -106,7 +119,16 @@ impl Litr {
         s.push_str(" }");
         s
       }
-      Ninst(inst)=> (unsafe { &*inst.cls }.to_str)(inst)
+      Ninst(inst)=> (unsafe { &*inst.cls }.to_str)(inst),
+      Enum(e)=> {
+        let mut s = e.name();
+        if e.v.len() > 0 {
+          s.push('(');
+          s.push_str(&e.v.iter().map(|v|v.str()).collect::<Vec<String>>().join(", "));
+          s.push(')');
+        }
+        s
+      }
     }
   }
 }
@@ -138,7 +160,9 @@ pub enum Function {
 pub struct ArgDecl {
   pub name: Interned,
   pub t: KsType,
-  pub default: Expr
+  pub default: Expr,
+  /// 解构参数的模式
+  pub pat: Option<AssignTo>
 }
 
 /// 未绑定作用域的本地定义函数
@@ -146,7 +170,11 @@ pub struct ArgDecl {
 pub struct LocalFuncRaw {
   pub name: Interned,
   pub argdecl: LocalFuncRawArg, 
-  pub stmts: Statements
+  pub stmts: Statements,
+  /// 返回值类型, 使用`->`声明
+  pub ret: KsType,
+  /// 函数体中含有yield, 调用时返回生成器
+  pub generator: bool
 }
 
 /// 本地函数传参方式
@@ -166,12 +194,32 @@ pub struct ExternFunc {
 }
 
 /// 类实例
-#[derive(Debug)]
 pub struct Instance {
   pub cls: *const ClassDef,
   pub v: Box<[Litr]>
 }
 
+impl Instance {
+  /// 以实例的副本作为self调用@str, @debug这类钩子, 没有定义时返回None
+  /// 
+  /// 副本不经过@clone和@drop, 钩子里对self的修改不会影响原实例
+  fn call_hook(&self, cache:&MethodCache, name:Interned)-> Option<Litr> {
+    let cls = unsafe{&*self.cls};
+    let mthd = cache.class_method(cls, name)?;
+    let f = LocalFunc::new(&mthd.f, cls.cx);
+    let mut this = Litr::Inst(Instance { cls: self.cls, v: self.v.clone() });
+    let res = Scope::call_local_with_self(&f, vec![], &mut this);
+    match this {
+      Litr::Inst(mut inst)=> {
+        drop(std::mem::take(&mut inst.v));
+        std::mem::forget(inst);
+      }
+      v=> drop(v)
+    }
+    Some(res)
+  }
+}
+
 impl Clone for Instance {
   /// 为想要管理内存的实例提供@clone方法
   fn clone(&self) -> Self {
@@ -210,6 +258,56 @@ impl Drop for Instance {
   }
 }
 
+impl std::fmt::Debug for Instance {
+  /// 为想要自定义调试输出的实例提供@debug方法
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let cls = unsafe{&*self.cls};
+    thread_local! {static CACHE:MethodCache = const {MethodCache::new()};}
+    match CACHE.with(|c|self.call_hook(c, intern(b"@debug"))) {
+      Some(Litr::Str(s))=> f.write_str(&s),
+      Some(v)=> f.write_str(&v.str()),
+      // 默认只打印类名和属性, 不打印指针
+      None=> {
+        let mut d = f.debug_struct(&cls.name.str());
+        for (prop, v) in cls.props.iter().zip(self.v.iter()) {
+          d.field(&prop.name.str(), v);
+        }
+        d.finish()
+      }
+    }
+  }
+}
+
+/// 枚举值
+#[derive(Clone)]
+pub struct EnumInstance {
+  pub def: *const EnumDef,
+  /// 成员在声明中的序号
+  pub tag: usize,
+  /// 成员携带的值, 顺序同声明
+  pub v: Box<[Litr]>
+}
+impl EnumInstance {
+  /// 获取`枚举名::成员名`
+  pub fn name(&self)-> String {
+    let def = unsafe{&*self.def};
+    format!("{}::{}", def.name, def.variants[self.tag].name)
+  }
+  /// 判断是否来自同一个枚举声明
+  pub fn same_def(&self, def:*const EnumDef)-> bool {
+    unsafe{(*self.def).p == (*def).p}
+  }
+}
+impl std::fmt::Debug for EnumInstance {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let mut d = f.debug_tuple(&self.name());
+    for v in self.v.iter() {
+      d.field(v);
+    }
+    d.finish()
+  }
+}
+
 
 /// Key语言内的类型声明
 #[derive(Clone)]
@@ -219,12 +317,23 @@ pub enum KsType {
   Uint,
   Float,
   Bool,
+  BigInt,
   Func, 
   Str,
   Buf,
   List,
   Obj,
-  Class(Interned)
+  Class(Interned),
+  /// List<T>, 会检查每个元素
+  ListOf(Box<KsType>),
+  /// Obj<T>, 会检查每个值
+  ObjOf(Box<KsType>),
+  /// Int|Float
+  Union(Vec<KsType>),
+  /// Str? 也接受uninit
+  Optional(Box<KsType>),
+  /// Func(Int, Str)-> Bool
+  Sig(Vec<KsType>, Box<KsType>)
 }
 impl KsType {
   /// 在一个作用域判断这个Litr是不是该类型
@@ -232,43 +341,123 @@ impl KsType {
     use crate::runtime::Class;
     // 类型检查
     macro_rules! matcher {($($t:ident)*)=> {
-      if let Litr::Uninit = &arg {
-        if let KsType::Any = self {
-          true
-        }else { false }
-      } else {match self {
+      match self {
         KsType::Any=> true,
-        $(
-          KsType::$t=> matches!(arg, Litr::$t(_)),
-        )*
-        KsType::Class(cls)=> {
-          let cls = cx.find_class(*cls).unwrap_or_else(||panic!("无法找到'{}'类型",cls));
-          match cls {
-            Class::Local(cls)=> if let Litr::Inst(inst) = &arg {
-              inst.cls == cls
-            }else {false}
-            Class::Native(cls)=> if let Litr::Ninst(inst) = &arg {
-              inst.cls == cls
-            }else {false}
+        KsType::Union(ts)=> ts.iter().any(|t|t.is(arg, cx)),
+        KsType::Optional(t)=> matches!(arg, Litr::Uninit) || t.is(arg, cx),
+        _=> if let Litr::Uninit = &arg {false} else {match self {
+          $(
+            KsType::$t=> matches!(arg, Litr::$t(_)),
+          )*
+          // 只有声明了元素类型时才逐个检查
+          KsType::ListOf(t)=> match arg {
+            Litr::List(v)=> v.iter().all(|v|t.is(v, cx)),
+            _=> false
           }
-        }
-      }}
+          KsType::ObjOf(t)=> match arg {
+            Litr::Obj(o)=> o.values().all(|v|t.is(v, cx)),
+            _=> false
+          }
+          KsType::Sig(args, ret)=> match arg {
+            Litr::Func(f)=> fits_sig(f, args, ret, cx),
+            _=> false
+          }
+          KsType::Class(cls)=> {
+            let cls = cx.find_class(*cls).unwrap_or_else(||panic!("无法找到'{}'类型",cls));
+            match cls {
+              Class::Local(cls)=> if let Litr::Inst(inst) = &arg {
+                inst.cls == cls
+              }else {false}
+              Class::Native(cls)=> if let Litr::Ninst(inst) = &arg {
+                inst.cls == cls
+              }else {false}
+              Class::Enum(def)=> if let Litr::Enum(e) = &arg {
+                e.same_def(def)
+              }else {false}
+            }
+          }
+          _=> unreachable!()
+        }}
+      }
     }}
-    matcher!{Buf Bool Float Func Int Uint List Obj Str}
+    matcher!{Buf Bool BigInt Float Func Int Uint List Obj Str}
   }
+
+  /// t类型的值能否放进声明为该类型的位置, 无法确定时视为可以
+  /// 
+  /// 涉及类的比较交给cls判断
+  pub fn accepts(&self, t:&KsType, cls:&dyn Fn(&KsType, &KsType)-> bool)-> bool {
+    use KsType::*;
+    match (self, t) {
+      (Any, _)|(_, Any)=> true,
+      (_, Union(ts))=> ts.iter().all(|t|self.accepts(t, cls)),
+      (Union(ws), _)=> ws.iter().any(|w|w.accepts(t, cls)),
+      (Optional(w), Optional(t))=> w.accepts(t, cls),
+      (Optional(w), _)=> w.accepts(t, cls),
+      (_, Optional(_))=> false,
+      (List, ListOf(_))|(ListOf(_), List)|(Obj, ObjOf(_))|(ObjOf(_), Obj)=> true,
+      (ListOf(w), ListOf(t))|(ObjOf(w), ObjOf(t))=> w.accepts(t, cls),
+      (Func, Sig(..))|(Sig(..), Func)=> true,
+      // 函数的参数要能接受签名中的参数
+      (Sig(wargs, wret), Sig(targs, tret))=> wargs.len() == targs.len()
+        && wargs.iter().zip(targs).all(|(w, t)|t.accepts(w, cls))
+        && wret.accepts(tret, cls),
+      (Class(_), _)|(_, Class(_))=> cls(self, t),
+      _=> std::mem::discriminant(self) == std::mem::discriminant(t)
+    }
+  }
+}
+
+/// 函数是否符合签名, 只有本地函数的参数和返回值类型可以检查
+fn fits_sig(f:&Function, args:&[KsType], ret:&KsType, cx:crate::runtime::Scope)-> bool {
+  let f = match f {
+    Function::Local(f)=> f,
+    _=> return true
+  };
+  let decl = match &f.argdecl {
+    LocalFuncRawArg::Normal(decl)=> decl,
+    LocalFuncRawArg::Custom(_)=> return true
+  };
+  let cls = |a:&KsType, b:&KsType|match (a, b) {
+    (KsType::Class(a), KsType::Class(b))=> a == b || cx.find_class(*a) == cx.find_class(*b),
+    _=> false
+  };
+  // 多传的参数会被忽略, 没传到的参数会得到uninit
+  decl.iter().enumerate().all(|(n, a)|match args.get(n) {
+    Some(t)=> a.t.accepts(t, &cls),
+    None=> !matches!(a.default, Expr::Literal(Litr::Uninit)) || a.t.is(&Litr::Uninit, cx)
+  }) && ret.accepts(&f.ret, &cls)
 }
 
 impl std::fmt::Debug for KsType {
   fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    /// 联合类型和函数签名作为其他类型的一部分时需要括号
+    fn inner(t:&KsType)-> String {
+      match t {
+        KsType::Union(_)|KsType::Sig(..)=> format!("({:?})", t),
+        _=> format!("{:?}", t)
+      }
+    }
     macro_rules! m {{$($t:ident)*}=> {
       match self {
         $(
-          KsType::$t=> stringify!($t),
+          KsType::$t=> f.write_str(stringify!($t)),
         )*
-        KsType::Class(n)=> unsafe{ std::str::from_utf8_unchecked(n.vec()) }
+        KsType::Class(n)=> f.write_str(unsafe{ std::str::from_utf8_unchecked(n.vec()) }),
+        KsType::ListOf(t)=> write!(f, "List<{:?}>", t),
+        KsType::ObjOf(t)=> write!(f, "Obj<{:?}>", t),
+        KsType::Optional(t)=> write!(f, "{}?", inner(t)),
+        KsType::Union(ts)=> f.write_str(&ts.iter().map(inner).collect::<Vec<_>>().join("|")),
+        KsType::Sig(args, ret)=> {
+          write!(f, "Func({})", args.iter().map(|t|format!("{:?}", t)).collect::<Vec<_>>().join(", "))?;
+          if let KsType::Any = **ret {
+            return Ok(());
+          }
+          write!(f, "-> {:?}", ret)
+        }
       }
     }}
-    f.write_str(m!{Any Buf Bool Float Func Int Uint List Obj Str})
+    m!{Any Buf Bool BigInt Float Func Int Uint List Obj Str}
   }
 }
 
@@ -276,7 +465,9 @@ impl std::fmt::Debug for KsType {
 impl PartialEq for Litr {
   fn eq(&self, other: &Self) -> bool {
     match self {
-      Litr::Ninst(l)=> if let Litr::Ninst(r) = other {l == r}else {false},
+      Litr::Ninst(l)=> if let Litr::Ninst(r) = other {
+        super::collection::eq(l, r).unwrap_or_else(||l == r)
+      }else {false},
       Litr::Func(l)=> if let Litr::Func(r) = other {
         match (l, r) {
           (Function::Local(l), Function::Local(r))=> 
@@ -309,6 +500,12 @@ impl PartialOrd for Litr {
         (Float(l), Uint(r))=> l.partial_cmp(&(*r as f64)),
         (Float(l), Int(r))=> l.partial_cmp(&(*r as f64)),
         (Float(l), Float(r))=> l.partial_cmp(r),
+        (BigInt(l), Float(r))=> l.to_f64().partial_cmp(r),
+        (Float(l), BigInt(r))=> l.partial_cmp(&r.to_f64()),
+        (BigInt(_), _)|(_, BigInt(_))=> match (super::bigint::promote(l), super::bigint::promote(r)) {
+          (Some(l), Some(r))=> l.partial_cmp(&r),
+          _=> None
+        }
         (Bool(l), Bool(r))=> l.partial_cmp(r),
         (Str(l), Str(r))=> l.partial_cmp(r),
         (Buf(l), Buf(r))=> l.partial_cmp(r),
@@ -319,6 +516,15 @@ impl PartialOrd for Litr {
             match_list(&*l.v, &*r.v)
           }else {None}
         }
+        // 同一枚举按声明顺序比较, 成员相同时再比较携带的值
+        (Enum(l),Enum(r))=> {
+          if l.same_def(r.def) {
+            match l.tag.cmp(&r.tag) {
+              Equal=> match_list(&*l.v, &*r.v),
+              n=> Some(n)
+            }
+          }else {None}
+        }
         _=> None
       }
     }
@@ -3,6 +3,7 @@
 //! 和对基本类型方法的实现
 
 pub mod litr;
+pub mod shared;
 
 pub mod kstd;
 
@@ -11,13 +12,24 @@ pub mod list;
 pub mod int;
 pub mod float;
 pub mod kstr;
+pub mod kbool;
 pub mod sym;
 pub mod obj;
+pub mod objmap;
 pub mod iter;
 pub mod func;
 pub mod planet;
+pub mod generator;
+pub mod format;
+pub mod bigint;
+pub mod regex;
+pub mod hash;
+pub mod zip;
+pub mod codec;
+pub mod collection;
 
 use litr::{Litr, Function};
+use shared::Shared;
 use crate::native::{
   NativeClassDef, 
   NativeFn,
@@ -28,6 +40,14 @@ use crate::intern::{Interned, intern};
 
 static mut CLASSES:Option<Vec<(Interned, *mut NativeClassDef)>> = None;
 
+/// 基本类型的方法, 和原生类的NativeMethod一致
+pub type Method<T> = fn(&mut T, Vec<CalcRef>, Scope)-> Litr;
+
+/// 将方法名缓存为Interned, 调用时只需比较指针
+pub fn method_table<T>(m:&[(&[u8], Method<T>)])-> Vec<(Interned, Method<T>)> {
+  m.iter().map(|(name, f)|(intern(name), *f)).collect()
+}
+
 pub fn ninst_to_str(inst:&NativeInstance)-> String {
   format!("{} {{ Builtin }}", &unsafe{&*inst.cls}.name.str())
 }
@@ -83,10 +103,18 @@ pub fn classes()-> Vec<(Interned, Class)> {unsafe {
       new_static_class(b"Obj", obj::statics()),
       new_static_class(b"Int", int::statics_int()),
       new_static_class(b"Uint", int::statics_uint()),
+      new_static_class(b"BigInt", bigint::statics()),
       new_static_class(b"Float", float::statics()),
       new_static_class(b"Str", kstr::statics()),
       sym::init(),
       planet::init(),
+      generator::init(),
+      regex::init(),
+      hash::init(),
+      zip::init(),
+      collection::init_set(),
+      collection::init_map(),
+      iter::init(),
       new_static_class(b"Func", func::statics()),
     ]);
     classes()
@@ -96,7 +124,16 @@ pub fn classes()-> Vec<(Interned, Class)> {unsafe {
 
 /// 在作用域中获取Litr的属性
 pub fn get_prop(this:Scope, mut from:CalcRef, find:Interned)-> CalcRef {
-  match &mut *from {
+  let r = prop_of(this, &mut from, find);
+  // 临时值会在返回后被丢弃, 指向其内部的引用要先取出来
+  match (&from, r) {
+    (CalcRef::Own(_), CalcRef::Ref(p))=> CalcRef::Own(std::mem::take(unsafe {&mut *p})),
+    (_, r)=> r
+  }
+}
+
+fn prop_of(this:Scope, from:&mut Litr, find:Interned)-> CalcRef {
+  match from {
     // 本地class的实例
     Litr::Inst(inst)=> {
       let can_access_private = unsafe {(*inst.cls).cx.exports} == this.exports;
@@ -115,6 +152,15 @@ pub fn get_prop(this:Scope, mut from:CalcRef, find:Interned)-> CalcRef {
       panic!("'{}'类型上没有'{}'属性", cls.name, find)
     },
 
+    // 枚举携带的值
+    Litr::Enum(e)=> {
+      let decl = &unsafe {&*e.def}.variants[e.tag];
+      match decl.fields.iter().position(|f|f.name == find) {
+        Some(n)=> CalcRef::Ref(&mut e.v[n]),
+        None=> panic!("'{}'没有'{}'值", e.name(), find)
+      }
+    }
+
     // 原生类的实例
     Litr::Ninst(inst)=> {
       let cls = unsafe {&*inst.cls};
@@ -140,14 +186,14 @@ pub fn get_prop(this:Scope, mut from:CalcRef, find:Interned)-> CalcRef {
 
     Litr::Func(f)=> CalcRef::Own(match find.vec() {
       b"name"=> match f {
-        Function::Local(f)=> Litr::Str(f.name.str()),
-        Function::Extern(_)=> Litr::Str("@extern".to_owned()),
-        Function::Native(_)=> Litr::Str("@native".to_owned())
+        Function::Local(f)=> Litr::Str(f.name.str().into()),
+        Function::Extern(_)=> Litr::Str("@extern".to_owned().into()),
+        Function::Native(_)=> Litr::Str("@native".to_owned().into())
       }
       b"type"=> match f {
-        Function::Local(_)=> Litr::Str("local".to_owned()),
-        Function::Extern(_)=> Litr::Str("extern".to_owned()),
-        Function::Native(_)=> Litr::Str("native".to_owned())
+        Function::Local(_)=> Litr::Str("local".to_owned().into()),
+        Function::Extern(_)=> Litr::Str("extern".to_owned().into()),
+        Function::Native(_)=> Litr::Str("native".to_owned().into())
       }
       b"raw"=> match f {
         Function::Local(f)=> Litr::Uint(f.ptr as _),
@@ -3,10 +3,11 @@
 use crate::{
   intern::{intern, Interned}, 
   native::{NativeClassDef, NativeFn, NativeInstance}, 
-  primitive::{litr::Litr, sym}, 
+  primitive::{litr::{Litr, Shared}, sym, codec, Method, method_table}, 
   runtime::{calc::CalcRef, Scope}
 };
-use std::collections::HashMap;
+use super::objmap::ObjMap;
+use std::sync::LazyLock;
 
 /// obj.keys()返回的迭代器类型
 static mut ITER_KEYS:*mut NativeClassDef = std::ptr::null_mut();
@@ -15,24 +16,26 @@ static mut ITER_VALUES:*mut NativeClassDef = std::ptr::null_mut();
 /// obj.entries()的迭代器
 static mut ITER_ENTRIES:*mut NativeClassDef = std::ptr::null_mut();
 
-pub fn method(v:&mut HashMap<Interned, Litr>, scope:Scope, name:Interned, args:Vec<CalcRef>)-> Litr {
-  match name.vec() {
-    b"get"=> get(v, args),
-    b"set"=> set(v, args),
-    b"remove"=> remove(v, args),
-    b"for_each"=> for_each(v, args, scope),
-    b"has"=> has(v, args),
-    b"keys"=> keys(v),
-    b"values"=> values(v),
-    b"entries"=> entries(v),
-    b"len"=> Litr::Uint(v.len()),
-    b"concat"=> concat(v, args),
-    _=> panic!("Obj没有{}方法",name)
-  }
+/// Obj的方法表
+static METHODS:LazyLock<Vec<(Interned, Method<Shared<ObjMap>>)>> = LazyLock::new(||method_table(&[
+  (b"get", |v, args, _|get(v, args)),
+  (b"set", |v, args, _|set(v, args)),
+  (b"remove", |v, args, _|remove(v, args)),
+  (b"for_each", |v, args, scope|for_each(v, args, scope)),
+  (b"has", |v, args, _|has(v, args)),
+  (b"keys", |v, _, _|keys(v)),
+  (b"values", |v, _, _|values(v)),
+  (b"entries", |v, _, _|entries(v)),
+  (b"len", |v, _, _|Litr::Uint(v.len())),
+  (b"concat", |v, args, _|concat(v, args)),
+]));
+
+pub fn methods()-> &'static [(Interned, Method<Shared<ObjMap>>)] {
+  &METHODS
 }
 
 /// 插入元素, 返回原有的元素或uninit
-fn set(v:&mut HashMap<Interned, Litr>, args:Vec<CalcRef>)-> Litr {
+fn set(v:&mut ObjMap, args:Vec<CalcRef>)-> Litr {
   let name = match &**args.get(0).expect("obj.insert需要传入键名") {
     Litr::Str(s)=> intern(s.as_bytes()),
     _=> panic!("obj.insert第一个参数必须是字符串")
@@ -42,7 +45,7 @@ fn set(v:&mut HashMap<Interned, Litr>, args:Vec<CalcRef>)-> Litr {
 }
 
 /// 删除一个元素,返回被删除的元素
-fn remove(v:&mut HashMap<Interned, Litr>, args:Vec<CalcRef>)-> Litr {
+fn remove(v:&mut ObjMap, args:Vec<CalcRef>)-> Litr {
   let name = match &**args.get(0).expect("obj.remove需要传入键名") {
     Litr::Str(s)=> intern(s.as_bytes()),
     _=> panic!("obj.remove第一个参数必须是字符串")
@@ -51,19 +54,19 @@ fn remove(v:&mut HashMap<Interned, Litr>, args:Vec<CalcRef>)-> Litr {
 }
 
 /// 传入函数遍历|k,v|
-fn for_each(v:&mut HashMap<Interned, Litr>, args:Vec<CalcRef>, scope:Scope)-> Litr {
+fn for_each(v:&mut ObjMap, args:Vec<CalcRef>, scope:Scope)-> Litr {
   let f = match &**args.get(0).expect("obj.for_each需要传入一个函数") {
     Litr::Func(f)=> f,
     _=> panic!("obj.for_each第一个参数必须是Func")
   };
   v.iter_mut().for_each(|(k,v)|{
-    scope.call(vec![CalcRef::Own(Litr::Str(k.str())), CalcRef::Ref(v)], f);
+    scope.call(vec![CalcRef::Own(Litr::Str(k.str().into())), CalcRef::Ref(v)], f);
   });
   Litr::Uninit
 }
 
 /// 获取Litr
-fn get(v:&mut HashMap<Interned, Litr>, args:Vec<CalcRef>)-> Litr {
+fn get(v:&ObjMap, args:Vec<CalcRef>)-> Litr {
   let name = match &**args.get(0).expect("obj.get需要传入键名") {
     Litr::Str(s)=> intern(s.as_bytes()),
     _=> panic!("obj.get第一个参数必须是字符串")
@@ -72,7 +75,7 @@ fn get(v:&mut HashMap<Interned, Litr>, args:Vec<CalcRef>)-> Litr {
 }
 
 /// 测试是否有该元素
-fn has(v:&mut HashMap<Interned, Litr>, args:Vec<CalcRef>)-> Litr {
+fn has(v:&ObjMap, args:Vec<CalcRef>)-> Litr {
   let name = match &**args.get(0).expect("obj.has需要传入键名") {
     Litr::Str(s)=> intern(s.as_bytes()),
     _=> panic!("obj.has第一个参数必须是字符串")
@@ -84,25 +87,25 @@ fn has(v:&mut HashMap<Interned, Litr>, args:Vec<CalcRef>)-> Litr {
 }
 
 /// 返回对所有键名的迭代器
-fn keys(o:&mut HashMap<Interned, Litr>)-> Litr {
+fn keys(o:&ObjMap)-> Litr {
   let v = Box::into_raw(Box::new(o.keys())) as usize;
   Litr::Ninst(NativeInstance {cls:unsafe{ITER_KEYS},v,w:0})
 }
 
 /// 返回对所有值的迭代器
-fn values(o:&mut HashMap<Interned, Litr>)-> Litr {
+fn values(o:&ObjMap)-> Litr {
   let v = Box::into_raw(Box::new(o.values())) as usize;
   Litr::Ninst(NativeInstance {cls:unsafe{ITER_VALUES},v,w:0})
 }
 
 /// 返回对所有键对的迭代器
-fn entries(o:&mut HashMap<Interned, Litr>)-> Litr {
+fn entries(o:&ObjMap)-> Litr {
   let v = Box::into_raw(Box::new(o.iter())) as usize;
   Litr::Ninst(NativeInstance {cls:unsafe{ITER_ENTRIES},v,w:0})
 }
 
 /// concat内部使用
-fn _concat_extend(o:&mut HashMap<Interned, Litr>, arg:&Litr) {
+fn _concat_extend(o:&mut ObjMap, arg:&Litr) {
   match arg {
     Litr::Obj(other)=>
       o.extend(other.iter().map(|(k,v)|(*k, v.clone()))),
@@ -118,7 +121,7 @@ fn _concat_extend(o:&mut HashMap<Interned, Litr>, arg:&Litr) {
 }
 
 /// 将Obj和Obj或Inst合并(inst只会拼接public的属性)
-fn concat(o:&mut HashMap<Interned, Litr>, args:Vec<CalcRef>)-> Litr {
+fn concat(o:&mut ObjMap, args:Vec<CalcRef>)-> Litr {
   _concat_extend(o, &**args.get(0).expect("obj.concat需要传入拼接对象或实例"));
   Litr::Uninit
 }
@@ -127,18 +130,18 @@ fn concat(o:&mut HashMap<Interned, Litr>, args:Vec<CalcRef>)-> Litr {
 // - statics -
 pub fn statics()-> Vec<(Interned, NativeFn)> {
   unsafe {
-    use std::collections::hash_map::{Keys, Values, Iter};
+    use super::objmap::{Keys, Values, Iter};
 
     // 初始化keys()迭代器类
     ITER_KEYS = Box::into_raw(Box::new(super::new_iter_class(
       b"Obj.keys", 
       |v| {
-        let itr = v.v as *mut Keys<'_, Interned, Litr>;
+        let itr = v.v as *mut Keys<'_>;
         (*itr).next().map_or(sym::iter_end(), 
-        |v|Litr::Str(v.str()))
+        |v|Litr::Str(v.str().into()))
       }, 
       |v| {
-        drop(Box::from_raw(v.v as *mut Keys<'_, Interned, Litr>))
+        drop(Box::from_raw(v.v as *mut Keys<'_>))
       }
     )));
 
@@ -146,12 +149,12 @@ pub fn statics()-> Vec<(Interned, NativeFn)> {
     ITER_VALUES = Box::into_raw(Box::new(super::new_iter_class(
       b"Obj.values", 
       |v| {
-        let itr = v.v as *mut Values<'_, Interned, Litr>;
+        let itr = v.v as *mut Values<'_>;
         (*itr).next()
           .map_or(sym::iter_end(),|v|v.clone())
       }, 
       |v| {
-        drop(Box::from_raw(v.v as *mut Values<'_, Interned, Litr>))
+        drop(Box::from_raw(v.v as *mut Values<'_>))
       }
     )));
 
@@ -159,13 +162,13 @@ pub fn statics()-> Vec<(Interned, NativeFn)> {
     ITER_ENTRIES = Box::into_raw(Box::new(super::new_iter_class(
       b"Obj.entries", 
       |v| {
-        let itr = v.v as *mut Iter<'_, Interned, Litr>;
+        let itr = v.v as *mut Iter<'_>;
         (*itr).next().map_or(sym::iter_end(),|(k,v)|Litr::List(
-          vec![Litr::Str(k.str()), v.clone()]
+          vec![Litr::Str(k.str().into()), v.clone()].into()
         ))
       }, 
       |v| {
-        drop(Box::from_raw(v.v as *mut Iter<'_, Interned, Litr>))
+        drop(Box::from_raw(v.v as *mut Iter<'_>))
       }
     )));
   }
@@ -174,17 +177,19 @@ pub fn statics()-> Vec<(Interned, NativeFn)> {
     (intern(b"concat"), s_concat),
     (intern(b"from_list"), s_from_list),
     (intern(b"group_by"), s_group_by),
-    (intern(b"new"), s_new)
+    (intern(b"new"), s_new),
+    (intern(b"to_query"), codec::s_to_query),
+    (intern(b"from_query"), codec::s_from_query)
   ]
 }
 
 /// 将多个Obj或Inst拼接成一个Obj,前后顺序会影响覆盖关系
 fn s_concat(args:Vec<CalcRef>, _cx:Scope)-> Litr {
-  let mut o = HashMap::new();
+  let mut o = ObjMap::new();
   for arg in args.into_iter() {
     _concat_extend(&mut o, &*arg);
   }
-  Litr::Obj(o)
+  Litr::Obj(o.into())
 }
 
 // 通过成员全都为[key,value]的列表构造一个Obj
@@ -193,7 +198,7 @@ fn s_from_list(args:Vec<CalcRef>, _cx:Scope)-> Litr {
     Litr::List(l)=> l,
     _=> panic!("Obj::from_list第一个参数必须是List")
   };
-  let mut o = HashMap::with_capacity(l.len());
+  let mut o = ObjMap::with_capacity(l.len());
   for v in l {
     if let Litr::List(v) = v {
       let key = if let Some(s) = v.get(0) {
@@ -206,14 +211,14 @@ fn s_from_list(args:Vec<CalcRef>, _cx:Scope)-> Litr {
       o.insert(key, val);
     }
   }
-  Litr::Obj(o)
+  Litr::Obj(o.into())
 }
 
 /// 传入一个返回字符串的函数, 根据字符串把List的内容分类成Obj
 fn s_group_by(args:Vec<CalcRef>, cx:Scope)-> Litr {
   assert!(args.len()>=2, "Obj::group_by需要一个List和返回字符串的函数");
   let mut args = args.into_iter();
-  let mut o = HashMap::new();
+  let mut o = ObjMap::new();
   let mut ls_ = args.next().unwrap();
   let ls = if let Litr::List(l) = &mut *ls_ {l}
     else {panic!("Obj::group_by第一个参数必须是List")};
@@ -233,20 +238,20 @@ fn s_group_by(args:Vec<CalcRef>, cx:Scope)-> Litr {
         v.push(elem.clone());
       }else {unreachable!()}
       None=> {
-        o.insert(s, Litr::List(vec![elem.clone()]));
+        o.insert(s, Litr::List(vec![elem.clone()].into()));
       }
     }
   }
-  Litr::Obj(o)
+  Litr::Obj(o.into())
 }
 
 /// 允许传入一个长度值作为其初始大小
 fn s_new(args:Vec<CalcRef>,_cx:Scope)-> Litr {
   Litr::Obj(if let Some(n) = args.get(0) {
-    HashMap::with_capacity(match &**n {
+    ObjMap::with_capacity(match &**n {
       Litr::Uint(n)=> *n,
       Litr::Int(n)=> *n as usize,
       _=> 0
-    })
-  }else {HashMap::new()})
+    }).into()
+  }else {ObjMap::new().into()})
 }
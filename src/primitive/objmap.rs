@@ -0,0 +1,222 @@
+//! Obj, Map和Set使用的有序哈希表
+//!
+//! 按插入顺序保存键值对, 遍历, 打印和解构的顺序都是确定的.
+//! 键不多时直接线性查找, 超过`LINEAR`个才建立索引.
+//! 删除只留下空位, 空位过多时再整体压缩. 遍历期间不会压缩, 槽位下标保持不变
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter::{FilterMap, Map};
+use std::slice;
+use crate::intern::Interned;
+use super::litr::Litr;
+
+/// 不建索引时的最大长度
+const LINEAR:usize = 8;
+
+type Slot<K, V> = Option<(K, V)>;
+
+pub type Iter<'a, K = Interned, V = Litr> =
+  FilterMap<slice::Iter<'a, Slot<K, V>>, fn(&'a Slot<K, V>)-> Option<&'a (K, V)>>;
+pub type Keys<'a, K = Interned, V = Litr> = Map<Iter<'a, K, V>, fn(&'a (K, V))-> &'a K>;
+pub type Values<'a, K = Interned, V = Litr> = Map<Iter<'a, K, V>, fn(&'a (K, V))-> &'a V>;
+
+/// Obj的键值表
+pub type ObjMap = OrdMap<Interned, Litr>;
+
+pub struct OrdMap<K, V> {
+  slots: Vec<Slot<K, V>>,
+  /// 键到slots下标, 槽位不超过LINEAR时为空
+  index: HashMap<K, usize>,
+  len: usize,
+  /// 正在按下标遍历的数量, 不为0时不压缩
+  frozen: usize
+}
+
+impl<K, V> Default for OrdMap<K, V> {
+  fn default()-> Self {
+    OrdMap {slots: Vec::new(), index: HashMap::new(), len: 0, frozen: 0}
+  }
+}
+
+/// 复制出的表不在遍历中
+impl<K:Clone, V:Clone> Clone for OrdMap<K, V> {
+  fn clone(&self)-> Self {
+    OrdMap {slots: self.slots.clone(), index: self.index.clone(), len: self.len, frozen: 0}
+  }
+}
+
+impl<K:Clone + Eq + Hash, V> OrdMap<K, V> {
+  pub fn new()-> Self {
+    OrdMap::default()
+  }
+
+  pub fn with_capacity(n:usize)-> Self {
+    OrdMap {slots: Vec::with_capacity(n), index: HashMap::new(), len: 0, frozen: 0}
+  }
+
+  pub fn len(&self)-> usize {
+    self.len
+  }
+
+  pub fn is_empty(&self)-> bool {
+    self.len == 0
+  }
+
+  fn find(&self, k:&K)-> Option<usize> {
+    if self.index.is_empty() {
+      self.slots.iter().position(|s|matches!(s, Some((n, _)) if n == k))
+    }else {
+      self.index.get(k).copied()
+    }
+  }
+
+  /// 去掉空位并重建索引, 遍历中跳过
+  fn compact(&mut self) {
+    if self.frozen > 0 {
+      return;
+    }
+    self.slots.retain(|s|s.is_some());
+    self.index = HashMap::new();
+    if self.slots.len() > LINEAR {
+      for (i, s) in self.slots.iter().enumerate() {
+        self.index.insert(s.as_ref().unwrap().0.clone(), i);
+      }
+    }
+  }
+
+  pub fn get(&self, k:&K)-> Option<&V> {
+    self.find(k).map(|i|&self.slots[i].as_ref().unwrap().1)
+  }
+
+  pub fn get_mut(&mut self, k:&K)-> Option<&mut V> {
+    self.find(k).map(|i|&mut self.slots[i].as_mut().unwrap().1)
+  }
+
+  pub fn contains_key(&self, k:&K)-> bool {
+    self.find(k).is_some()
+  }
+
+  /// 已有的键保持原来的位置, 返回旧值
+  pub fn insert(&mut self, k:K, v:V)-> Option<V> {
+    if let Some(i) = self.find(&k) {
+      return Some(std::mem::replace(&mut self.slots[i].as_mut().unwrap().1, v));
+    }
+    if !self.index.is_empty() {
+      self.index.insert(k.clone(), self.slots.len());
+    }
+    self.slots.push(Some((k, v)));
+    self.len += 1;
+    if self.index.is_empty() && self.slots.len() > LINEAR {
+      self.compact();
+    }
+    None
+  }
+
+  /// 移除后剩余的键顺序不变
+  pub fn remove(&mut self, k:&K)-> Option<V> {
+    let i = self.find(k)?;
+    self.index.remove(k);
+    let (_, v) = self.slots[i].take().unwrap();
+    self.len -= 1;
+    let holes = self.slots.len() - self.len;
+    if holes > LINEAR && holes > self.len {
+      self.compact();
+    }
+    Some(v)
+  }
+
+  /// 遍历中清空时保留遍历计数
+  pub fn clear(&mut self) {
+    *self = OrdMap {frozen: self.frozen, ..OrdMap::default()};
+  }
+
+  /// 开始按下标遍历, 结束时需要调用unfreeze
+  pub fn freeze(&mut self) {
+    self.frozen += 1;
+  }
+
+  pub fn unfreeze(&mut self) {
+    self.frozen -= 1;
+    let holes = self.slots.len() - self.len;
+    if self.frozen == 0 && (holes > LINEAR && holes > self.len || self.index.is_empty() && self.slots.len() > LINEAR) {
+      self.compact();
+    }
+  }
+
+  /// 第i个槽位及其后第一个有值的位置, 用于允许修改的遍历
+  pub fn slot_from(&self, mut i:usize)-> Option<(usize, &(K, V))> {
+    while i < self.slots.len() {
+      if let Some(e) = &self.slots[i] {
+        return Some((i, e));
+      }
+      i += 1;
+    }
+    None
+  }
+
+  pub fn iter(&self)-> Iter<'_, K, V> {
+    self.slots.iter().filter_map(Option::as_ref)
+  }
+
+  pub fn iter_mut(&mut self)-> impl Iterator<Item = (&K, &mut V)> {
+    self.slots.iter_mut().filter_map(|s|s.as_mut().map(|(k, v)|(&*k, v)))
+  }
+
+  pub fn keys(&self)-> Keys<'_, K, V> {
+    self.iter().map(|(k, _)|k)
+  }
+
+  pub fn values(&self)-> Values<'_, K, V> {
+    self.iter().map(|(_, v)|v)
+  }
+
+  pub fn values_mut(&mut self)-> impl Iterator<Item = &mut V> {
+    self.iter_mut().map(|(_, v)|v)
+  }
+}
+
+impl<K, V> IntoIterator for OrdMap<K, V> {
+  type Item = (K, V);
+  type IntoIter = std::iter::Flatten<std::vec::IntoIter<Slot<K, V>>>;
+  fn into_iter(self)-> Self::IntoIter {
+    self.slots.into_iter().flatten()
+  }
+}
+
+impl<'a, K:Clone + Eq + Hash, V> IntoIterator for &'a OrdMap<K, V> {
+  type Item = &'a (K, V);
+  type IntoIter = Iter<'a, K, V>;
+  fn into_iter(self)-> Self::IntoIter {
+    self.iter()
+  }
+}
+
+impl<K:Clone + Eq + Hash, V> Extend<(K, V)> for OrdMap<K, V> {
+  fn extend<T: IntoIterator<Item = (K, V)>>(&mut self, iter:T) {
+    for (k, v) in iter {
+      self.insert(k, v);
+    }
+  }
+}
+
+impl<K:Clone + Eq + Hash, V> FromIterator<(K, V)> for OrdMap<K, V> {
+  fn from_iter<T: IntoIterator<Item = (K, V)>>(iter:T)-> Self {
+    let mut o = OrdMap::new();
+    o.extend(iter);
+    o
+  }
+}
+
+/// 相等比较不考虑顺序
+impl<K:Clone + Eq + Hash, V:PartialEq> PartialEq for OrdMap<K, V> {
+  fn eq(&self, other:&Self)-> bool {
+    self.len() == other.len() && self.iter().all(|(k, v)|other.get(k) == Some(v))
+  }
+}
+
+impl<K:Clone + Eq + Hash + std::fmt::Debug, V:std::fmt::Debug> std::fmt::Debug for OrdMap<K, V> {
+  fn fmt(&self, f:&mut std::fmt::Formatter<'_>)-> std::fmt::Result {
+    f.debug_map().entries(self.iter().map(|(k, v)|(k, v))).finish()
+  }
+}
@@ -124,7 +124,7 @@ fn all(args:Vec<CalcRef>, _cx:Scope)-> Litr {
 
     res.push(rust_fall(plan))
   }
-  Litr::List(res)
+  Litr::List(res.into())
 }
 
 
@@ -0,0 +1,1161 @@
+//! 正则表达式类Regex
+//!
+//! 编译为指令后回溯匹配. 不含反向引用时会记录走过的状态, 匹配时间和文本长度成线性
+//!
+//! 支持的语法:
+//! - 字符类`[a-z]`, `[^...]`, `.`, `\d` `\w` `\s`及其大写取反, `\p{L}`等Unicode属性
+//! - 量词`*` `+` `?` `{n}` `{n,}` `{n,m}`, 在后面加`?`为非贪婪
+//! - 锚点`^` `$` `\A` `\z` `\b` `\B`
+//! - 分组`(...)`, `(?:...)`, 命名分组`(?<name>...)`, 前瞻`(?=...)` `(?!...)`
+//! - 选择`|`, 反向引用`\1`和`\k<name>`
+//! - 标志`i`忽略大小写, `m`多行, `s`让`.`匹配换行. 可在构造时传入, 也可写作`(?i)`或`(?i:...)`
+//!
+//! 匹配Str时按Unicode字符匹配, 位置为字符索引; 匹配Buf时按字节匹配, 位置为字节索引
+
+use super::*;
+use std::collections::{HashMap, HashSet};
+use super::objmap::ObjMap;
+
+pub static mut REGEX_CLASS: *mut NativeClassDef = std::ptr::null_mut();
+
+/// 指令数上限, 防止`{n,m}`展开过多
+const MAX_INSTS:usize = 100_000;
+/// 含有反向引用时无法记录状态, 用回溯次数限制最坏情况
+const MAX_STEPS:usize = 50_000_000;
+
+#[derive(Clone, Copy)]
+struct Flags {
+  icase: bool,
+  multi: bool,
+  dotall: bool
+}
+
+#[derive(Clone, Copy)]
+enum Prop {
+  Letter,
+  Upper,
+  Lower,
+  Number,
+  Space,
+  Punct,
+  Han
+}
+impl Prop {
+  fn parse(s:&str)-> Prop {
+    match s {
+      "L"|"Letter"|"Alphabetic"=> Prop::Letter,
+      "Lu"|"Uppercase"=> Prop::Upper,
+      "Ll"|"Lowercase"=> Prop::Lower,
+      "N"|"Nd"|"Number"=> Prop::Number,
+      "Z"|"White_Space"=> Prop::Space,
+      "P"|"Punct"=> Prop::Punct,
+      "Han"=> Prop::Han,
+      _=> panic!("不支持的Unicode属性'{}'", s)
+    }
+  }
+  fn has(&self, c:char)-> bool {
+    match self {
+      Prop::Letter=> c.is_alphabetic(),
+      Prop::Upper=> c.is_uppercase(),
+      Prop::Lower=> c.is_lowercase(),
+      Prop::Number=> c.is_numeric(),
+      Prop::Space=> c.is_whitespace(),
+      Prop::Punct=> c.is_ascii_punctuation() || matches!(c,
+        '\u{2010}'..='\u{2027}'|'\u{3001}'..='\u{3003}'|'\u{3008}'..='\u{3011}'|'\u{FF01}'..='\u{FF0F}'|
+        '\u{FF1A}'..='\u{FF20}'|'\u{FF3B}'..='\u{FF40}'|'\u{FF5B}'..='\u{FF65}'),
+      Prop::Han=> matches!(c,
+        '\u{3400}'..='\u{4DBF}'|'\u{4E00}'..='\u{9FFF}'|'\u{F900}'..='\u{FAFF}'|
+        '\u{20000}'..='\u{2A6DF}'|'\u{2A700}'..='\u{2EBEF}'|'\u{30000}'..='\u{3134F}')
+    }
+  }
+}
+
+/// 字符类中的一项, bool表示取反
+#[derive(Clone)]
+enum ClassItem {
+  Range(char, char),
+  Digit(bool),
+  Word(bool),
+  Space(bool),
+  Prop(Prop, bool)
+}
+impl ClassItem {
+  fn has(&self, c:char)-> bool {
+    match self {
+      ClassItem::Range(a, b)=> *a <= c && c <= *b,
+      ClassItem::Digit(n)=> c.is_ascii_digit() != *n,
+      ClassItem::Word(n)=> is_word(c) != *n,
+      ClassItem::Space(n)=> c.is_whitespace() != *n,
+      ClassItem::Prop(p, n)=> p.has(c) != *n
+    }
+  }
+}
+
+#[derive(Clone)]
+struct Class {
+  items: Vec<ClassItem>,
+  neg: bool,
+  icase: bool
+}
+impl Class {
+  fn has(&self, c:char)-> bool {
+    let hit = |c:char|self.items.iter().any(|it|it.has(c));
+    let found = hit(c) || (self.icase && (hit(lower(c)) || hit(upper(c))));
+    found != self.neg
+  }
+}
+
+fn is_word(c:char)-> bool {
+  c.is_alphanumeric() || c == '_'
+}
+fn lower(c:char)-> char {
+  c.to_lowercase().next().unwrap_or(c)
+}
+fn upper(c:char)-> char {
+  c.to_uppercase().next().unwrap_or(c)
+}
+
+#[derive(Clone, Copy)]
+enum Look {
+  Start,
+  End,
+  LineStart,
+  LineEnd,
+  WordB,
+  NotWordB
+}
+
+enum Node {
+  Char(char, bool),
+  Any(bool),
+  Class(Class),
+  Assert(Look),
+  Group(Box<Node>, Option<usize>),
+  Cat(Vec<Node>),
+  Alt(Vec<Node>),
+  Repeat {node: Box<Node>, min: usize, max: Option<usize>, greedy: bool},
+  Backref(usize, bool),
+  Ahead(Box<Node>, bool)
+}
+
+#[derive(Clone)]
+enum Inst {
+  Match,
+  /// 字符和是否忽略大小写
+  Char(char, bool),
+  /// 是否匹配换行
+  Any(bool),
+  Class(usize),
+  Assert(Look),
+  Save(usize),
+  /// 优先尝试前者
+  Split(usize, usize),
+  Jmp(usize),
+  Backref(usize, bool),
+  /// 位置和记录的循环起点相同时跳出循环, 防止可空的循环体无限空转
+  Progress(usize, usize),
+  /// 前瞻的子程序和是否取反
+  Ahead(usize, bool)
+}
+
+/// 编译后的正则
+#[derive(Clone)]
+pub struct Regex {
+  src: String,
+  flags: String,
+  /// 第0个为主程序, 其余为前瞻的子程序
+  progs: Vec<Vec<Inst>>,
+  classes: Vec<Class>,
+  /// 分组数量, 包括代表整个匹配的第0组
+  groups: usize,
+  /// 匹配时记录位置的槽数, 分组之后是可空循环的起点
+  slots: usize,
+  names: Vec<(String, usize)>,
+  backref: bool,
+  /// 以`\A`开头, 只需要在文本开头尝试
+  anchored: bool
+}
+
+// - 解析 -
+
+struct Parser {
+  chars: Vec<char>,
+  i: usize,
+  flags: Flags,
+  groups: usize,
+  names: Vec<(String, usize)>,
+  backref: bool
+}
+
+impl Parser {
+  fn peek(&self)-> Option<char> {
+    self.chars.get(self.i).copied()
+  }
+  fn eat(&mut self, c:char)-> bool {
+    if self.peek() == Some(c) {
+      self.i += 1;
+      true
+    }else {false}
+  }
+  fn next(&mut self)-> char {
+    let c = self.peek().unwrap_or_else(||panic!("正则意外结束"));
+    self.i += 1;
+    c
+  }
+
+  fn alt(&mut self)-> Node {
+    let mut alts = vec![self.cat()];
+    while self.eat('|') {
+      alts.push(self.cat());
+    }
+    if alts.len() == 1 {alts.pop().unwrap()}else {Node::Alt(alts)}
+  }
+
+  fn cat(&mut self)-> Node {
+    let mut v = Vec::new();
+    while let Some(c) = self.peek() {
+      if c == '|' || c == ')' {
+        break;
+      }
+      // 单独的(?i)只改变标志
+      if let Some(atom) = self.atom() {
+        let q = self.quant(atom);
+        v.push(q);
+      }
+    }
+    Node::Cat(v)
+  }
+
+  fn atom(&mut self)-> Option<Node> {
+    let f = self.flags;
+    Some(match self.next() {
+      '('=> return self.group(),
+      '['=> Node::Class(self.class()),
+      '.'=> Node::Any(f.dotall),
+      '^'=> Node::Assert(if f.multi {Look::LineStart}else {Look::Start}),
+      '$'=> Node::Assert(if f.multi {Look::LineEnd}else {Look::End}),
+      '\\'=> self.escape(),
+      c@('*'|'+'|'?')=> panic!("量词'{}'前没有可以重复的内容", c),
+      c=> Node::Char(c, f.icase)
+    })
+  }
+
+  /// 解析原子后面的量词
+  fn quant(&mut self, node:Node)-> Node {
+    let (min, max) = match self.peek() {
+      Some('{')=> match self.braces() {
+        Some(v)=> v,
+        None=> return node
+      }
+      Some(c@('*'|'+'|'?'))=> {
+        self.i += 1;
+        match c {
+          '*'=> (0, None),
+          '+'=> (1, None),
+          _=> (0, Some(1))
+        }
+      }
+      _=> return node
+    };
+    if let Some(max) = max {
+      assert!(min <= max, "量词{{{},{}}}的最小值大于最大值", min, max);
+    }
+    let greedy = !self.eat('?');
+    Node::Repeat {node: Box::new(node), min, max, greedy}
+  }
+
+  /// 解析`{n}`, `{n,}`, `{n,m}`, 不是量词时不移动位置
+  fn braces(&mut self)-> Option<(usize, Option<usize>)> {
+    let start = self.i;
+    let mut end = start + 1;
+    while self.chars.get(end).map_or(false, |c|*c != '}') {
+      end += 1;
+    }
+    if end >= self.chars.len() {
+      return None;
+    }
+    let inner:String = self.chars[start + 1..end].iter().collect();
+    let num = |s:&str|s.trim().parse::<usize>().ok();
+    let res = match inner.split_once(',') {
+      None=> num(&inner).map(|n|(n, Some(n))),
+      Some((a, b))=> match (num(a), b.trim()) {
+        (Some(a), "")=> Some((a, None)),
+        (Some(a), b)=> num(b).map(|b|(a, Some(b))),
+        _=> None
+      }
+    };
+    if res.is_some() {
+      self.i = end + 1;
+    }
+    res
+  }
+
+  fn group(&mut self)-> Option<Node> {
+    let saved = self.flags;
+    let mut cap = None;
+    let mut ahead = None;
+    if self.eat('?') {
+      match self.next() {
+        ':'=> (),
+        '='=> ahead = Some(false),
+        '!'=> ahead = Some(true),
+        '<' if matches!(self.peek(), Some('='|'!'))=> panic!("不支持后顾断言"),
+        c@('<'|'P')=> {
+          if c == 'P' {
+            assert!(self.next() == '<', "命名分组应写作(?<name>...)");
+          }
+          let mut name = String::new();
+          loop {
+            match self.next() {
+              '>'=> break,
+              c=> name.push(c)
+            }
+          }
+          assert!(!name.is_empty(), "分组名不能为空");
+          self.groups += 1;
+          self.names.push((name, self.groups));
+          cap = Some(self.groups);
+        }
+        _=> {
+          // 标志组(?ims)或(?ims:...)
+          self.i -= 1;
+          let mut on = true;
+          loop {
+            match self.next() {
+              '-'=> on = false,
+              'i'=> self.flags.icase = on,
+              'm'=> self.flags.multi = on,
+              's'=> self.flags.dotall = on,
+              ')'=> return None,
+              ':'=> break,
+              c=> panic!("未知的正则标志'{}'", c)
+            }
+          }
+        }
+      }
+    }else {
+      self.groups += 1;
+      cap = Some(self.groups);
+    }
+    let inner = self.alt();
+    assert!(self.eat(')'), "正则的'('未闭合");
+    self.flags = saved;
+    Some(match ahead {
+      Some(neg)=> Node::Ahead(Box::new(inner), neg),
+      None=> Node::Group(Box::new(inner), cap)
+    })
+  }
+
+  /// 解析`[...]`
+  fn class(&mut self)-> Class {
+    let neg = self.eat('^');
+    let mut items = Vec::new();
+    let mut first = true;
+    loop {
+      let c = self.next();
+      if c == ']' && !first {
+        break;
+      }
+      first = false;
+      let lo = if c == '\\' {
+        match self.escape_item() {
+          Ok(c)=> c,
+          Err(item)=> {
+            items.push(item);
+            continue;
+          }
+        }
+      }else {c};
+      // 范围
+      if self.peek() == Some('-') && self.chars.get(self.i + 1).map_or(false, |c|*c != ']') {
+        self.i += 1;
+        let hi = match self.next() {
+          '\\'=> self.escape_item().unwrap_or_else(|_|panic!("字符类的范围不能以\\d等结尾")),
+          c=> c
+        };
+        assert!(lo <= hi, "字符类的范围{}-{}顺序错误", lo, hi);
+        items.push(ClassItem::Range(lo, hi));
+      }else {
+        items.push(ClassItem::Range(lo, lo));
+      }
+    }
+    Class {items, neg, icase: self.flags.icase}
+  }
+
+  /// 字符类内外都能用的转义, 得到字符或字符类项
+  fn escape_item(&mut self)-> Result<char, ClassItem> {
+    Ok(match self.next() {
+      'd'=> return Err(ClassItem::Digit(false)),
+      'D'=> return Err(ClassItem::Digit(true)),
+      'w'=> return Err(ClassItem::Word(false)),
+      'W'=> return Err(ClassItem::Word(true)),
+      's'=> return Err(ClassItem::Space(false)),
+      'S'=> return Err(ClassItem::Space(true)),
+      c@('p'|'P')=> {
+        let name:String = if self.eat('{') {
+          let mut s = String::new();
+          loop {
+            match self.next() {
+              '}'=> break,
+              c=> s.push(c)
+            }
+          }
+          s
+        }else {self.next().to_string()};
+        return Err(ClassItem::Prop(Prop::parse(&name), c == 'P'));
+      }
+      'n'=> '\n',
+      't'=> '\t',
+      'r'=> '\r',
+      'f'=> '\x0c',
+      'v'=> '\x0b',
+      '0'=> '\0',
+      c@('x'|'u')=> {
+        let hex:String = if self.eat('{') {
+          let mut s = String::new();
+          loop {
+            match self.next() {
+              '}'=> break,
+              c=> s.push(c)
+            }
+          }
+          s
+        }else {
+          (0..if c == 'x' {2}else {4}).map(|_|self.next()).collect()
+        };
+        u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32)
+          .unwrap_or_else(||panic!("无效的字符编码'\\{}{}'", c, hex))
+      }
+      c if c.is_ascii_alphanumeric()=> panic!("未知的转义'\\{}'", c),
+      c=> c
+    })
+  }
+
+  /// 字符类外的转义
+  fn escape(&mut self)-> Node {
+    let icase = self.flags.icase;
+    match self.peek() {
+      Some('b')=> {self.i += 1; Node::Assert(Look::WordB)}
+      Some('B')=> {self.i += 1; Node::Assert(Look::NotWordB)}
+      Some('A')=> {self.i += 1; Node::Assert(Look::Start)}
+      Some('z')=> {self.i += 1; Node::Assert(Look::End)}
+      Some('1'..='9')=> {
+        let mut n = 0;
+        while let Some(d) = self.peek().and_then(|c|c.to_digit(10)) {
+          n = n * 10 + d as usize;
+          self.i += 1;
+        }
+        assert!(n <= self.groups, "反向引用\\{}指向了不存在的分组", n);
+        self.backref = true;
+        Node::Backref(n, icase)
+      }
+      Some('k')=> {
+        self.i += 1;
+        assert!(self.eat('<'), "命名反向引用应写作\\k<name>");
+        let mut name = String::new();
+        loop {
+          match self.next() {
+            '>'=> break,
+            c=> name.push(c)
+          }
+        }
+        let n = self.names.iter().find(|(n, _)|*n == name)
+          .unwrap_or_else(||panic!("反向引用指向了不存在的分组'{}'", name)).1;
+        self.backref = true;
+        Node::Backref(n, icase)
+      }
+      _=> match self.escape_item() {
+        Ok(c)=> Node::Char(c, icase),
+        Err(item)=> Node::Class(Class {items: vec![item], neg: false, icase})
+      }
+    }
+  }
+}
+
+// - 编译 -
+
+struct Compiler {
+  progs: Vec<Vec<Inst>>,
+  classes: Vec<Class>,
+  slots: usize
+}
+
+/// 节点是否可能匹配空串
+fn nullable(node:&Node)-> bool {
+  match node {
+    Node::Char(..)|Node::Any(_)|Node::Class(_)=> false,
+    Node::Assert(_)|Node::Backref(..)|Node::Ahead(..)=> true,
+    Node::Group(inner, _)=> nullable(inner),
+    Node::Cat(v)=> v.iter().all(nullable),
+    Node::Alt(v)=> v.iter().any(nullable),
+    Node::Repeat {node, min, ..}=> *min == 0 || nullable(node)
+  }
+}
+
+impl Compiler {
+  fn emit(&mut self, p:&mut Vec<Inst>, node:&Node) {
+    assert!(p.len() < MAX_INSTS, "正则过于复杂");
+    match node {
+      Node::Char(c, icase)=> p.push(Inst::Char(*c, *icase)),
+      Node::Any(dotall)=> p.push(Inst::Any(*dotall)),
+      Node::Class(c)=> {
+        self.classes.push(c.clone());
+        p.push(Inst::Class(self.classes.len() - 1));
+      }
+      Node::Assert(l)=> p.push(Inst::Assert(*l)),
+      Node::Group(inner, cap)=> match cap {
+        Some(n)=> {
+          p.push(Inst::Save(n * 2));
+          self.emit(p, inner);
+          p.push(Inst::Save(n * 2 + 1));
+        }
+        None=> self.emit(p, inner)
+      }
+      Node::Cat(v)=> for n in v {
+        self.emit(p, n);
+      }
+      Node::Alt(v)=> {
+        let mut jumps = Vec::new();
+        for (i, n) in v.iter().enumerate() {
+          if i + 1 < v.len() {
+            let split = p.len();
+            p.push(Inst::Split(split + 1, 0));
+            self.emit(p, n);
+            jumps.push(p.len());
+            p.push(Inst::Jmp(0));
+            let next = p.len();
+            p[split] = Inst::Split(split + 1, next);
+          }else {
+            self.emit(p, n);
+          }
+        }
+        let end = p.len();
+        for j in jumps {
+          p[j] = Inst::Jmp(end);
+        }
+      }
+      Node::Repeat {node, min, max, greedy}=> {
+        for _ in 0..*min {
+          self.emit(p, node);
+        }
+        let split = |l:usize, exit:usize|if *greedy {Inst::Split(l + 1, exit)}else {Inst::Split(exit, l + 1)};
+        match max {
+          None=> {
+            let l = p.len();
+            p.push(Inst::Jmp(0));
+            // 循环体能匹配空串时, 空的一次迭代之后不再继续循环
+            let progress = if nullable(node) {
+              let s = self.slots;
+              self.slots += 1;
+              p.push(Inst::Save(s));
+              self.emit(p, node);
+              p.push(Inst::Progress(s, 0));
+              Some((p.len() - 1, s))
+            }else {
+              self.emit(p, node);
+              None
+            };
+            p.push(Inst::Jmp(l));
+            let exit = p.len();
+            p[l] = split(l, exit);
+            if let Some((i, s)) = progress {
+              p[i] = Inst::Progress(s, exit);
+            }
+          }
+          Some(max)=> {
+            let mut holes = Vec::new();
+            for _ in *min..*max {
+              holes.push(p.len());
+              p.push(Inst::Jmp(0));
+              self.emit(p, node);
+            }
+            let exit = p.len();
+            for l in holes {
+              p[l] = split(l, exit);
+            }
+          }
+        }
+      }
+      Node::Backref(n, icase)=> p.push(Inst::Backref(*n, *icase)),
+      Node::Ahead(inner, neg)=> {
+        let mut sub = Vec::new();
+        self.emit(&mut sub, inner);
+        sub.push(Inst::Match);
+        self.progs.push(sub);
+        p.push(Inst::Ahead(self.progs.len() - 1, *neg));
+      }
+    }
+  }
+}
+
+impl Regex {
+  pub fn new(src:&str, flags:&str)-> Regex {
+    let mut f = Flags {icase: false, multi: false, dotall: false};
+    for c in flags.chars() {
+      match c {
+        'i'=> f.icase = true,
+        'm'=> f.multi = true,
+        's'=> f.dotall = true,
+        _=> panic!("未知的正则标志'{}'", c)
+      }
+    }
+    let mut parser = Parser {
+      chars: src.chars().collect(), i: 0, flags: f, groups: 0, names: Vec::new(), backref: false
+    };
+    let node = parser.alt();
+    if parser.i < parser.chars.len() {
+      panic!("正则中有多余的')'");
+    }
+
+    let groups = parser.groups + 1;
+    let mut c = Compiler {progs: vec![Vec::new()], classes: Vec::new(), slots: groups * 2};
+    let mut main = Vec::new();
+    c.emit(&mut main, &node);
+    main.push(Inst::Match);
+    let anchored = matches!(main.first(), Some(Inst::Assert(Look::Start)));
+    c.progs[0] = main;
+    Regex {
+      src: src.to_string(), flags: flags.to_string(), progs: c.progs, classes: c.classes,
+      groups, slots: c.slots, names: parser.names, backref: parser.backref, anchored
+    }
+  }
+}
+
+// - 匹配 -
+
+/// 被匹配的文本, Str按UTF-8字符读取, Buf按字节读取
+#[derive(Clone, Copy)]
+struct Input<'a> {
+  h: &'a [u8],
+  utf8: bool
+}
+impl Input<'_> {
+  fn at(&self, pos:usize)-> Option<(char, usize)> {
+    if pos >= self.h.len() {
+      return None;
+    }
+    if !self.utf8 {
+      return Some((self.h[pos] as char, 1));
+    }
+    // 位置总是在字符边界上
+    let c = unsafe {std::str::from_utf8_unchecked(&self.h[pos..])}.chars().next()?;
+    Some((c, c.len_utf8()))
+  }
+  fn before(&self, pos:usize)-> Option<char> {
+    if pos == 0 {
+      return None;
+    }
+    let mut i = pos - 1;
+    while self.utf8 && i > 0 && self.h[i] & 0xc0 == 0x80 {
+      i -= 1;
+    }
+    self.at(i).map(|c|c.0)
+  }
+}
+
+enum Job {
+  Explore(usize, usize),
+  Restore(usize, Option<usize>)
+}
+
+struct Matcher<'a> {
+  re: &'a Regex,
+  inp: Input<'a>,
+  /// 主程序走过的(指令, 位置), 走过的状态不会再成功
+  visited: Vec<u64>,
+  /// 本次尝试标记过的状态, 成功后要清除
+  touched: Vec<usize>,
+  /// 前瞻在各位置的结果
+  aheads: HashMap<(usize, usize), bool>,
+  steps: usize
+}
+
+impl<'a> Matcher<'a> {
+  fn new(re:&'a Regex, inp:Input<'a>)-> Self {
+    let states = re.progs[0].len() * (inp.h.len() + 1);
+    // 状态过多时不使用位图, 改为每次尝试单独记录
+    let visited = if !re.backref && states <= 1 << 28 {
+      vec![0; states / 64 + 1]
+    }else {Vec::new()};
+    Matcher {re, inp, visited, touched: Vec::new(), aheads: HashMap::new(), steps: 0}
+  }
+
+  fn check(&self, l:Look, pos:usize)-> bool {
+    let h = self.inp.h;
+    match l {
+      Look::Start=> pos == 0,
+      Look::End=> pos == h.len(),
+      Look::LineStart=> pos == 0 || h[pos - 1] == b'\n',
+      Look::LineEnd=> pos == h.len() || h[pos] == b'\n',
+      Look::WordB|Look::NotWordB=> {
+        let a = self.inp.before(pos).map_or(false, is_word);
+        let b = self.inp.at(pos).map_or(false, |c|is_word(c.0));
+        (a != b) == matches!(l, Look::WordB)
+      }
+    }
+  }
+
+  /// 运行一个程序, 成功时返回结束位置
+  fn run(&mut self, prog:usize, pos:usize, slots:&mut [Option<usize>])-> Option<usize> {
+    let re = self.re;
+    let insts = &re.progs[prog];
+    let width = self.inp.h.len() + 1;
+    let dense = prog == 0 && !self.visited.is_empty();
+    let mut sparse = HashSet::new();
+    let mut stack = vec![Job::Explore(0, pos)];
+
+    while let Some(job) = stack.pop() {
+      let (mut pc, mut pos) = match job {
+        Job::Restore(s, v)=> {
+          slots[s] = v;
+          continue;
+        }
+        Job::Explore(pc, pos)=> (pc, pos)
+      };
+      loop {
+        if re.backref {
+          self.steps += 1;
+          assert!(self.steps < MAX_STEPS, "正则回溯次数过多");
+        }else {
+          let k = pc * width + pos;
+          if dense {
+            if self.visited[k / 64] >> (k % 64) & 1 == 1 {
+              break;
+            }
+            self.visited[k / 64] |= 1 << (k % 64);
+            self.touched.push(k);
+          }else if !sparse.insert(k) {
+            break;
+          }
+        }
+
+        match &insts[pc] {
+          Inst::Match=> return Some(pos),
+          Inst::Char(c, icase)=> match self.inp.at(pos) {
+            Some((ch, n)) if ch == *c || (*icase && lower(ch) == lower(*c))=> {
+              pc += 1;
+              pos += n;
+            }
+            _=> break
+          }
+          Inst::Any(dotall)=> match self.inp.at(pos) {
+            Some((ch, n)) if *dotall || ch != '\n'=> {
+              pc += 1;
+              pos += n;
+            }
+            _=> break
+          }
+          Inst::Class(i)=> match self.inp.at(pos) {
+            Some((ch, n)) if re.classes[*i].has(ch)=> {
+              pc += 1;
+              pos += n;
+            }
+            _=> break
+          }
+          Inst::Assert(l)=> if self.check(*l, pos) {pc += 1}else {break}
+          Inst::Save(s)=> {
+            stack.push(Job::Restore(*s, slots[*s]));
+            slots[*s] = Some(pos);
+            pc += 1;
+          }
+          Inst::Split(a, b)=> {
+            stack.push(Job::Explore(*b, pos));
+            pc = *a;
+          }
+          Inst::Jmp(a)=> pc = *a,
+          Inst::Backref(g, icase)=> {
+            // 没有参与匹配的分组无法被引用
+            let (s, e) = match (slots[g * 2], slots[g * 2 + 1]) {
+              (Some(s), Some(e))=> (s, e),
+              _=> break
+            };
+            let want = Input {h: &self.inp.h[s..e], utf8: self.inp.utf8};
+            let mut i = 0;
+            let mut p = pos;
+            let ok = loop {
+              let Some((a, n)) = want.at(i) else {break true};
+              match self.inp.at(p) {
+                Some((b, m)) if a == b || (*icase && lower(a) == lower(b))=> {
+                  i += n;
+                  p += m;
+                }
+                _=> break false
+              }
+            };
+            if !ok {
+              break;
+            }
+            pc += 1;
+            pos = p;
+          }
+          Inst::Progress(s, exit)=> pc = if slots[*s] == Some(pos) {*exit}else {pc + 1},
+          Inst::Ahead(sub, neg)=> {
+            let found = match self.aheads.get(&(*sub, pos)) {
+              Some(b)=> *b,
+              None=> {
+                let mut sub_slots = vec![None; re.slots];
+                let b = self.run(*sub, pos, &mut sub_slots).is_some();
+                self.aheads.insert((*sub, pos), b);
+                b
+              }
+            };
+            if found == *neg {
+              break;
+            }
+            pc += 1;
+          }
+        }
+      }
+    }
+    None
+  }
+
+  /// 从start开始寻找第一个匹配, 返回各分组的字节位置
+  fn exec(&mut self, start:usize)-> Option<Vec<Option<usize>>> {
+    let mut pos = start;
+    loop {
+      if self.re.anchored && pos > 0 {
+        return None;
+      }
+      let mut slots = vec![None; self.re.slots];
+      self.touched.clear();
+      if let Some(end) = self.run(0, pos, &mut slots) {
+        // 成功路径上的状态并不是失败状态
+        for k in self.touched.drain(..) {
+          self.visited[k / 64] &= !(1 << (k % 64));
+        }
+        slots[0] = Some(pos);
+        slots[1] = Some(end);
+        return Some(slots);
+      }
+      match self.inp.at(pos) {
+        Some((_, n))=> pos += n,
+        None=> return None
+      }
+    }
+  }
+}
+
+/// 依次找出所有不重叠的匹配, 空匹配后会前进一个字符
+fn each_match(re:&Regex, inp:Input, limit:usize, mut f:impl FnMut(Vec<Option<usize>>)) {
+  let mut m = Matcher::new(re, inp);
+  let mut pos = 0;
+  let mut n = 0;
+  while n < limit {
+    let Some(slots) = m.exec(pos) else {break};
+    let (s, e) = (slots[0].unwrap(), slots[1].unwrap());
+    pos = if s == e {
+      match inp.at(e) {
+        Some((_, n))=> e + n,
+        None=> inp.h.len() + 1
+      }
+    }else {e};
+    f(slots);
+    n += 1;
+    if pos > inp.h.len() {
+      break;
+    }
+  }
+}
+
+// - Key接口 -
+
+pub fn init()-> (Interned, *mut NativeClassDef) {
+  unsafe {
+    let s = new_static_class(b"Regex", vec![
+      (intern(b"new"), s_new),
+      (intern(b"escape"), s_escape)
+    ]);
+    REGEX_CLASS = s.1;
+    let re = &mut *REGEX_CLASS;
+    re.methods.push((intern(b"test"), test));
+    re.methods.push((intern(b"find"), find));
+    re.methods.push((intern(b"find_all"), find_all));
+    re.methods.push((intern(b"captures"), captures));
+    re.methods.push((intern(b"captures_all"), captures_all));
+    re.methods.push((intern(b"replace"), replace));
+    re.methods.push((intern(b"split"), split));
+    re.getter = getter;
+    re.to_str = |inst|format!("/{}/{}", this(inst).src, this(inst).flags);
+    re.onclone = |inst|NativeInstance {
+      cls: inst.cls, v: Box::into_raw(Box::new(this(inst).clone())) as usize, w: 0
+    };
+    re.ondrop = |inst|drop(Box::from_raw(inst.v as *mut Regex));
+    s
+  }
+}
+
+fn this(inst:&NativeInstance)-> &Regex {
+  unsafe {&*(inst.v as *const Regex)}
+}
+
+/// Regex::new(正则, 标志)
+fn s_new(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let src = match args.get(0).map(|v|&**v) {
+    Some(Litr::Str(s))=> s,
+    _=> panic!("Regex::new第一个参数必须是Str")
+  };
+  let flags = match args.get(1).map(|v|&**v) {
+    Some(Litr::Str(s))=> s.as_str(),
+    None|Some(Litr::Uninit)=> "",
+    _=> panic!("Regex::new第二个参数必须是Str")
+  };
+  let re = Box::new(Regex::new(src, flags));
+  Litr::Ninst(NativeInstance {cls: unsafe{REGEX_CLASS}, v: Box::into_raw(re) as usize, w: 0})
+}
+
+/// 转义字符串中的正则符号
+fn s_escape(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let s = match args.get(0).map(|v|&**v) {
+    Some(Litr::Str(s))=> s,
+    _=> panic!("Regex::escape第一个参数必须是Str")
+  };
+  let mut out = String::with_capacity(s.len());
+  for c in s.chars() {
+    if "\\.+*?()|[]{}^$-#&~".contains(c) {
+      out.push('\\');
+    }
+    out.push(c);
+  }
+  Litr::Str(out.into())
+}
+
+fn getter(inst:&NativeInstance, get:Interned)-> Litr {
+  let re = this(inst);
+  match get.vec() {
+    b"source"=> Litr::Str(re.src.clone().into()),
+    b"flags"=> Litr::Str(re.flags.clone().into()),
+    b"groups"=> Litr::Uint(re.groups - 1),
+    _=> Litr::Uninit
+  }
+}
+
+/// 取出被匹配的Str或Buf
+fn input<'a>(args:&'a [CalcRef], name:&str)-> Input<'a> {
+  match args.get(0).map(|v|&**v) {
+    Some(Litr::Str(s))=> Input {h: s.as_bytes(), utf8: true},
+    Some(Litr::Buf(b))=> Input {h: b, utf8: false},
+    _=> panic!("Regex.{}第一个参数必须是Str或Buf", name)
+  }
+}
+
+/// 将Key的起始位置转为字节位置
+fn start_at(inp:Input, arg:Option<&CalcRef>)-> usize {
+  let n = match arg.map(|v|&**v) {
+    Some(Litr::Uint(n))=> *n,
+    Some(Litr::Int(n)) if *n >= 0=> *n as usize,
+    None|Some(Litr::Uninit)=> 0,
+    _=> panic!("起始位置必须是非负整数")
+  };
+  if !inp.utf8 {
+    return n.min(inp.h.len());
+  }
+  let s = unsafe {std::str::from_utf8_unchecked(inp.h)};
+  s.char_indices().nth(n).map_or(s.len(), |(i, _)|i)
+}
+
+/// 将字节位置转为Key中的位置, 位置需要递增地查询
+struct PosMap<'a> {
+  inp: Input<'a>,
+  byte: usize,
+  ch: usize
+}
+impl PosMap<'_> {
+  fn get(&mut self, b:usize)-> usize {
+    if !self.inp.utf8 {
+      return b;
+    }
+    if b < self.byte {
+      self.byte = 0;
+      self.ch = 0;
+    }
+    self.ch += self.inp.h[self.byte..b].iter().filter(|c|**c & 0xc0 != 0x80).count();
+    self.byte = b;
+    self.ch
+  }
+}
+
+/// 截取匹配到的文本, 和输入的类型相同
+fn piece(inp:Input, s:usize, e:usize)-> Litr {
+  if inp.utf8 {
+    Litr::Str((unsafe {std::str::from_utf8_unchecked(&inp.h[s..e])}.to_string()).into())
+  }else {
+    Litr::Buf(inp.h[s..e].to_vec().into())
+  }
+}
+
+/// 匹配结果`{text, start, end}`
+fn match_obj(inp:Input, slots:&[Option<usize>], pos:&mut PosMap)-> Litr {
+  let (s, e) = (slots[0].unwrap(), slots[1].unwrap());
+  let mut o = ObjMap::new();
+  o.insert(intern(b"text"), piece(inp, s, e));
+  o.insert(intern(b"start"), Litr::Uint(pos.get(s)));
+  o.insert(intern(b"end"), Litr::Uint(pos.get(e)));
+  Litr::Obj(o.into())
+}
+
+/// 匹配结果加上`groups`列表(第0项为整个匹配)和`named`对象, 未参与匹配的分组为uninit
+fn captures_obj(re:&Regex, inp:Input, slots:&[Option<usize>], pos:&mut PosMap)-> Litr {
+  let group = |i:usize|match (slots[i * 2], slots[i * 2 + 1]) {
+    (Some(s), Some(e))=> piece(inp, s, e),
+    _=> Litr::Uninit
+  };
+  let mut o = match match_obj(inp, slots, pos) {
+    Litr::Obj(o)=> o,
+    _=> unreachable!()
+  };
+  o.insert(intern(b"groups"), Litr::List((0..re.groups).map(group).collect()));
+  o.insert(intern(b"named"), Litr::Obj(
+    re.names.iter().map(|(n, i)|(intern(n.as_bytes()), group(*i))).collect()
+  ));
+  Litr::Obj(o)
+}
+
+fn test(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let inp = input(&args, "test");
+  Litr::Bool(Matcher::new(this(inst), inp).exec(0).is_some())
+}
+
+/// 第二个参数为起始位置
+fn find(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let inp = input(&args, "find");
+  let start = start_at(inp, args.get(1));
+  match Matcher::new(this(inst), inp).exec(start) {
+    Some(slots)=> match_obj(inp, &slots, &mut PosMap {inp, byte: 0, ch: 0}),
+    None=> Litr::Uninit
+  }
+}
+
+fn find_all(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let inp = input(&args, "find_all");
+  let mut pos = PosMap {inp, byte: 0, ch: 0};
+  let mut v = Vec::new();
+  each_match(this(inst), inp, usize::MAX, |slots|v.push(match_obj(inp, &slots, &mut pos)));
+  Litr::List(v.into())
+}
+
+fn captures(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let inp = input(&args, "captures");
+  let start = start_at(inp, args.get(1));
+  let re = this(inst);
+  match Matcher::new(re, inp).exec(start) {
+    Some(slots)=> captures_obj(re, inp, &slots, &mut PosMap {inp, byte: 0, ch: 0}),
+    None=> Litr::Uninit
+  }
+}
+
+fn captures_all(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let inp = input(&args, "captures_all");
+  let re = this(inst);
+  let mut pos = PosMap {inp, byte: 0, ch: 0};
+  let mut v = Vec::new();
+  each_match(re, inp, usize::MAX, |slots|v.push(captures_obj(re, inp, &slots, &mut pos)));
+  Litr::List(v.into())
+}
+
+/// 展开替换模板中的`$0`, `$1`, `${name}`和`$$`
+fn expand(re:&Regex, inp:Input, slots:&[Option<usize>], tpl:&[u8], out:&mut Vec<u8>) {
+  let mut i = 0;
+  while i < tpl.len() {
+    if tpl[i] != b'$' || i + 1 >= tpl.len() {
+      out.push(tpl[i]);
+      i += 1;
+      continue;
+    }
+    let group = if tpl[i + 1] == b'$' {
+      out.push(b'$');
+      i += 2;
+      continue;
+    }else if tpl[i + 1] == b'{' {
+      let end = tpl[i..].iter().position(|c|*c == b'}').map(|n|n + i)
+        .unwrap_or_else(||panic!("替换模板中的'${{'未闭合"));
+      let name = String::from_utf8_lossy(&tpl[i + 2..end]).to_string();
+      i = end + 1;
+      match name.parse::<usize>() {
+        Ok(n)=> n,
+        Err(_)=> re.names.iter().find(|(n, _)|*n == name)
+          .unwrap_or_else(||panic!("替换模板引用了不存在的分组'{}'", name)).1
+      }
+    }else if tpl[i + 1].is_ascii_digit() {
+      let mut n = 0;
+      i += 1;
+      while i < tpl.len() && tpl[i].is_ascii_digit() {
+        n = n * 10 + (tpl[i] - b'0') as usize;
+        i += 1;
+      }
+      n
+    }else {
+      out.push(b'$');
+      i += 1;
+      continue;
+    };
+    assert!(group < re.groups, "替换模板引用了不存在的分组{}", group);
+    if let (Some(s), Some(e)) = (slots[group * 2], slots[group * 2 + 1]) {
+      out.extend_from_slice(&inp.h[s..e]);
+    }
+  }
+}
+
+/// replace(文本, 模板或函数, 次数), 函数会收到和captures相同的对象, 次数默认为全部替换
+fn replace(inst:&mut NativeInstance, args:Vec<CalcRef>, cx:Scope)-> Litr {
+  let inp = input(&args, "replace");
+  let re = this(inst);
+  let limit = match args.get(2).map(|v|&**v) {
+    Some(Litr::Uint(n))=> *n,
+    Some(Litr::Int(n)) if *n >= 0=> *n as usize,
+    None|Some(Litr::Uninit)=> usize::MAX,
+    _=> panic!("Regex.replace第三个参数必须是非负整数")
+  };
+  let with = args.get(1).unwrap_or_else(||panic!("Regex.replace需要传入替换模板或函数"));
+  let mut out = Vec::with_capacity(inp.h.len());
+  let mut last = 0;
+  let mut pos = PosMap {inp, byte: 0, ch: 0};
+  each_match(re, inp, limit, |slots|{
+    let (s, e) = (slots[0].unwrap(), slots[1].unwrap());
+    out.extend_from_slice(&inp.h[last..s]);
+    last = e;
+    match &**with {
+      Litr::Str(t)=> expand(re, inp, &slots, t.as_bytes(), &mut out),
+      Litr::Buf(t)=> expand(re, inp, &slots, t, &mut out),
+      Litr::Func(f)=> {
+        let caps = captures_obj(re, inp, &slots, &mut pos);
+        match cx.call(vec![CalcRef::Own(caps)], f) {
+          Litr::Str(s)=> out.extend_from_slice(s.as_bytes()),
+          Litr::Buf(b)=> out.extend_from_slice(&b),
+          v=> out.extend_from_slice(v.str().as_bytes())
+        }
+      }
+      _=> panic!("Regex.replace第二个参数必须是Str, Buf或函数")
+    }
+  });
+  out.extend_from_slice(&inp.h[last..]);
+  if inp.utf8 {
+    Litr::Str(String::from_utf8(out).unwrap_or_else(|_|panic!("Regex.replace的结果不是有效的UTF-8")).into())
+  }else {
+    Litr::Buf(out.into())
+  }
+}
+
+/// split(文本, 次数), 次数为最多切分的次数
+fn split(inst:&mut NativeInstance, args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let inp = input(&args, "split");
+  let limit = match args.get(1).map(|v|&**v) {
+    Some(Litr::Uint(n))=> *n,
+    Some(Litr::Int(n)) if *n >= 0=> *n as usize,
+    None|Some(Litr::Uninit)=> usize::MAX,
+    _=> panic!("Regex.split第二个参数必须是非负整数")
+  };
+  let mut v = Vec::new();
+  let mut last = 0;
+  each_match(this(inst), inp, limit, |slots|{
+    let (s, e) = (slots[0].unwrap(), slots[1].unwrap());
+    // 首尾的空匹配不切出空串
+    if s == e && (e == 0 || e == inp.h.len()) {
+      return;
+    }
+    v.push(piece(inp, last, s));
+    last = e;
+  });
+  v.push(piece(inp, last, inp.h.len()));
+  Litr::List(v.into())
+}
@@ -0,0 +1,130 @@
+//! Str, Buf, List和Obj使用的写时复制存储
+//!
+//! 复制值时只增加引用计数, 第一次通过`&mut`访问时如果仍被共享才真正复制,
+//! 因此传参和赋值都是O(1), 而Key的值语义保持不变
+
+use std::ops::{Deref, DerefMut};
+use std::rc::Rc;
+
+#[derive(Default)]
+pub struct Shared<T>(Rc<T>);
+
+impl<T> Shared<T> {
+  pub fn new(v:T)-> Self {
+    Shared(Rc::new(v))
+  }
+
+  /// 是否和其他值共用同一份数据
+  pub fn is_shared(&self)-> bool {
+    Rc::strong_count(&self.0) > 1
+  }
+}
+
+impl<T:Clone> Shared<T> {
+  /// 取出内部的值, 仍被共享时复制一份
+  pub fn into_inner(self)-> T {
+    Rc::try_unwrap(self.0).unwrap_or_else(|rc|(*rc).clone())
+  }
+}
+
+/// 大于0时复制不再共享数据
+static mut PINNED:usize = 0;
+
+/// 持有容器内部的可变引用时又要运行脚本(计算参数, 右值或调用方法)
+/// 
+/// 脚本中复制该容器的话, 副本会和引用指向同一份存储, 之后的写入就会改到副本上.
+/// 返回的守卫存活期间的复制都会真正复制数据
+pub fn pin()-> Pinned {
+  unsafe {PINNED += 1;}
+  Pinned
+}
+
+/// pin的守卫, 报错跳出时也会在drop时解除
+pub struct Pinned;
+impl Drop for Pinned {
+  fn drop(&mut self) {
+    unsafe {PINNED -= 1;}
+  }
+}
+
+impl<T:Clone> Clone for Shared<T> {
+  fn clone(&self)-> Self {
+    if unsafe {PINNED} > 0 {
+      return Shared::new((*self.0).clone());
+    }
+    Shared(self.0.clone())
+  }
+}
+
+impl<T> Deref for Shared<T> {
+  type Target = T;
+  fn deref(&self)-> &T {
+    &self.0
+  }
+}
+
+/// 写入前确保独占
+impl<T:Clone> DerefMut for Shared<T> {
+  fn deref_mut(&mut self)-> &mut T {
+    Rc::make_mut(&mut self.0)
+  }
+}
+
+impl<T> From<T> for Shared<T> {
+  fn from(v:T)-> Self {
+    Shared::new(v)
+  }
+}
+
+impl<T:PartialEq> PartialEq for Shared<T> {
+  fn eq(&self, other:&Self)-> bool {
+    Rc::ptr_eq(&self.0, &other.0) || *self.0 == *other.0
+  }
+}
+
+impl<T:PartialOrd> PartialOrd for Shared<T> {
+  fn partial_cmp(&self, other:&Self)-> Option<std::cmp::Ordering> {
+    self.0.partial_cmp(&other.0)
+  }
+}
+
+impl<T:std::hash::Hash> std::hash::Hash for Shared<T> {
+  fn hash<H:std::hash::Hasher>(&self, h:&mut H) {
+    self.0.hash(h)
+  }
+}
+
+impl<T:std::fmt::Debug> std::fmt::Debug for Shared<T> {
+  fn fmt(&self, f:&mut std::fmt::Formatter<'_>)-> std::fmt::Result {
+    self.0.fmt(f)
+  }
+}
+
+impl<T:std::fmt::Display> std::fmt::Display for Shared<T> {
+  fn fmt(&self, f:&mut std::fmt::Formatter<'_>)-> std::fmt::Result {
+    self.0.fmt(f)
+  }
+}
+
+impl<A, C:FromIterator<A>> FromIterator<A> for Shared<C> {
+  fn from_iter<I:IntoIterator<Item = A>>(iter:I)-> Self {
+    Shared::new(C::from_iter(iter))
+  }
+}
+
+impl<'a, C> IntoIterator for &'a Shared<C> where &'a C: IntoIterator {
+  type Item = <&'a C as IntoIterator>::Item;
+  type IntoIter = <&'a C as IntoIterator>::IntoIter;
+  fn into_iter(self)-> Self::IntoIter {
+    (&*self.0).into_iter()
+  }
+}
+
+/// 独占时直接移出, 否则复制一份
+impl<C:Clone + IntoIterator> IntoIterator for Shared<C> {
+  type Item = C::Item;
+  type IntoIter = C::IntoIter;
+  fn into_iter(self)-> Self::IntoIter {
+    self.into_inner().into_iter()
+  }
+}
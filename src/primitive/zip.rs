@@ -0,0 +1,961 @@
+//! DEFLATE压缩和解压, 以及zlib和gzip封装
+//!
+//! `Zip::gzip(data, 等级)`等一次性处理, `Zip::compressor`和`Zip::decompressor`
+//! 得到可以分段`write`的实例, 每次返回已经产出的数据, 结束时调用`finish`
+
+use super::*;
+use super::hash::Hasher;
+
+pub static mut COMPRESSOR_CLASS: *mut NativeClassDef = std::ptr::null_mut();
+pub static mut DECOMPRESSOR_CLASS: *mut NativeClassDef = std::ptr::null_mut();
+
+/// 回溯窗口大小
+const WINDOW:usize = 32768;
+/// 压缩时每块的输入长度
+const BLOCK:usize = 65536;
+
+const LEN_BASE:[u16; 29] = [
+  3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31,
+  35, 43, 51, 59, 67, 83, 99, 115, 131, 163, 195, 227, 258
+];
+const LEN_EXTRA:[u8; 29] = [
+  0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2,
+  3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0
+];
+const DIST_BASE:[u16; 30] = [
+  1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193,
+  257, 385, 513, 769, 1025, 1537, 2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577
+];
+const DIST_EXTRA:[u8; 30] = [
+  0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6,
+  7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13, 13
+];
+/// 码长的码长的排列顺序
+const CL_ORDER:[usize; 19] = [16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15];
+
+/// 压缩格式
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+  Raw,
+  Zlib,
+  Gzip
+}
+impl Format {
+  fn parse(s:&str)-> Format {
+    match s {
+      "deflate"|"raw"=> Format::Raw,
+      "zlib"=> Format::Zlib,
+      "gzip"=> Format::Gzip,
+      _=> panic!("未知的压缩格式'{}', 可用deflate, zlib, gzip", s)
+    }
+  }
+  fn name(&self)-> &'static str {
+    match self {
+      Format::Raw=> "deflate",
+      Format::Zlib=> "zlib",
+      Format::Gzip=> "gzip"
+    }
+  }
+  fn checksum(&self)-> Option<Hasher> {
+    match self {
+      Format::Raw=> None,
+      Format::Zlib=> Some(Hasher::new("adler32")),
+      Format::Gzip=> Some(Hasher::new("crc32"))
+    }
+  }
+}
+
+fn fixed_lens()-> (Vec<u8>, Vec<u8>) {
+  let mut lit = vec![8u8; 288];
+  lit[144..256].fill(9);
+  lit[256..280].fill(7);
+  (lit, vec![5u8; 30])
+}
+
+// - 解压 -
+
+/// 输入不足, 需要等待更多数据
+struct Short;
+
+/// 解码用的规范哈夫曼表
+struct Huff {
+  counts: [u16; 16],
+  syms: Vec<u16>
+}
+impl Huff {
+  fn new(lens:&[u8])-> Huff {
+    let mut counts = [0u16; 16];
+    for l in lens {
+      counts[*l as usize] += 1;
+    }
+    counts[0] = 0;
+    let mut left = 1i32;
+    for len in 1..16 {
+      left = (left << 1) - counts[len] as i32;
+      assert!(left >= 0, "压缩数据损坏: 哈夫曼编码过多");
+    }
+    let mut offs = [0u16; 16];
+    for len in 1..15 {
+      offs[len + 1] = offs[len] + counts[len];
+    }
+    let mut syms = vec![0u16; lens.len()];
+    for (s, l) in lens.iter().enumerate() {
+      if *l != 0 {
+        syms[offs[*l as usize] as usize] = s as u16;
+        offs[*l as usize] += 1;
+      }
+    }
+    Huff {counts, syms}
+  }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Stage {
+  Header,
+  Block,
+  Trailer,
+  Done
+}
+
+/// 可以分段输入的解压器
+pub struct Inflater {
+  fmt: Format,
+  /// 还未用完的输入
+  input: Vec<u8>,
+  /// input中已读到的位
+  pos: usize,
+  /// 已输出的数据, 只保留回溯需要的部分
+  hist: Vec<u8>,
+  stage: Stage,
+  check: Option<Hasher>,
+  /// 当前gzip成员的输出长度
+  member_len: usize,
+  total_in: usize,
+  total_out: usize
+}
+
+impl Inflater {
+  pub fn new(fmt:Format)-> Inflater {
+    Inflater {
+      fmt, input: Vec::new(), pos: 0, hist: Vec::new(),
+      stage: if fmt == Format::Raw {Stage::Block}else {Stage::Header},
+      check: fmt.checksum(), member_len: 0, total_in: 0, total_out: 0
+    }
+  }
+
+  fn bits(&mut self, n:u32)-> Result<u32, Short> {
+    if self.pos + n as usize > self.input.len() * 8 {
+      return Err(Short);
+    }
+    let v = (self.peek() & ((1u64 << n) - 1)) as u32;
+    self.pos += n as usize;
+    Ok(v)
+  }
+
+  /// 从当前位置起至少25位
+  fn peek(&self)-> u64 {
+    let i = self.pos >> 3;
+    let mut w = 0u64;
+    for k in 0..4 {
+      if let Some(b) = self.input.get(i + k) {
+        w |= (*b as u64) << (8 * k);
+      }
+    }
+    w >> (self.pos & 7)
+  }
+
+  fn decode(&mut self, h:&Huff)-> Result<u16, Short> {
+    let avail = self.input.len() * 8 - self.pos;
+    let w = self.peek();
+    let (mut code, mut first, mut index) = (0i32, 0i32, 0i32);
+    for len in 1..16 {
+      if len > avail {
+        return Err(Short);
+      }
+      code |= ((w >> (len - 1)) & 1) as i32;
+      let count = h.counts[len] as i32;
+      if code - count < first {
+        self.pos += len;
+        return Ok(h.syms[(index + code - first) as usize]);
+      }
+      index += count;
+      first = (first + count) << 1;
+      code <<= 1;
+    }
+    panic!("压缩数据损坏: 无效的哈夫曼编码")
+  }
+
+  /// 读取对齐后的整字节
+  fn bytes(&mut self, n:usize)-> Result<Vec<u8>, Short> {
+    let i = (self.pos + 7) >> 3;
+    if i + n > self.input.len() {
+      return Err(Short);
+    }
+    self.pos = (i + n) * 8;
+    Ok(self.input[i..i + n].to_vec())
+  }
+
+  fn header(&mut self)-> Result<(), Short> {
+    match self.fmt {
+      Format::Zlib=> {
+        let h = self.bytes(2)?;
+        assert!(h[0] & 0x0f == 8 && (h[0] as u16 * 256 + h[1] as u16) % 31 == 0, "不是有效的zlib数据");
+        assert!(h[1] & 0x20 == 0, "不支持带预设字典的zlib数据");
+      }
+      Format::Gzip=> {
+        let h = self.bytes(10)?;
+        assert!(h[0] == 0x1f && h[1] == 0x8b && h[2] == 8, "不是有效的gzip数据");
+        let flags = h[3];
+        if flags & 4 != 0 {
+          let x = self.bytes(2)?;
+          self.bytes(u16::from_le_bytes([x[0], x[1]]) as usize)?;
+        }
+        // 文件名和注释以0结尾
+        for flag in [8, 16] {
+          if flags & flag != 0 {
+            while self.bytes(1)?[0] != 0 {}
+          }
+        }
+        if flags & 2 != 0 {
+          self.bytes(2)?;
+        }
+      }
+      Format::Raw=> ()
+    }
+    Ok(())
+  }
+
+  fn trailer(&mut self)-> Result<(), Short> {
+    let sum = self.check.as_ref().map(|h|h.finish());
+    match self.fmt {
+      Format::Zlib=> {
+        let t = self.bytes(4)?;
+        assert!(Some(t) == sum, "zlib数据校验失败");
+      }
+      Format::Gzip=> {
+        let t = self.bytes(8)?;
+        let crc:Vec<u8> = t[..4].iter().rev().copied().collect();
+        assert!(Some(crc) == sum, "gzip数据校验失败");
+        assert!(u32::from_le_bytes([t[4], t[5], t[6], t[7]]) == self.member_len as u32, "gzip数据长度不符");
+      }
+      Format::Raw=> ()
+    }
+    Ok(())
+  }
+
+  /// 解压一个块到hist, 返回是否为最后一块
+  fn block(&mut self)-> Result<bool, Short> {
+    let last = self.bits(1)? == 1;
+    match self.bits(2)? {
+      0=> {
+        let h = self.bytes(4)?;
+        let len = u16::from_le_bytes([h[0], h[1]]);
+        assert!(len == !u16::from_le_bytes([h[2], h[3]]), "压缩数据损坏: 未压缩块的长度校验失败");
+        let data = self.bytes(len as usize)?;
+        self.hist.extend_from_slice(&data);
+      }
+      1=> {
+        let (lit, dist) = fixed_lens();
+        self.codes(&Huff::new(&lit), &Huff::new(&dist))?;
+      }
+      2=> {
+        let hlit = self.bits(5)? as usize + 257;
+        let hdist = self.bits(5)? as usize + 1;
+        let hclen = self.bits(4)? as usize + 4;
+        let mut cl = [0u8; 19];
+        for i in 0..hclen {
+          cl[CL_ORDER[i]] = self.bits(3)? as u8;
+        }
+        let cl = Huff::new(&cl);
+        let mut lens = Vec::with_capacity(hlit + hdist);
+        while lens.len() < hlit + hdist {
+          let sym = self.decode(&cl)?;
+          let (v, n) = match sym {
+            0..=15=> (sym as u8, 1),
+            16=> (*lens.last().expect("压缩数据损坏: 没有可重复的码长"), 3 + self.bits(2)?),
+            17=> (0, 3 + self.bits(3)?),
+            _=> (0, 11 + self.bits(7)?)
+          };
+          for _ in 0..n {
+            lens.push(v);
+          }
+        }
+        assert!(lens.len() == hlit + hdist, "压缩数据损坏: 码长过多");
+        assert!(lens[256] != 0, "压缩数据损坏: 缺少块结束符");
+        self.codes(&Huff::new(&lens[..hlit]), &Huff::new(&lens[hlit..]))?;
+      }
+      _=> panic!("压缩数据损坏: 无效的块类型")
+    }
+    Ok(last)
+  }
+
+  fn codes(&mut self, lit:&Huff, dist:&Huff)-> Result<(), Short> {
+    loop {
+      let sym = self.decode(lit)? as usize;
+      if sym < 256 {
+        self.hist.push(sym as u8);
+        continue;
+      }
+      if sym == 256 {
+        return Ok(());
+      }
+      let sym = sym - 257;
+      assert!(sym < 29, "压缩数据损坏: 无效的长度");
+      let len = LEN_BASE[sym] as usize + self.bits(LEN_EXTRA[sym] as u32)? as usize;
+      let dsym = self.decode(dist)? as usize;
+      assert!(dsym < 30, "压缩数据损坏: 无效的距离");
+      let d = DIST_BASE[dsym] as usize + self.bits(DIST_EXTRA[dsym] as u32)? as usize;
+      assert!(d <= self.hist.len(), "压缩数据损坏: 距离超出了已解压的数据");
+      for _ in 0..len {
+        let b = self.hist[self.hist.len() - d];
+        self.hist.push(b);
+      }
+    }
+  }
+
+  /// 输入一段数据, 返回能解压出的部分
+  pub fn write(&mut self, data:&[u8])-> Vec<u8> {
+    self.input.extend_from_slice(data);
+    self.total_in += data.len();
+    let mut out = Vec::new();
+    loop {
+      // 数据不足时回到这一步开始前的状态
+      let (pos, mark) = (self.pos, self.hist.len());
+      let res = match self.stage {
+        Stage::Header=> self.header().map(|_|Stage::Block),
+        Stage::Block=> self.block().map(|last|{
+          let new = &self.hist[mark..];
+          if let Some(h) = &mut self.check {
+            h.update(new);
+          }
+          self.member_len += new.len();
+          out.extend_from_slice(new);
+          if self.hist.len() > WINDOW * 2 {
+            self.hist.drain(..self.hist.len() - WINDOW);
+          }
+          if last {Stage::Trailer}else {Stage::Block}
+        }),
+        Stage::Trailer=> self.trailer().map(|_|Stage::Done),
+        Stage::Done=> {
+          // 连在一起的多个gzip成员
+          let rest = &self.input[(self.pos + 7) >> 3..];
+          if self.fmt == Format::Gzip && rest.len() >= 2 && rest[..2] == [0x1f, 0x8b] {
+            self.check = self.fmt.checksum();
+            self.member_len = 0;
+            Ok(Stage::Header)
+          }else {break}
+        }
+      };
+      match res {
+        Ok(s)=> self.stage = s,
+        Err(Short)=> {
+          self.pos = pos;
+          self.hist.truncate(mark);
+          break;
+        }
+      }
+      // 丢掉已经读完的输入
+      let used = self.pos >> 3;
+      self.input.drain(..used);
+      self.pos -= used * 8;
+    }
+    self.total_out += out.len();
+    out
+  }
+
+  /// 结束输入, 数据不完整时报错
+  pub fn finish(&mut self)-> Vec<u8> {
+    let out = self.write(&[]);
+    assert!(self.stage == Stage::Done, "压缩数据不完整");
+    out
+  }
+}
+
+// - 压缩 -
+
+struct BitWriter {
+  bytes: Vec<u8>,
+  acc: u64,
+  n: u32
+}
+impl BitWriter {
+  fn put(&mut self, v:u32, n:u32) {
+    self.acc |= (v as u64) << self.n;
+    self.n += n;
+    while self.n >= 8 {
+      self.bytes.push(self.acc as u8);
+      self.acc >>= 8;
+      self.n -= 8;
+    }
+  }
+  fn align(&mut self) {
+    if self.n > 0 {
+      self.bytes.push(self.acc as u8);
+      self.acc = 0;
+      self.n = 0;
+    }
+  }
+}
+
+#[derive(Clone, Copy)]
+enum Token {
+  Lit(u8),
+  /// 长度和距离
+  Match(u16, u16)
+}
+
+fn len_sym(len:u16)-> usize {
+  LEN_BASE.iter().rposition(|b|*b <= len).unwrap()
+}
+fn dist_sym(d:u16)-> usize {
+  DIST_BASE.iter().rposition(|b|*b <= d).unwrap()
+}
+
+/// 计算不超过limit位的哈夫曼码长, 超出时将频率减半后重建
+fn code_lengths(freq:&[u32], limit:u8)-> Vec<u8> {
+  use std::collections::BinaryHeap;
+  use std::cmp::Reverse;
+  let mut freq = freq.to_vec();
+  // 至少要有两个码才能构成完整的编码
+  let used = freq.iter().filter(|f|**f > 0).count();
+  for i in 0..freq.len() {
+    if used + i >= 2 {
+      break;
+    }
+    if freq[i] == 0 {
+      freq[i] = 1;
+    }
+  }
+  if freq.iter().filter(|f|**f > 0).count() < 2 {
+    let i = freq.iter().position(|f|*f == 0).unwrap();
+    freq[i] = 1;
+  }
+
+  loop {
+    // 节点: 叶子为符号, 之后为合并的节点
+    let n = freq.len();
+    let mut parent = vec![0usize; n];
+    let mut heap = BinaryHeap::new();
+    for (i, f) in freq.iter().enumerate() {
+      if *f > 0 {
+        heap.push(Reverse((*f as u64, i)));
+      }
+    }
+    let mut next = n;
+    while heap.len() > 1 {
+      let Reverse((fa, a)) = heap.pop().unwrap();
+      let Reverse((fb, b)) = heap.pop().unwrap();
+      parent.push(0);
+      parent[a] = next;
+      parent[b] = next;
+      heap.push(Reverse((fa + fb, next)));
+      next += 1;
+    }
+    let root = next - 1;
+    let mut lens = vec![0u8; n];
+    let mut depth = vec![0u8; next];
+    for i in (n..root).rev() {
+      depth[i] = depth[parent[i]] + 1;
+    }
+    for i in 0..n {
+      if freq[i] > 0 {
+        lens[i] = depth[parent[i]] + 1;
+      }
+    }
+    if lens.iter().all(|l|*l <= limit) {
+      return lens;
+    }
+    for f in freq.iter_mut() {
+      if *f > 0 {
+        *f = (*f >> 1).max(1);
+      }
+    }
+  }
+}
+
+/// 由码长得到规范编码, 已按位反转以便低位先写
+fn canonical(lens:&[u8])-> Vec<u16> {
+  let mut count = [0u16; 16];
+  for l in lens {
+    count[*l as usize] += 1;
+  }
+  count[0] = 0;
+  let mut next = [0u16; 16];
+  let mut code = 0u16;
+  for len in 1..16 {
+    code = (code + count[len - 1]) << 1;
+    next[len] = code;
+  }
+  lens.iter().map(|l|{
+    if *l == 0 {
+      return 0;
+    }
+    let c = next[*l as usize];
+    next[*l as usize] += 1;
+    c.reverse_bits() >> (16 - *l)
+  }).collect()
+}
+
+/// 动态块的头部: 码长序列的游程编码
+struct DynHeader {
+  hlit: usize,
+  hdist: usize,
+  hclen: usize,
+  cl_lens: Vec<u8>,
+  /// (码长符号, 附加值)
+  seq: Vec<(u8, u8)>
+}
+impl DynHeader {
+  fn new(lit:&[u8], dist:&[u8])-> DynHeader {
+    let hlit = 257.max(lit.iter().rposition(|l|*l != 0).map_or(0, |n|n + 1));
+    let hdist = 1.max(dist.iter().rposition(|l|*l != 0).map_or(0, |n|n + 1));
+    let all:Vec<u8> = lit[..hlit].iter().chain(&dist[..hdist]).copied().collect();
+    let mut seq = Vec::new();
+    let mut i = 0;
+    while i < all.len() {
+      let l = all[i];
+      let mut run = 1;
+      while i + run < all.len() && all[i + run] == l {
+        run += 1;
+      }
+      i += run;
+      if l == 0 {
+        let mut r = run;
+        while r >= 11 {
+          let k = r.min(138);
+          seq.push((18, (k - 11) as u8));
+          r -= k;
+        }
+        if r >= 3 {
+          seq.push((17, (r - 3) as u8));
+          r = 0;
+        }
+        for _ in 0..r {
+          seq.push((0, 0));
+        }
+      }else {
+        seq.push((l, 0));
+        let mut r = run - 1;
+        while r >= 3 {
+          let k = r.min(6);
+          seq.push((16, (k - 3) as u8));
+          r -= k;
+        }
+        for _ in 0..r {
+          seq.push((l, 0));
+        }
+      }
+    }
+    let mut freq = [0u32; 19];
+    for (s, _) in &seq {
+      freq[*s as usize] += 1;
+    }
+    let cl_lens = code_lengths(&freq, 7);
+    let hclen = 4.max(CL_ORDER.iter().rposition(|i|cl_lens[*i] != 0).map_or(0, |n|n + 1));
+    DynHeader {hlit, hdist, hclen, cl_lens, seq}
+  }
+
+  fn extra(sym:u8)-> u32 {
+    match sym {
+      16=> 2,
+      17=> 3,
+      18=> 7,
+      _=> 0
+    }
+  }
+
+  fn cost(&self)-> usize {
+    14 + 3 * self.hclen + self.seq.iter()
+      .map(|(s, _)|self.cl_lens[*s as usize] as usize + Self::extra(*s) as usize).sum::<usize>()
+  }
+
+  fn write(&self, w:&mut BitWriter) {
+    w.put((self.hlit - 257) as u32, 5);
+    w.put((self.hdist - 1) as u32, 5);
+    w.put((self.hclen - 4) as u32, 4);
+    for i in 0..self.hclen {
+      w.put(self.cl_lens[CL_ORDER[i]] as u32, 3);
+    }
+    let codes = canonical(&self.cl_lens);
+    for (s, x) in &self.seq {
+      w.put(codes[*s as usize] as u32, self.cl_lens[*s as usize] as u32);
+      w.put(*x as u32, Self::extra(*s));
+    }
+  }
+}
+
+/// 可以分段输入的压缩器
+pub struct Deflater {
+  fmt: Format,
+  level: u8,
+  /// 回溯窗口和还未压缩的输入
+  hist: Vec<u8>,
+  /// hist中未压缩部分的起点
+  start: usize,
+  out: BitWriter,
+  header: bool,
+  check: Option<Hasher>,
+  total_in: usize,
+  total_out: usize
+}
+
+impl Deflater {
+  pub fn new(fmt:Format, level:u8)-> Deflater {
+    assert!(level <= 9, "压缩等级必须在0到9之间");
+    Deflater {
+      fmt, level, hist: Vec::new(), start: 0, out: BitWriter {bytes: Vec::new(), acc: 0, n: 0},
+      header: false, check: fmt.checksum(), total_in: 0, total_out: 0
+    }
+  }
+
+  fn write_header(&mut self) {
+    if self.header {
+      return;
+    }
+    self.header = true;
+    match self.fmt {
+      Format::Zlib=> {
+        let flevel = match self.level {
+          0|1=> 0,
+          2..=5=> 1,
+          6=> 2,
+          _=> 3
+        };
+        let cmf = 0x78u16;
+        let mut flg = flevel << 6;
+        flg += 31 - (cmf * 256 + flg) % 31;
+        self.out.bytes.extend_from_slice(&[cmf as u8, flg as u8]);
+      }
+      Format::Gzip=> {
+        let xfl = match self.level {
+          9=> 2,
+          1=> 4,
+          _=> 0
+        };
+        self.out.bytes.extend_from_slice(&[0x1f, 0x8b, 8, 0, 0, 0, 0, 0, xfl, 255]);
+      }
+      Format::Raw=> ()
+    }
+  }
+
+  /// 取出已经完整的字节
+  fn take(&mut self)-> Vec<u8> {
+    let v = std::mem::take(&mut self.out.bytes);
+    self.total_out += v.len();
+    v
+  }
+
+  pub fn write(&mut self, data:&[u8])-> Vec<u8> {
+    self.write_header();
+    if let Some(h) = &mut self.check {
+      h.update(data);
+    }
+    self.total_in += data.len();
+    self.hist.extend_from_slice(data);
+    while self.hist.len() - self.start >= BLOCK {
+      self.block(self.start, self.start + BLOCK, false);
+      self.start += BLOCK;
+      if self.start > WINDOW * 2 {
+        let cut = self.start - WINDOW;
+        self.hist.drain(..cut);
+        self.start -= cut;
+      }
+    }
+    self.take()
+  }
+
+  pub fn finish(&mut self)-> Vec<u8> {
+    self.write_header();
+    self.block(self.start, self.hist.len(), true);
+    self.start = self.hist.len();
+    self.out.align();
+    let sum = self.check.as_ref().map(|h|h.finish());
+    match self.fmt {
+      Format::Zlib=> self.out.bytes.extend_from_slice(&sum.unwrap()),
+      Format::Gzip=> {
+        self.out.bytes.extend(sum.unwrap().iter().rev());
+        self.out.bytes.extend_from_slice(&(self.total_in as u32).to_le_bytes());
+      }
+      Format::Raw=> ()
+    }
+    self.take()
+  }
+
+  fn stored(&mut self, from:usize, to:usize, last:bool) {
+    let mut i = from;
+    loop {
+      let end = to.min(i + 65535);
+      let fin = last && end == to;
+      self.out.put(fin as u32, 1);
+      self.out.put(0, 2);
+      self.out.align();
+      let len = (end - i) as u16;
+      self.out.bytes.extend_from_slice(&len.to_le_bytes());
+      self.out.bytes.extend_from_slice(&(!len).to_le_bytes());
+      self.out.bytes.extend_from_slice(&self.hist[i..end]);
+      i = end;
+      if i >= to {
+        break;
+      }
+    }
+  }
+
+  /// 用哈希链寻找重复串
+  fn lz77(&self, from:usize, to:usize)-> Vec<Token> {
+    const CHAIN:[usize; 10] = [0, 4, 8, 16, 32, 64, 128, 256, 1024, 4096];
+    const NICE:[usize; 10] = [0, 8, 16, 32, 16, 32, 128, 128, 258, 258];
+    let (chain, nice) = (CHAIN[self.level as usize], NICE[self.level as usize]);
+    let lazy = self.level >= 4;
+    let h = &self.hist;
+    let base = from.saturating_sub(WINDOW);
+    let mut head = vec![usize::MAX; 1 << 15];
+    let mut prev = vec![usize::MAX; to - base];
+    let hash = |p:usize|((h[p] as usize) << 10 ^ (h[p + 1] as usize) << 5 ^ h[p + 2] as usize) & 0x7fff;
+    let insert = |p:usize, head:&mut Vec<usize>, prev:&mut Vec<usize>|{
+      if p + 3 <= to {
+        let k = hash(p);
+        prev[p - base] = head[k];
+        head[k] = p;
+      }
+    };
+    for p in base..from {
+      insert(p, &mut head, &mut prev);
+    }
+
+    let find = |i:usize, head:&Vec<usize>, prev:&Vec<usize>|-> (usize, usize) {
+      if i + 3 > to {
+        return (0, 0);
+      }
+      let max = (to - i).min(258);
+      let mut best = (0, 0);
+      let mut cand = head[hash(i)];
+      let mut n = chain;
+      while cand != usize::MAX && i - cand <= WINDOW && n > 0 {
+        if h[cand + best.0.min(max - 1)] == h[i + best.0.min(max - 1)] {
+          let mut l = 0;
+          while l < max && h[cand + l] == h[i + l] {
+            l += 1;
+          }
+          if l > best.0 {
+            best = (l, i - cand);
+            if l >= nice {
+              break;
+            }
+          }
+        }
+        cand = prev[cand - base];
+        n -= 1;
+      }
+      best
+    };
+
+    let mut toks = Vec::new();
+    let mut i = from;
+    while i < to {
+      let (len, dist) = find(i, &head, &prev);
+      if len >= 3 {
+        // 下一个位置有更长的匹配时先输出一个字面量
+        if lazy && len < nice {
+          insert(i, &mut head, &mut prev);
+          if find(i + 1, &head, &prev).0 > len {
+            toks.push(Token::Lit(h[i]));
+            i += 1;
+            continue;
+          }
+        }else {
+          insert(i, &mut head, &mut prev);
+        }
+        toks.push(Token::Match(len as u16, dist as u16));
+        for p in i + 1..i + len {
+          insert(p, &mut head, &mut prev);
+        }
+        i += len;
+      }else {
+        insert(i, &mut head, &mut prev);
+        toks.push(Token::Lit(h[i]));
+        i += 1;
+      }
+    }
+    toks
+  }
+
+  /// 压缩一块, 从动态哈夫曼, 固定哈夫曼和不压缩中选最小的
+  fn block(&mut self, from:usize, to:usize, last:bool) {
+    if self.level == 0 {
+      return self.stored(from, to, last);
+    }
+    let toks = self.lz77(from, to);
+    let mut lf = [0u32; 286];
+    let mut df = [0u32; 30];
+    lf[256] = 1;
+    for t in &toks {
+      match t {
+        Token::Lit(b)=> lf[*b as usize] += 1,
+        Token::Match(l, d)=> {
+          lf[257 + len_sym(*l)] += 1;
+          df[dist_sym(*d)] += 1;
+        }
+      }
+    }
+    let (dyn_lit, dyn_dist) = (code_lengths(&lf, 15), code_lengths(&df, 15));
+    let header = DynHeader::new(&dyn_lit, &dyn_dist);
+    let (fix_lit, fix_dist) = fixed_lens();
+
+    let cost = |lit:&[u8], dist:&[u8]|-> usize {
+      let mut c = lit[256] as usize;
+      for t in &toks {
+        c += match t {
+          Token::Lit(b)=> lit[*b as usize] as usize,
+          Token::Match(l, d)=> {
+            let (ls, ds) = (len_sym(*l), dist_sym(*d));
+            lit[257 + ls] as usize + LEN_EXTRA[ls] as usize + dist[ds] as usize + DIST_EXTRA[ds] as usize
+          }
+        };
+      }
+      c
+    };
+    let dyn_cost = header.cost() + cost(&dyn_lit, &dyn_dist);
+    let fix_cost = cost(&fix_lit, &fix_dist);
+    let stored_cost = (to - from) * 8 + ((to - from) / 65535 + 1) * 40;
+
+    if stored_cost < dyn_cost.min(fix_cost) {
+      return self.stored(from, to, last);
+    }
+    self.out.put(last as u32, 1);
+    let (lit, dist) = if dyn_cost < fix_cost {
+      self.out.put(2, 2);
+      header.write(&mut self.out);
+      (dyn_lit, dyn_dist)
+    }else {
+      self.out.put(1, 2);
+      (fix_lit, fix_dist)
+    };
+    let (lc, dc) = (canonical(&lit), canonical(&dist));
+    for t in &toks {
+      match t {
+        Token::Lit(b)=> self.out.put(lc[*b as usize] as u32, lit[*b as usize] as u32),
+        Token::Match(l, d)=> {
+          let (ls, ds) = (len_sym(*l), dist_sym(*d));
+          self.out.put(lc[257 + ls] as u32, lit[257 + ls] as u32);
+          self.out.put((*l - LEN_BASE[ls]) as u32, LEN_EXTRA[ls] as u32);
+          self.out.put(dc[ds] as u32, dist[ds] as u32);
+          self.out.put((*d - DIST_BASE[ds]) as u32, DIST_EXTRA[ds] as u32);
+        }
+      }
+    }
+    self.out.put(lc[256] as u32, lit[256] as u32);
+  }
+}
+
+// - Key接口 -
+
+pub fn init()-> (Interned, *mut NativeClassDef) {
+  unsafe {
+    let s = new_static_class(b"Zip", vec![
+      (intern(b"deflate"), |a, _|compress(Format::Raw, a)),
+      (intern(b"zlib"), |a, _|compress(Format::Zlib, a)),
+      (intern(b"gzip"), |a, _|compress(Format::Gzip, a)),
+      (intern(b"inflate"), |a, _|decompress(Format::Raw, a)),
+      (intern(b"unzlib"), |a, _|decompress(Format::Zlib, a)),
+      (intern(b"gunzip"), |a, _|decompress(Format::Gzip, a)),
+      (intern(b"compressor"), s_compressor),
+      (intern(b"decompressor"), s_decompressor)
+    ]);
+
+    COMPRESSOR_CLASS = new_static_class(b"Zip.compressor", vec![]).1;
+    let c = &mut *COMPRESSOR_CLASS;
+    c.methods.push((intern(b"write"), |inst, args, _|
+      Litr::Buf(deflater(inst.v).write(bytes(args.get(0), "compressor.write")).into())));
+    c.methods.push((intern(b"finish"), |inst, _, _|Litr::Buf(deflater(inst.v).finish().into())));
+    c.getter = |inst, get|{
+      let d = deflater(inst.v);
+      match get.vec() {
+        b"format"=> Litr::Str(d.fmt.name().to_string().into()),
+        b"level"=> Litr::Uint(d.level as usize),
+        b"total_in"=> Litr::Uint(d.total_in),
+        b"total_out"=> Litr::Uint(d.total_out),
+        _=> Litr::Uninit
+      }
+    };
+    c.onclone = |_|panic!("无法复制压缩器. 请考虑用take函数代替");
+    c.ondrop = |inst|drop(Box::from_raw(inst.v as *mut Deflater));
+
+    DECOMPRESSOR_CLASS = new_static_class(b"Zip.decompressor", vec![]).1;
+    let d = &mut *DECOMPRESSOR_CLASS;
+    d.methods.push((intern(b"write"), |inst, args, _|
+      Litr::Buf(inflater(inst.v).write(bytes(args.get(0), "decompressor.write")).into())));
+    d.methods.push((intern(b"finish"), |inst, _, _|Litr::Buf(inflater(inst.v).finish().into())));
+    d.getter = |inst, get|{
+      let d = inflater(inst.v);
+      match get.vec() {
+        b"format"=> Litr::Str(d.fmt.name().to_string().into()),
+        b"done"=> Litr::Bool(d.stage == Stage::Done),
+        b"total_in"=> Litr::Uint(d.total_in),
+        b"total_out"=> Litr::Uint(d.total_out),
+        _=> Litr::Uninit
+      }
+    };
+    d.onclone = |_|panic!("无法复制解压器. 请考虑用take函数代替");
+    d.ondrop = |inst|drop(Box::from_raw(inst.v as *mut Inflater));
+    s
+  }
+}
+
+fn deflater(v:usize)-> &'static mut Deflater {
+  unsafe {&mut *(v as *mut Deflater)}
+}
+fn inflater(v:usize)-> &'static mut Inflater {
+  unsafe {&mut *(v as *mut Inflater)}
+}
+
+fn bytes<'a>(v:Option<&'a CalcRef>, f:&str)-> &'a [u8] {
+  match v.map(|v|&**v) {
+    Some(Litr::Buf(b))=> b,
+    Some(Litr::Str(s))=> s.as_bytes(),
+    _=> panic!("{}需要Buf或Str", f)
+  }
+}
+
+/// 压缩等级默认为6
+fn level(v:Option<&CalcRef>)-> u8 {
+  match v.map(|v|&**v) {
+    Some(Litr::Uint(n))=> *n as u8,
+    Some(Litr::Int(n)) if (0..=9).contains(n)=> *n as u8,
+    None|Some(Litr::Uninit)=> 6,
+    _=> panic!("压缩等级必须在0到9之间")
+  }
+}
+
+fn format(v:Option<&CalcRef>)-> Format {
+  match v.map(|v|&**v) {
+    Some(Litr::Str(s))=> Format::parse(s),
+    None|Some(Litr::Uninit)=> Format::Gzip,
+    _=> panic!("压缩格式必须是Str")
+  }
+}
+
+fn compress(fmt:Format, args:Vec<CalcRef>)-> Litr {
+  let mut d = Deflater::new(fmt, level(args.get(1)));
+  let mut out = d.write(bytes(args.get(0), "Zip压缩"));
+  out.extend(d.finish());
+  Litr::Buf(out.into())
+}
+
+fn decompress(fmt:Format, args:Vec<CalcRef>)-> Litr {
+  let mut d = Inflater::new(fmt);
+  let mut out = d.write(bytes(args.get(0), "Zip解压"));
+  out.extend(d.finish());
+  Litr::Buf(out.into())
+}
+
+/// Zip::compressor(格式, 等级), 格式默认为gzip
+fn s_compressor(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let d = Box::new(Deflater::new(format(args.get(0)), level(args.get(1))));
+  Litr::Ninst(NativeInstance {cls: unsafe{COMPRESSOR_CLASS}, v: Box::into_raw(d) as usize, w: 0})
+}
+
+/// Zip::decompressor(格式), 格式默认为gzip
+fn s_decompressor(args:Vec<CalcRef>, _cx:Scope)-> Litr {
+  let d = Box::new(Inflater::new(format(args.get(0))));
+  Litr::Ninst(NativeInstance {cls: unsafe{DECOMPRESSOR_CLASS}, v: Box::into_raw(d) as usize, w: 0})
+}
@@ -1,6 +1,6 @@
 //! 注释都在mod.rs里，这没有注解
 
-use crate::primitive::{litr::*, get_prop};
+use crate::primitive::{litr::*, objmap::ObjMap, get_prop, bigint, int, shared};
 use super::*;
 
 /// calc_ref既可能得到引用，也可能得到计算过的值
@@ -49,32 +49,59 @@ impl std::ops::DerefMut for CalcRef {
 }
 
 impl Scope {
+  /// 计算f(..), tail为true时本地函数会作为尾调用延后执行
+  pub fn call_expr(self, args:&[Expr], targ:&Expr, tail:bool)-> Litr {
+    // Enum::Variant(a, b)构建带值的枚举
+    if let Some((def, tag)) = self.find_enum_variant(targ) {
+      let decl = Some(&*unsafe {&*def}.variants[tag].fields);
+      let (mut args, named) = self.calc_args(args, decl);
+      place_named(&mut args, named, decl);
+      return new_enum(def, tag, args.into_iter().map(|v|v.own()).collect());
+    }
+    let f = self.calc_ref(targ);
+    self.call_value(args, f, targ, tail)
+  }
+
+  /// 以算好的函数值f调用, targ仅用于报错
+  fn call_value(self, args:&[Expr], f:CalcRef, targ:&Expr, tail:bool)-> Litr {
+    let targ = match &*f {
+      Litr::Func(f)=> f,
+      _=> {
+        let s = match targ {
+          Expr::Literal(n)=> n.str(),
+          Expr::Variant(n)=> n.str(),
+          _=> "".to_string()
+        };
+        panic!("{s}不是一个函数")
+      }
+    };
+    let decl = match targ {
+      Function::Local(f)=> func_argdecl(f),
+      _=> None
+    };
+    let (mut args, named) = self.calc_args(args, decl);
+    place_named(&mut args, named, decl);
+    // return f()时把调用留给外层函数执行
+    if let (true, Function::Local(f)) = (tail, targ) {
+      let args = args.into_iter().map(|e|e.own()).collect();
+      unsafe {super::call::TAIL_CALL = Some((f.clone(), args, self.kself));}
+      return Litr::Uninit;
+    }
+    self.call(args, targ)
+  }
+
   /// 解析一个表达式，对应Expr
   /// 
   /// 该函数必定发生复制
   pub fn calc(self,e:&Expr)-> Litr {
+    call::stack_guard();
     match e {
-      Expr::Call { args, targ }=> {
-        let targ_ = self.calc_ref(targ);
-        let targ = match &*targ_ {
-          Litr::Func(f)=> f,
-          _=> {
-            let s = match &**targ {
-              Expr::Literal(n)=> n.str(),
-              Expr::Variant(n)=> n.str(),
-              _=> "".to_string()
-            };
-            panic!("{s}不是一个函数")
-          }
-        };
-        let args = args.iter().map(|v|self.calc_ref(v)).collect();
-        self.call(args, targ)
-      },
+      Expr::Call { args, targ }=> self.call_expr(args, targ, false),
 
-      Expr::CallMethod { args, targ, name }=> {
+      Expr::CallMethod { args, targ, name, cache }=> {
+        let _pin = inner_place(targ).then(shared::pin);
         let targ = self.calc_ref(targ);
-        let args = args.iter().map(|v|self.calc_ref(v)).collect();
-        self.call_method(args, targ, *name)
+        self.call_method_expr(args, targ, *name, cache, false)
       },
 
       Expr::Index { left, i }=> {
@@ -103,8 +130,9 @@ impl Scope {
         match op {
           b'-'=> {
             match &*right {
-              Int(n)=> Int(-n),
+              Int(n)=> Int(n.checked_neg().unwrap_or_else(||panic!("整数溢出: -({})超出了Int的范围", n))),
               Float(n)=> Float(-n),
+              BigInt(n)=> BigInt(n.neg()),
               _=> panic!("负号只能用在有符号数")
             }
           }
@@ -113,6 +141,7 @@ impl Scope {
               Bool(b)=> Bool(!b),
               Int(n)=> Int(!n),
               Uint(n)=> Uint(!n),
+              BigInt(n)=> BigInt(n.not()),
               Uninit => Bool(true),
               _=> panic!("!运算符只能用于整数和Bool")
             }
@@ -121,19 +150,43 @@ impl Scope {
       }
 
       // [列表]
-      Expr::List(v)=> Litr::List(
-        v.iter().map(|e| self.calc(e)).collect()
-      ),
+      Expr::List(v)=> {
+        let mut ls = Vec::with_capacity(v.len());
+        for e in v {
+          // [...a]展开任意可迭代的值
+          if let Expr::Spread(e) = e {
+            let mut v = self.calc_ref(e);
+            ls.extend(crate::primitive::iter::LitrIterator::new(&mut v));
+          }else {
+            ls.push(self.calc(e));
+          }
+        }
+        Litr::List(ls.into())
+      }
+
+      // `模板{字符串}`
+      Expr::Template(parts)=> template(self, parts),
 
       // {a:"对",b:"象"}
       Expr::Obj(decl)=> {
-        let mut map = HashMap::new();
+        let mut map = ObjMap::new();
         decl.iter().for_each(|(name, v)|{
-          map.insert(*name, self.calc(v));
+          // {...o}展开对象, 后写的键会覆盖先写的
+          if let Expr::Spread(e) = v {
+            match self.calc(e) {
+              Litr::Obj(o)=> map.extend(o),
+              _=> panic!("对象中只能展开Obj")
+            }
+          }else {
+            map.insert(*name, self.calc(v));
+          }
         });
-        Litr::Obj(map)
+        Litr::Obj(map.into())
       }
 
+      Expr::Spread(_)=> panic!("...只能在调用参数, 列表和对象中使用"),
+      Expr::Named(name, _)=> panic!("具名参数'{}='只能在调用参数中使用", name),
+
       // Class::{}创建实例
       Expr::NewInst{cls, val}=> {
         let (cls, clsname) = match &**cls {
@@ -143,6 +196,9 @@ impl Scope {
             (self.find_class(*clsname).unwrap_or_else(||panic!("未定义类 '{}'", clsname.str())), clsname),
           _=> panic!("构建实例::左侧必须是类型名")
         };
+        if let Class::Enum(_) = cls {
+          panic!("枚举'{}'需要使用'{}::成员'创建", clsname, clsname)
+        }
         if let Class::Local(cls) = cls {
           let cls = unsafe {&*cls};
           let mut v = vec![Litr::Uninit;cls.props.len()];
@@ -150,13 +206,23 @@ impl Scope {
           let mut writen = vec![false; cls.props.len()];
           // 确认你在模块内还是模块外
           let can_access_private = self.exports == cls.cx.exports;
-          'a: for (id, e) in val.iter() {
+          // Class::{...o}会把o的键值作为属性展开
+          let mut val_ = Vec::with_capacity(val.len());
+          for (id, e) in val.iter() {
+            match e {
+              Expr::Spread(e)=> match self.calc(e) {
+                Litr::Obj(o)=> val_.extend(o),
+                _=> panic!("创建实例时只能展开Obj")
+              }
+              _=> val_.push((*id, self.calc(e)))
+            }
+          }
+          'a: for (id, right) in val_ {
             for (n, prop) in cls.props.iter().enumerate() {
-              if prop.name == *id {
+              if prop.name == id {
                 assert!(prop.public || can_access_private,
                   "成员属性'{}'是私有的",id);
                 // 类型检查
-                let right = self.calc(e);
                 assert!(prop.typ.is(&right, cls.cx), "'{}'属性要求{:?}类型, 但传入了{:?}", id, prop.typ, right);
                 // 写入值
                 unsafe{
@@ -252,6 +318,13 @@ impl Scope {
               panic!("'{}'原生类型中没有'{}'静态方法", cls.name, find.str())
               // native模块的method使用bind太不安全了，只允许访问静态方法
             }
+            // 不带括号访问枚举成员时, 携带的值都使用默认值
+            Class::Enum(def)=> {
+              let d = unsafe {&*def};
+              let tag = d.variants.iter().position(|v|v.name == find)
+                .unwrap_or_else(||panic!("枚举'{}'没有'{}'成员", d.name, find));
+              new_enum(def, tag, Vec::new())
+            }
           }
         }
 
@@ -273,8 +346,19 @@ impl Scope {
         get_prop(self, from, *find).own()
       }
 
+      Expr::Optional(_)=> self.calc_ref(e).own(),
+      Expr::OptLeft(e)=> self.calc(e),
+
       Expr::Kself => unsafe{(*self.kself).clone()},
 
+      // 循环表达式
+      Expr::Loop(stmt)=> {
+        let mut ctl = evil::LoopCtl::Run;
+        let v = evil::run_loop(self, stmt, &mut ctl);
+        evil::unhandled_ctl(ctl);
+        v
+      }
+
       // is操作符
       Expr::Is { left, right }=> {
         let v = self.calc_ref(left);
@@ -291,6 +375,10 @@ impl Scope {
                 Class::Native(cls)=> cls == inst.cls,
                 _=> false
               }
+              Litr::Enum(e)=> match cls {
+                Class::Enum(def)=> e.same_def(def),
+                _=> false
+              }
               _=> false
             })
           }
@@ -306,6 +394,10 @@ impl Scope {
               Some(Class::Native(c))=> c == inst.cls,
               _=> false,
             }),
+            Litr::Enum(e)=> Litr::Bool(match self.find_class(*right) {
+              Some(Class::Enum(def))=> e.same_def(def),
+              _=> false,
+            }),
             Litr::Uninit=> Litr::Bool(false),
             $(
               Litr::$d(_) => Litr::Bool(intern(stringify!($d).as_bytes()) == *right),
@@ -313,7 +405,7 @@ impl Scope {
           }
         }}
         matcher!{
-          Bool Buf Float Func Int List Obj Str Uint
+          BigInt Bool Buf Float Func Int List Obj Str Uint
         }
       }
       
@@ -323,6 +415,7 @@ impl Scope {
 
   /// 能引用优先引用的calc，能避免很多复制同时保证引用正确
   pub fn calc_ref(mut self, e:&Expr)-> CalcRef {
+    call::stack_guard();
     match e {
       Expr::Kself=> {
         let v = unsafe{&mut *self.kself};
@@ -333,11 +426,14 @@ impl Scope {
         get_prop(self, left, *name)
       }
       Expr::Index { left, i }=> {
+        let _pin = inner_place(left).then(shared::pin);
         let left = self.calc_ref(left);
         let i = self.calc_ref(i);
         get_index(left, i)
       },
       Expr::Variant(id)=> self.var(*id).unwrap_or_else(||panic!("无法找到变量 '{}'", id.str())),
+      // 可选链中任意一处?.或?[左侧为uninit时, 整条链直接得到uninit
+      Expr::Optional(e)=> self.chain(e).unwrap_or_else(CalcRef::uninit),
       _=> {
         let v = self.calc(e);
         CalcRef::Own(v)
@@ -357,6 +453,7 @@ impl Scope {
         get_prop(self, left, *name)
       }
       Expr::Index { left, i }=> {
+        let _pin = inner_place(left).then(shared::pin);
         let left = self.calc_ref_unlocked(left);
         let i = self.calc_ref(i);
         get_index(left, i)
@@ -384,10 +481,123 @@ impl Scope {
     }
   }
 
+  /// 计算可选链, 遇到左侧为uninit的?.或?[时返回None
+  fn chain(self, e:&Expr)-> Option<CalcRef> {
+    Some(match e {
+      Expr::OptLeft(e)=> {
+        let v = self.chain(e)?;
+        if let Litr::Uninit = &*v {
+          return None;
+        }
+        v
+      }
+      Expr::Property(left, name)=> {
+        let left = self.chain(left)?;
+        get_prop(self, left, *name)
+      }
+      Expr::Index { left, i }=> {
+        let _pin = inner_place(left).then(shared::pin);
+        let left = self.chain(left)?;
+        let i = self.calc_ref(i);
+        get_index(left, i)
+      }
+      Expr::CallMethod { args, targ, name, cache }=> {
+        let _pin = inner_place(targ).then(shared::pin);
+        let targ = self.chain(targ)?;
+        CalcRef::Own(self.call_method_expr(args, targ, *name, cache, false))
+      }
+      Expr::Call { args, targ }=> {
+        let f = self.chain(targ)?;
+        CalcRef::Own(self.call_value(args, f, targ, false))
+      }
+      _=> self.calc_ref(e)
+    })
+  }
+
+  /// 计算参数后调用targ的方法
+  pub fn call_method_expr(self, args:&[Expr], targ:CalcRef, name:Interned, cache:&call::MethodCache, tail:bool)-> Litr {
+    let decl = method_argdecl(&targ, name);
+    let (mut args, named) = self.calc_args(args, decl);
+    place_named(&mut args, named, decl);
+    // return self.f()时把调用留给外层函数执行, self就是外层函数的self
+    if let (true, Litr::Inst(inst)) = (tail, &*targ) {
+      let f = self.inst_method(inst, name, cache);
+      let args = args.into_iter().map(|e|e.own()).collect();
+      unsafe {super::call::TAIL_CALL = Some(((*f).clone(), args, self.kself));}
+      return Litr::Uninit;
+    }
+    self.call_method(args, targ, name, cache)
+  }
+
+  /// 计算调用参数, 展开...并将具名参数单独返回
+  /// 
+  /// ...Obj展开为具名参数, 其他可迭代的值展开为位置参数.
+  /// name=v只在decl中有该参数时作为具名参数, 否则仍是赋值表达式
+  pub fn calc_args(self, args:&[Expr], decl:Option<&[ArgDecl]>)-> (Vec<CalcRef>, Vec<(Interned, CalcRef)>) {
+    let mut out = Vec::with_capacity(args.len());
+    let mut named = Vec::new();
+    for e in args {
+      match e {
+        Expr::Spread(e)=> match self.calc(e) {
+          Litr::Obj(o)=> named.extend(o.into_iter().map(|(k, v)|(k, CalcRef::Own(v)))),
+          mut v=> out.extend(crate::primitive::iter::LitrIterator::new(&mut v).map(|v|CalcRef::Own(v)))
+        }
+        Expr::Named(name, e) if decl.is_some_and(|d|d.iter().any(|a|a.name == *name))=> 
+          named.push((*name, self.calc_ref(e))),
+        Expr::Named(name, e)=> {
+          let v = self.calc(e);
+          expr_set(self, &Expr::Variant(*name), v);
+          out.push(CalcRef::uninit());
+        }
+        _=> out.push(self.calc_ref(e))
+      }
+    }
+    (out, named)
+  }
+
 }
 
 
 
+/// 按参数声明把具名参数放到对应的位置, 中间空出的参数为uninit以使用默认值
+fn place_named(args:&mut Vec<CalcRef>, named:Vec<(Interned, CalcRef)>, decl:Option<&[ArgDecl]>) {
+  if named.len() == 0 {
+    return;
+  }
+  let decl = decl.expect("只有声明了参数名的本地函数和枚举成员可以使用具名参数");
+  for (name, v) in named {
+    let n = decl.iter().position(|a|a.name == name)
+      .unwrap_or_else(||panic!("没有名为'{}'的参数", name));
+    if n >= args.len() {
+      args.resize_with(n + 1, CalcRef::uninit);
+    }else if !matches!(&*args[n], Litr::Uninit) {
+      panic!("参数'{}'被重复传入", name)
+    }
+    args[n] = v;
+  }
+}
+
+/// 本地函数的参数声明, 使用[args]自定义参数的函数没有参数声明
+fn func_argdecl(f:&LocalFunc)-> Option<&[ArgDecl]> {
+  match &f.argdecl {
+    LocalFuncRawArg::Normal(decl)=> Some(decl),
+    LocalFuncRawArg::Custom(_)=> None
+  }
+}
+
+/// 实例方法的参数声明
+fn method_argdecl(targ:&Litr, name:Interned)-> Option<&[ArgDecl]> {
+  if let Litr::Inst(inst) = targ {
+    let cls = unsafe {&*inst.cls};
+    let f = &cls.methods.iter().find(|f|f.f.name == name)?.f;
+    return match &f.argdecl {
+      LocalFuncRawArg::Normal(decl)=> Some(decl),
+      LocalFuncRawArg::Custom(_)=> None
+    };
+  }
+  None
+}
+
 /// 在一个作用域设置一个表达式为v
 fn expr_set(this: Scope, left: &Expr, right: Litr) {
   match left {
@@ -424,12 +634,22 @@ fn expr_set(this: Scope, left: &Expr, right: Litr) {
           }
           panic!("'{}'类型上没有'{}'属性", cls.name, find)
         }
+        Litr::Enum(e)=> {
+          let def = unsafe {&*e.def};
+          let decl = &def.variants[e.tag];
+          let n = decl.fields.iter().position(|f|f.name == *find)
+            .unwrap_or_else(||panic!("'{}'没有'{}'值", e.name(), find));
+          let field = &decl.fields[n];
+          assert!(field.t.is(&right, def.cx), "'{}'要求{:?}类型, 但传入了{:?}", find, field.t, right);
+          e.v[n] = right;
+        }
         _=> ()
       }
     }
 
     // 捕获index_set
     Expr::Index{left,i}=> {
+      let _pin = inner_place(left).then(shared::pin);
       let left = this.calc_ref_unlocked(left);
       // 如果左值不是引用就没必要继续运行
       let left = match left {
@@ -494,6 +714,7 @@ fn expr_set_diff(this: Scope, left: &Expr, f:impl Fn(&Litr)-> Litr) {
     Expr::Property(e, find)=> {
       // 修改并赋值的定义中是包含读一次数值的行为的
       // 即使不是引用也要写入
+      let _pin = inner_place(e).then(shared::pin);
       let left = &mut *this.calc_ref_unlocked(e);
       match left {
         Litr::Ninst(inst)=> {
@@ -527,12 +748,23 @@ fn expr_set_diff(this: Scope, left: &Expr, f:impl Fn(&Litr)-> Litr) {
           }
           panic!("'{}'类型上没有'{}'属性", cls.name, find)
         }
+        Litr::Enum(e)=> {
+          let def = unsafe {&*e.def};
+          let decl = &def.variants[e.tag];
+          let n = decl.fields.iter().position(|f|f.name == *find)
+            .unwrap_or_else(||panic!("'{}'没有'{}'值", e.name(), find));
+          let field = &decl.fields[n];
+          let write = f(&e.v[n]);
+          assert!(field.t.is(&write, def.cx), "'{}'要求{:?}类型, 但传入了{:?}", find, field.t, write);
+          e.v[n] = write;
+        }
         _=> ()
       }
     }
 
     // 捕获index_set
     Expr::Index{left,i}=> {
+      let _pin = inner_place(left).then(shared::pin);
       let mut left = this.calc_ref_unlocked(left);
       let left = &mut*left;
       let i = this.calc_ref(i);
@@ -590,9 +822,10 @@ fn expr_set_diff(this: Scope, left: &Expr, f:impl Fn(&Litr)-> Litr) {
             _=> 0
           };
         }
+        // f中可能复制left, 算完再重新取引用写入
         _=> {
-          let mut ori = get_index(CalcRef::Ref(left), i);
-          *ori = f(&*ori);
+          let write = f(&*get_index(CalcRef::Ref(left), i.clone()));
+          *get_index(CalcRef::Ref(left), i) = write;
         }
       }
     }
@@ -605,6 +838,13 @@ fn expr_set_diff(this: Scope, left: &Expr, f:impl Fn(&Litr)-> Litr) {
 }
 
 
+/// 该表达式取到的引用是否可能指向容器内部
+/// 
+/// 持有这类引用时运行脚本需要shared::pin, 以免脚本复制容器后写到副本上
+fn inner_place(e:&Expr)-> bool {
+  matches!(e, Expr::Index{..}|Expr::Property(..)|Expr::Optional(_)|Expr::OptLeft(_))
+}
+
 /// 获取一个ks值索引处的值
 fn get_index(mut left:CalcRef, i:CalcRef)-> CalcRef {
   // 先判断Obj
@@ -657,7 +897,7 @@ fn get_index(mut left:CalcRef, i:CalcRef)-> CalcRef {
     }
     Litr::Str(n)=> {
       match n.chars().nth(i) {
-        Some(c)=> CalcRef::Own(Litr::Str(c.to_string())),
+        Some(c)=> CalcRef::Own(Litr::Str(c.to_string().into())),
         None=> CalcRef::uninit()
       }
     }
@@ -670,6 +910,68 @@ fn get_index(mut left:CalcRef, i:CalcRef)-> CalcRef {
 }
 
 
+/// 按成员声明构建一个枚举值, 和函数传参一样处理默认值和类型检查
+pub fn new_enum(def:*const EnumDef, tag:usize, args:Vec<Litr>)-> Litr {
+  let d = unsafe {&*def};
+  let decl = &d.variants[tag];
+  let given = args.len();
+  let mut args = args.into_iter();
+  let v = decl.fields.iter().map(|field| {
+    let mut arg = args.next().unwrap_or(Litr::Uninit);
+    if let Litr::Uninit = arg {
+      arg = d.cx.calc(&field.default);
+      // 没传值也没有默认值, 直接报缺少几个值而不是类型不符
+      if let Litr::Uninit = arg {
+        if !field.t.is(&arg, d.cx) {
+          let need = decl.fields.iter()
+            .filter(|f|matches!(f.default, Expr::Literal(Litr::Uninit))).count();
+          panic!("'{}::{}'需要{}个值, 但只传入了{}个", d.name, decl.name, need, given)
+        }
+      }
+    }
+    assert!(field.t.is(&arg, d.cx), "'{}::{}'的'{}'要求{:?}类型, 但传入了{:?}", 
+      d.name, decl.name, field.name, field.t, arg);
+    arg
+  }).collect();
+  Litr::Enum(EnumInstance { def, tag, v })
+}
+/// 按顺序把每段转为字符串, 再按总长度一次拼接
+fn template(this:Scope, parts:&[TmplPart])-> Litr {
+  enum Piece<'a> {
+    Text(&'a str),
+    // Str只增加引用计数, 不复制内容
+    Str(Shared<String>),
+    Owned(String)
+  }
+  impl Piece<'_> {
+    fn as_str(&self)-> &str {
+      match self {
+        Piece::Text(s)=> s,
+        Piece::Str(s)=> s,
+        Piece::Owned(s)=> s
+      }
+    }
+  }
+
+  let pieces:Vec<Piece> = parts.iter().map(|p|match p {
+    TmplPart::Text(s)=> Piece::Text(s),
+    TmplPart::Hole(e, spec)=> {
+      let v = this.calc_ref(e);
+      match (&*v, spec) {
+        (v, Some(spec))=> Piece::Owned(spec.apply(v)),
+        (Litr::Str(s), None)=> Piece::Str(s.clone()),
+        (v, None)=> Piece::Owned(v.str())
+      }
+    }
+  }).collect();
+
+  let mut s = String::with_capacity(pieces.iter().map(|p|p.as_str().len()).sum());
+  for p in &pieces {
+    s.push_str(p.as_str());
+  }
+  Litr::Str(s.into())
+}
+
 fn binary(this: Scope, left:&Box<Expr>, right:&Box<Expr>, op:&Box<[u8]>)-> Litr {
   use Litr::*;
 
@@ -680,11 +982,15 @@ fn binary(this: Scope, left:&Box<Expr>, right:&Box<Expr>, op:&Box<[u8]>)-> Litr
     ($o:tt) => {{
       expr_set_diff(this, &left, |left| {
         let right = this.calc(right);
+        if let Some(v) = bigint::binary(stringify!($o).as_bytes(), left, &right) {
+          return v;
+        }
+        let op = stringify!($o);
         // 将Int自动转为对应类型
         match (left, right) {
-          (Uint(l), Uint(r))=> Uint(l $o r),
-          (Uint(l), Int(r))=> Uint(*l $o r as usize),
-          (Int(l), Int(r))=> Int(l $o r),
+          (Uint(l), Uint(r))=> Uint(int::calc_uint(op, *l as i128, r as i128)),
+          (Uint(l), Int(r))=> Uint(int::calc_uint(op, *l as i128, r as i128)),
+          (Int(l), Int(r))=> Int(int::calc_int(op, *l as i128, r as i128)),
           (Float(l), Float(r))=> Float(*l $o r),
           (Float(l), Int(r))=> Float(*l $o r as f64),
           (Int(l), Float(r))=> Float((*l as f64) $o r),
@@ -700,11 +1006,15 @@ fn binary(this: Scope, left:&Box<Expr>, right:&Box<Expr>, op:&Box<[u8]>)-> Litr
     ($op:tt) => {{
       expr_set_diff(this, &left, |left| {
         let right = this.calc(right);
+        if let Some(v) = bigint::binary(stringify!($op).as_bytes(), left, &right) {
+          return v;
+        }
+        let op = stringify!($op);
         // 数字默认为Int，所以弄个Int自动转换
         match (left, right) {
-          (Uint(l), Uint(r))=> Uint(l $op r),
-          (Uint(l), Int(r))=> Uint(*l $op r as usize),
-          (Int(l), Uint(r))=> Uint((*l as usize) $op r),
+          (Uint(l), Uint(r))=> Uint(int::bits_uint(op, *l, r)),
+          (Uint(l), Int(r))=> Uint(int::bits_uint(op, *l, r as usize)),
+          (Int(l), Uint(r))=> Uint(int::bits_uint(op, *l as usize, r)),
           _=> panic!("按位运算并赋值只允许Uint为左值")
         }
       });
@@ -718,6 +1028,14 @@ fn binary(this: Scope, left:&Box<Expr>, right:&Box<Expr>, op:&Box<[u8]>)-> Litr
       expr_set(this, &left, v);
       return Uninit;
     }
+    // 左侧为uninit时才会计算右侧
+    b"??"=> {
+      let left = this.calc(&left);
+      if let Uninit = left {
+        return this.calc(&right);
+      }
+      return left;
+    }
     b"+=" => {
       expr_set_diff(this, left, |left|{
         let right = this.calc_ref(right);
@@ -725,16 +1043,19 @@ fn binary(this: Scope, left:&Box<Expr>, right:&Box<Expr>, op:&Box<[u8]>)-> Litr
           // litr.str()方法会把内部String复制一遍
           // 直接使用原String的引用可以避免这次复制
           if let Str(r) = &*right {
-            return Str([l.as_str(),r.as_str()].concat());
+            return Str([l.as_str(),r.as_str()].concat().into());
           }
           let r = right.str();
-          return Str([l.as_str(),r.as_str()].concat());
+          return Str([l.as_str(),r.as_str()].concat().into());
+        }
+        if let Some(v) = bigint::binary(b"+", left, &right) {
+          return v;
         }
 
         match (left, &*right) {
-          (Uint(l), Uint(r))=> Uint(l + r),
-          (Uint(l), Int(r))=> Uint(*l + *r as usize),
-          (Int(l), Int(r))=> Int(l + r),
+          (Uint(l), Uint(r))=> Uint(int::calc_uint("+", *l as i128, *r as i128)),
+          (Uint(l), Int(r))=> Uint(int::calc_uint("+", *l as i128, *r as i128)),
+          (Int(l), Int(r))=> Int(int::calc_int("+", *l as i128, *r as i128)),
           (Float(l), Float(r))=> Float(*l + r),
           (Float(l), Int(r))=> Float(*l + *r as f64),
           (Int(l), Float(r))=> Float((*l as f64) + r),
@@ -762,11 +1083,16 @@ fn binary(this: Scope, left:&Box<Expr>, right:&Box<Expr>, op:&Box<[u8]>)-> Litr
   /// 二元运算中普通数字的戏份
   macro_rules! impl_num {
     ($op:tt) => {{
+      // 有BigInt参与时整数会自动转为BigInt
+      if let Some(v) = bigint::binary(stringify!($op).as_bytes(), &left, &right) {
+        return v;
+      }
       match (&*left, &*right) {
-        (Int(l),Int(r))=> Int(l $op r),
-        (Uint(l),Uint(r))=> Uint(l $op r),
-        (Uint(l),Int(r))=> Uint(l $op *r as usize),
-        (Int(l),Uint(r))=> Int(l $op *r as isize),
+        // 整数溢出时报错, 需要回绕等行为时使用wrapping_add等方法
+        (Int(l),Int(r))=> Int(int::calc_int(stringify!($op), *l as i128, *r as i128)),
+        (Uint(l),Uint(r))=> Uint(int::calc_uint(stringify!($op), *l as i128, *r as i128)),
+        (Uint(l),Int(r))=> Uint(int::calc_uint(stringify!($op), *l as i128, *r as i128)),
+        (Int(l),Uint(r))=> Int(int::calc_int(stringify!($op), *l as i128, *r as i128)),
         (Float(l),Float(r))=> Float(l $op r),
         (Float(l),Int(r))=> Float(l $op *r as f64),
         (Int(l),Float(r))=> Float(*l as f64 $op r),
@@ -780,10 +1106,13 @@ fn binary(this: Scope, left:&Box<Expr>, right:&Box<Expr>, op:&Box<[u8]>)-> Litr
   /// 二元运算中无符号数的戏份
   macro_rules! impl_unsigned {
     ($op:tt) => {{
+      if let Some(v) = bigint::binary(stringify!($op).as_bytes(), &left, &right) {
+        return v;
+      }
       match (&*left, &*right) {
-        (Uint(l), Uint(r))=> Uint(l $op r),
-        (Uint(l), Int(r))=> Uint(l $op *r as usize),
-        (Int(l), Uint(r))=> Uint((*l as usize) $op r),
+        (Uint(l), Uint(r))=> Uint(int::bits_uint(stringify!($op), *l, *r)),
+        (Uint(l), Int(r))=> Uint(int::bits_uint(stringify!($op), *l, *r as usize)),
+        (Int(l), Uint(r))=> Uint(int::bits_uint(stringify!($op), *l as usize, *r)),
         _=> panic!("{}只允许Uint为左值", stringify!($op))
       }
     }};
@@ -808,10 +1137,14 @@ fn binary(this: Scope, left:&Box<Expr>, right:&Box<Expr>, op:&Box<[u8]>)-> Litr
         // litr.str()方法会把内部String复制一遍
         // 直接使用原String的引用可以避免这次复制
         if let Str(r) = &*right {
-          return Str([l.as_str(),r.as_str()].concat());
+          return Str([l.as_str(),r.as_str()].concat().into());
         }
         let r = right.str();
-        return Str([l.as_str(),r.as_str()].concat());
+        return Str([l.as_str(),r.as_str()].concat().into());
+      }
+      // 实例在左侧时也允许和字符串相加, 会使用@str或to_str
+      if let (Inst(_)|Ninst(_), Str(r)) = (&*left, &*right) {
+        return Str([left.str().as_str(),r.as_str()].concat().into());
       }
       impl_num!(+)
     },
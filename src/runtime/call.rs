@@ -3,26 +3,109 @@ use crate::primitive;
 use self::calc::CalcRef;
 
 use super::*;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::mem::ManuallyDrop;
 
 pub struct CallStackElem {
   pub file: &'static str,
   pub line: usize,
+  /// 调用所在语句的开头行, 只在调试时记录
+  pub start: usize,
   pub fname: Interned
 }
 /// 报错时打印调用栈
 pub static mut CALL_STACK: Vec<CallStackElem> = Vec::new();
-const MAX_CALL_COUNT: usize = 0x1000;
+/// 取得调用栈
+pub fn call_stack()-> &'static mut Vec<CallStackElem> {
+  unsafe {&mut *std::ptr::addr_of_mut!(CALL_STACK)}
+}
+/// 最大调用深度, 可用--max-depth修改
+pub static mut MAX_DEPTH: usize = 0x10000;
 fn push_stack(fname: Interned) {
+  stack_guard();
+  let stack = call_stack();
   unsafe{
     let file = crate::FILE_PATH;
-    if CALL_STACK.len()>MAX_CALL_COUNT {
-      panic!("递归过多, 超出最大调用限制{}", MAX_CALL_COUNT)
+    let max = MAX_DEPTH;
+    if stack.len()>=max {
+      panic!("递归过多, 调用深度超出限制{}", max)
+    }
+    stack.push(CallStackElem{file, line:LINE, start:crate::debugger::START, fname});
+    if crate::debugger::ACTIVE {
+      crate::debugger::on_call(fname);
     }
-    CALL_STACK.push(CallStackElem{file, line:LINE, fname})
+  }
+}
+
+pub fn set_max_depth(n:usize) {
+  unsafe {MAX_DEPTH = n;}
+}
+
+/// 留给报错和调用栈打印的栈空间
+const STACK_RESERVE: usize = 256 << 10;
+thread_local! {
+  /// 当前线程可用的最低栈地址, 为0时不检查
+  static STACK_LIMIT: Cell<usize> = const {Cell::new(0)};
+}
+
+/// 在线程开头调用, 记录该线程的栈大小
+pub fn set_stack_size(size:usize) {
+  let top = std::hint::black_box(&size) as *const usize as usize;
+  STACK_LIMIT.set(top.saturating_sub(size) + STACK_RESERVE);
+}
+
+/// 栈空间即将耗尽时报错, 而不是让整个进程崩溃
+#[inline]
+pub fn stack_guard() {
+  let sp = 0u8;
+  if std::hint::black_box(&sp) as *const u8 as usize <= STACK_LIMIT.get() {
+    panic!("栈空间不足, 当前调用深度{}", call_stack().len())
   }
 }
 fn pop_stack() {
-  unsafe{CALL_STACK.pop();}
+  call_stack().pop();
+}
+
+/// 正在运行的本地函数的返回值指针
+/// 
+/// return所在作用域的return_to与之相同时才能留下尾调用, try中会暂时置空
+pub static mut FUNC_RET:*mut Litr = std::ptr::null_mut();
+/// return f()留下的尾调用, 由外层的call_local_with_self接着执行
+pub static mut TAIL_CALL:Option<(LocalFunc, Vec<Litr>, *mut Litr)> = None;
+
+/// a.b()调用处的内联缓存
+/// 
+/// 记住上次调用时的类型和方法下标, 同一类型再次调用时跳过按名称查找
+#[derive(Debug, Clone, Default)]
+pub struct MethodCache(Cell<(usize, usize)>);
+
+/// 缓存命中前总会核对名称, 中途panic留下的旧值也不会被误用
+impl std::panic::RefUnwindSafe for MethodCache {}
+
+impl MethodCache {
+  pub const fn new()-> Self {
+    MethodCache(Cell::new((0, 0)))
+  }
+
+  /// 查找类的成员方法, 没有时返回None
+  pub fn class_method<'a>(&self, cls:&'a ClassDef, name:Interned)-> Option<&'a ClassFuncRaw> {
+    let i = self.find(cls as *const ClassDef as usize, &cls.methods, name, |m|m.f.name)?;
+    Some(&cls.methods[i])
+  }
+
+  /// 在方法表中找到name的下标
+  /// 
+  /// key用于区分类型, 命中后仍会核对名称, 防止类型被释放后地址被复用
+  fn find<T>(&self, key:usize, table:&[T], name:Interned, get:fn(&T)-> Interned)-> Option<usize> {
+    let (k, i) = self.0.get();
+    if k == key && table.get(i).is_some_and(|m|get(m) == name) {
+      return Some(i);
+    }
+    let i = table.iter().position(|m|get(m) == name)?;
+    self.0.set((key, i));
+    Some(i)
+  }
 }
 
 impl Scope {
@@ -48,72 +131,93 @@ impl Scope {
   }
 
   /// 为a.b()的行为匹配对应方法并调用
-  pub fn call_method(self, mut args:Vec<CalcRef>, mut targ:CalcRef, name:Interned)-> Litr {
+  pub fn call_method(self, args:Vec<CalcRef>, mut targ:CalcRef, name:Interned, cache:&MethodCache)-> Litr {
     push_stack(name);
+    // 按基本类型的方法表调用
+    macro_rules! table {($v:ident, $m:ident::$f:ident, $t:literal)=> {{
+      let table = primitive::$m::$f();
+      let i = cache.find(table.as_ptr() as usize, table, name, |m|m.0)
+        .unwrap_or_else(||panic!("{}没有{}方法", $t, name));
+      (table[i].1)($v, args, self)
+    }}}
     let r = match &mut *targ {
-      Litr::Bool(v)=> match name.vec() {
-        b"rev"=> Litr::Bool(!*v),
-        b"then"=> {
-          let f = match args.get_mut(0) {
-            Some(f)=> match &**f {
-              Litr::Func(f)=> f,
-              _=> panic!("bool.then第一个参数必须是函数")
-            },
-            None=> return Litr::Uninit
-          };
-          if *v {
-            self.call(vec![], f)
-          }else {
-            Litr::Uninit
-          }
-        }
-        _=> panic!("Bool类型只有'rev'和'then'方法")
-      }
-      Litr::Buf(v)=> primitive::buf::method(v, self, name, args),
-      Litr::List(v)=> primitive::list::method(v, self, name, args),
-      Litr::Obj(o)=> primitive::obj::method(o, self, name, args),
-      Litr::Int(n)=> primitive::int::method_int(*n, name, args),
-      Litr::Uint(n)=> primitive::int::method_uint(*n, name, args),
-      Litr::Float(n)=> primitive::float::method(*n, name, args),
-      Litr::Str(s)=> primitive::kstr::method(s, self, name, args),
-      Litr::Func(f)=> primitive::func::method(f, name, self, args),
+      Litr::Bool(v)=> table!(v, kbool::methods, "Bool"),
+      Litr::Buf(v)=> table!(v, buf::methods, "Buf"),
+      Litr::List(v)=> table!(v, list::methods, "List"),
+      Litr::Obj(o)=> table!(o, obj::methods, "Obj"),
+      Litr::Int(n)=> table!(n, int::int_methods, "Int"),
+      Litr::Uint(n)=> table!(n, int::uint_methods, "Uint"),
+      Litr::Float(n)=> table!(n, float::methods, "Float"),
+      Litr::BigInt(n)=> table!(n, bigint::methods, "BigInt"),
+      Litr::Str(s)=> table!(s, kstr::methods, "Str"),
+      Litr::Func(f)=> table!(f, func::methods, "Func"),
       Litr::Uninit=> panic!("uninit没有方法"),
+      Litr::Enum(e)=> panic!("枚举值'{}'没有'{}'方法", e.name(), name),
       Litr::Inst(inst)=> {
-        let cannot_access_private = unsafe {(*inst.cls).cx.exports} != self.exports;
-        let cls = unsafe {&*inst.cls};
-
-        let methods = &cls.methods;
-        for mthd in methods.iter() {
-          if mthd.f.name == name {
-            if !mthd.public && cannot_access_private {
-              panic!("'{}'类型的成员方法'{}'是私有的", cls.name, name)
-            }
-            let f = LocalFunc::new(&mthd.f, cls.cx);
-            let args = args.into_iter().map(|e|e.own()).collect();
-            return Scope::call_local_with_self(&f, args, &mut *targ);
-          }
-        }
-
-        panic!("'{}'类型没有'{}'方法\n  你需要用(x.{})()的写法吗?",cls.name, name, name)
+        let f = self.inst_method(inst, name, cache);
+        let args = args.into_iter().map(|e|e.own()).collect();
+        Scope::call_local_with_self(&f, args, &mut *targ)
       }
       Litr::Ninst(inst)=> {
         let cls = unsafe{&*inst.cls};
-        let (_,f) = cls.methods.iter()
-          .find(|(find,_)|name==*find).unwrap_or_else(||panic!("'{}'原生类型中没有'{}'方法\n  你需要用(x.{})()的写法吗?", cls.name, name, name));
-        (*f)(inst, args, self)
+        let i = cache.find(inst.cls as usize, &cls.methods, name, |m|m.0)
+          .unwrap_or_else(||panic!("'{}'原生类型中没有'{}'方法\n  你需要用(x.{})()的写法吗?", cls.name, name, name));
+        (cls.methods[i].1)(inst, args, self)
       }
     };
     pop_stack();
     r
   }
 
+  /// 找到本地类实例的方法, 在类外调用私有方法时报错
+  pub fn inst_method(self, inst:&Instance, name:Interned, cache:&MethodCache)-> ManuallyDrop<LocalFunc> {
+    let cannot_access_private = unsafe {(*inst.cls).cx.exports} != self.exports;
+    let cls = unsafe {&*inst.cls};
+
+    let mthd = cache.class_method(cls, name)
+      .unwrap_or_else(||panic!("'{}'类型没有'{}'方法\n  你需要用(x.{})()的写法吗?",cls.name, name, name));
+    if !mthd.public && cannot_access_private {
+      panic!("'{}'类型的成员方法'{}'是私有的", cls.name, name)
+    }
+    // 方法定义和类的作用域都比这次调用活得久, 不必增减作用域的引用计数
+    ManuallyDrop::new(LocalFunc {ptr:&mthd.f, scope:cls.cx})
+  }
+
   /// 实际调用一个local function
   pub fn call_local(self, f:&LocalFunc, args:Vec<Litr>)-> Litr {
     Scope::call_local_with_self(f, args, self.kself)
   }
   
   /// 实际调用一个local function并传入self
+  /// 
+  /// 函数体中return f()留下的尾调用会在这里接着执行, 不再增加调用深度
   pub fn call_local_with_self(f:&LocalFunc, args:Vec<Litr>, kself:*mut Litr)-> Litr {
+    let (mut f, mut args, mut kself) = (Cow::Borrowed(f), args, kself);
+    // 尾调用链上出现过的函数, 最终返回值要符合它们每一个的返回类型
+    let mut chain:Vec<LocalFunc> = Vec::new();
+    loop {
+      let ret = Scope::run_local(&f, args, kself);
+      match unsafe {std::ptr::replace(std::ptr::addr_of_mut!(TAIL_CALL), None)} {
+        Some((next, next_args, next_self))=> {
+          if !chain.iter().any(|g|g.ptr == f.ptr) {
+            chain.push(f.into_owned());
+          }
+          f = Cow::Owned(next);
+          args = next_args;
+          kself = next_self;
+        }
+        None=> {
+          for g in chain.iter().chain([&*f]).filter(|g|!g.generator) {
+            assert!(g.ret.is(&ret, g.scope), "函数'{}'要求返回{:?}类型, 但返回了{:?}", g.name, g.ret, ret);
+          }
+          return ret;
+        }
+      }
+    }
+  }
+
+  /// 绑定参数并运行一次函数体
+  fn run_local(f:&LocalFunc, args:Vec<Litr>, kself:*mut Litr)-> Litr {
     // 将传入参数按定义参数数量放入作用域
     let init_vars = match &f.argdecl {
       // 正常传参
@@ -128,19 +232,26 @@ impl Scope {
             arg = f.scope.calc(&argdecl.default);
           }
           assert!(argdecl.t.is(&arg, f.scope), "函数要求{:?}类型, 但传入了{:?}", argdecl.t, arg);
-          let var = Variant {name:argdecl.name, v:arg, locked:false};
-          vars.push(var);
+          match &argdecl.pat {
+            Some(pat)=> super::evil::destructure(f.scope, pat, arg, &mut |name, v|vars.push(Variant {name, v, locked:false})),
+            None=> vars.push(Variant {name:argdecl.name, v:arg, locked:false})
+          }
         }
         vars
       }
       // List传参
       LocalFuncRawArg::Custom(name)=> {
         let mut vars = Vec::with_capacity(f.stmts.vars + 1);
-        vars.push(Variant {name:*name, v:Litr::List(args), locked:false});
+        vars.push(Variant {name:*name, v:Litr::List(args.into()), locked:false});
         vars
       }
     };
 
+    // 生成器函数只在next时运行函数体
+    if f.generator {
+      return primitive::generator::new(f, init_vars, kself);
+    }
+
     let mut ret = Litr::Uninit;
     let mut scope = f.scope.subscope();
     scope.return_to = &mut ret;
@@ -155,7 +266,11 @@ impl Scope {
       // 调用栈推一份记录
       push_stack(f.name);
 
+      // 只有直接写在这个函数中的return才能留下尾调用
+      let func_ret = FUNC_RET;
+      FUNC_RET = &mut ret;
       scope.run(&f.stmts);
+      FUNC_RET = func_ret;
 
       crate::FILE_PATH = std::mem::take(&mut file_path);
       pop_stack();
@@ -7,6 +7,7 @@ use super::*;
 impl Scope {
   /// 解析一个语句，对应Stmt
   pub fn evil(&mut self, code:&Stmt) {
+    call::stack_guard();
     match code {
       // 只有表达式的语句
       Stmt::Expression(e)=> {
@@ -39,6 +40,12 @@ impl Scope {
         self.class_uses.push((unsafe{(**cls).name}, Class::Local(clsdef)));
       }
 
+      // 枚举声明
+      Stmt::Enum(e)=> {
+        let def = Box::into_raw(Box::new(EnumDef { p:*e, cx:self.clone() }));
+        self.class_uses.push((unsafe{(**e).name}, Class::Enum(def)));
+      }
+
       Stmt::Using(alia, e)=> {
         match e {
           Expr::Variant(id)=> {
@@ -87,11 +94,31 @@ impl Scope {
         module.classes.push((name, clsdef))
       }
 
+      // 导出枚举 mod:enum
+      Stmt::ExportEnum(e)=> {
+        let name = unsafe{(**e).name};
+        // 延长作用域生命周期
+        outlive::increase_scope_count(*self);
+
+        let def = Box::into_raw(Box::new(EnumDef { p:*e, cx:self.clone() }));
+        self.class_uses.push((name, Class::Enum(def)));
+
+        let module = unsafe {&mut*self.exports};
+        module.enums.push((name, def))
+      }
+
       // 返回一个值
       Stmt::Return(expr)=> {
         // 遇到return语句就停止当前遍历
         // 并将返回值指针相同(在同一函数内的作用域)设为已结束
-        unsafe{*self.return_to = self.calc(expr)};
+        let v = match expr {
+          // 直接在函数体中return f()时复用当前栈帧
+          Expr::Call { args, targ } if self.return_to == unsafe {call::FUNC_RET}=> self.call_expr(args, targ, true),
+          Expr::CallMethod { args, targ, name, cache } if matches!(**targ, Expr::Kself) && self.return_to == unsafe {call::FUNC_RET}=> 
+            self.call_method_expr(args, self.calc_ref(targ), *name, cache, true),
+          _=> self.calc(expr)
+        };
+        unsafe{*self.return_to = v};
         self.ended = true;
         let mut scope = *self;
         while let Some(mut s) = scope.parent {
@@ -110,55 +137,12 @@ impl Scope {
         }
       }
 
-      // for ()语句
-      Stmt::ForWhile { condition, exec }=>
-        start_loop(*self, ||cond(self.calc(condition)), exec),
-
-      // for!语句
-      Stmt::ForLoop(exec)=> start_loop(*self, ||true, exec),
-
-      // for v:iter语句
-      Stmt::ForIter{exec, id, iterator: iter}=> {
-        use primitive::iter::LitrIterator;
-
-        let mut iter_ = self.calc_ref(iter);
-        let iter = LitrIterator::new(&mut iter_);
-        let mut breaked = false;
-
-        match &**exec {
-          Stmt::Block(exec)=> {
-            for v in iter {
-              let mut scope = self.subscope();
-              scope.vars = Vec::with_capacity(exec.vars);
-              if scope.ended || breaked {
-                outlive::scope_end(scope);
-                return;
-              }
-              if let Some(id) = id {
-                scope.vars.push(Variant {name:*id, v, locked:false});
-              }
-              loop_run(scope, &mut breaked, exec);
-              outlive::scope_end(scope);
-            }
-          },
-
-          // 禁止单语句直接用循环控制语句
-          Stmt::Break=> panic!("不允许`for v:iter break`的写法"),
-          Stmt::Continue=> panic!("不允许`for v:iter continue`的写法`"),
-          
-          // 单语句运行
-          _=> if let None = id {
-            let scope = self.subscope();
-            for _ in iter {
-              self.evil(exec);
-            }
-            outlive::scope_end(scope);
-          }else {
-            // 指定迭代过程的变量名时不可使用单语句写法
-            panic!("指定了变量名的迭代 不可使用单语句")
-          }
-        }
-      },
+      // for循环语句
+      Stmt::ForWhile {..}|Stmt::ForLoop {..}|Stmt::ForIter {..}=> {
+        let mut ctl = LoopCtl::Run;
+        run_loop(*self, code, &mut ctl);
+        unhandled_ctl(ctl);
+      }
 
       Stmt::Match{ to, arms, def }=> {
         let to = self.calc_ref(to);
@@ -180,17 +164,29 @@ impl Scope {
           None=> false
         };
 
+        // 枚举成员匹配时绑定的变量
+        let mut binds = Vec::new();
         for (conds, stmts) in arms {
           // 如果第一个条件的符号是=就是逻辑或(any, 任意符合)
           let matched = if let MatchOrd::Eq = conds[0].1 {
-            conds.iter().any(matcher)
+            conds.iter().any(|cond|match cond {
+              // Enum::Variant(a, b)会匹配枚举成员并绑定其携带的值
+              (Expr::Call { args, targ }, MatchOrd::Eq)=> 
+                match match_enum(*self, &to, targ, args, &mut binds) {
+                  Some(b)=> b,
+                  None=> matcher(cond)
+                }
+              _=> matcher(cond)
+            })
           }else {
           // 如果是大于小于就是逻辑与(all, 全部符合)
             conds.iter().all(matcher)
           };
           // 匹配并运行
           if matched {
-            self.subscope().run(stmts);
+            let mut scope = self.subscope();
+            scope.vars = binds;
+            scope.run(stmts);
             return;
           };
         }
@@ -200,6 +196,8 @@ impl Scope {
         }
       }
 
+      // 交出一个值并挂起到下一次next
+      Stmt::Yield(e)=> primitive::generator::yielding(self.calc(e)),
       Stmt::Throw(s)=> panic!("{}", self.calc_ref(s).str()),
       Stmt::Try { stmt, catc }=> {
         let mut _self = *self;
@@ -207,10 +205,24 @@ impl Scope {
         // 静默panic
         let hook = std::panic::take_hook();
         std::panic::set_hook(Box::new(|_inf|()));
+        // try中的return f()不能延后执行, 否则f中的错误会逃出try
+        let func_ret = unsafe {std::ptr::replace(std::ptr::addr_of_mut!(call::FUNC_RET), std::ptr::null_mut())};
+        let depth = call::call_stack().len();
         let res = std::panic::catch_unwind(move|| _self.evil(stmt));
+        unsafe {
+          call::FUNC_RET = func_ret;
+          // 出错时调用栈来不及弹出
+          call::call_stack().truncate(depth);
+        }
         
         std::panic::set_hook(hook);
 
+        // 被丢弃的生成器需要一路退出线程, 不能被catch拦下
+        let res = match res {
+          Err(err) if err.is::<crate::primitive::generator::Abandoned>()=> std::panic::resume_unwind(err),
+          res=> res
+        };
+
         if let Some((id, catc)) = catc {
           if let Err(err) = res {
             let s = if let Some(mes) = err.downcast_ref::<&'static str>() {
@@ -219,82 +231,168 @@ impl Scope {
               mes.clone()
             }else{"错误".to_string()}; 
             let mut scope = self.subscope();
-            scope.vars.push(Variant { name:*id, locked: false, v:  Litr::Str(s)});
+            scope.vars.push(Variant { name:*id, locked: false, v:  Litr::Str(s.into())});
             scope.run(catc);
           }
         }
       }
 
       // -
-      Stmt::Break=> panic!("break不在循环体内"),
-      Stmt::Continue=> panic!("continue不在循环体内"),
+      Stmt::Break(..)=> panic!("break不在循环体内"),
+      Stmt::Continue(_)=> panic!("continue不在循环体内"),
       Stmt::Empty=> (),
     }
   }
 }
 
 /// let和const
-fn assign(mut s:Scope, asn:&AssignDef, locked: bool) {
+fn assign(s:Scope, asn:&AssignDef, locked: bool) {
   // 如果用的是<而不是=, 则直接夺取右侧值所有权
   let v = if asn.take {
     std::mem::take(&mut *s.calc_ref(&asn.val))
   } else {
     s.calc(&asn.val)
   };
+  // 只声明不赋值时不检查类型
+  if !matches!(asn.val, Expr::Literal(Litr::Uninit)) {
+    assert!(asn.typ.is(&v, s), "变量要求{:?}类型, 但赋值了{:?}", asn.typ, v);
+  }
   // 不检查变量是否存在是因为寻找变量的行为是反向的
-  match &asn.id {
-    AssignTo::One(id)=> {
-      s.vars.push(Variant {name:*id, v, locked});
-    }
-    AssignTo::Destr(ids)=> match v {
-      // 属性解构
-      Litr::Inst(mut inst)=> {
-        let cls = unsafe{&*inst.cls};
-        for id in ids {
-          let i = cls.props.iter()
-            .position(|prop|*id==prop.name)
-            .expect(&format!("本地类'{}'实例没有'{}'属性", cls.name, id));
-          let v = std::mem::take(&mut inst.v[i]);
-          s.vars.push(Variant {name:*id, v, locked});
-        }
-      }
-      Litr::Ninst(v)=> {
-        let cls = unsafe{&*v.cls};
-        for id in ids {
-          let v = (cls.getter)(&v, *id);
-          s.vars.push(Variant {name:*id, v, locked});
-        }
+  let mut vars = s;
+  destructure(s, &asn.id, v, &mut |name, v|vars.vars.push(Variant {name, v, locked}));
+}
+
+/// 将值按赋值目标解构, 每个得到的变量都交给bind
+/// 
+/// 取到uninit时使用默认值, 默认值在cx中计算
+pub fn destructure(cx:Scope, to:&AssignTo, v:Litr, bind:&mut impl FnMut(Interned, Litr)) {
+  let destr = match to {
+    AssignTo::One(id)=> return bind(*id, v),
+    AssignTo::Destr(d)=> &**d
+  };
+  let mut item = |item:&DestrItem, v:Litr| {
+    let v = if let Litr::Uninit = v {cx.calc(&item.default)}else {v};
+    destructure(cx, &item.to, v, bind);
+  };
+
+  let (rest, rest_v) = match destr {
+    Destr::List {items, rest}=> (rest, match v {
+      // 对Obj和类实例按变量名取属性, 原生类实例同样使用getter
+      Litr::Obj(_)|Litr::Inst(_)|Litr::Ninst(_)=> {
+        let keys = items.iter().map(|item|match &item.to {
+          AssignTo::One(id)=> *id,
+          AssignTo::Destr(_)=> panic!("按属性解构时不可用[]嵌套, 请使用{{}}")
+        }).collect();
+        destr_props(v, keys, rest.is_some(), |n, v|item(&items[n], v))
       }
-      Litr::Obj(mut map)=> for id in ids {
-        let v = map.remove(id).expect(&format!("哈希表中没有'{id}'属性"));
-        s.vars.push(Variant {name:*id, v, locked});
+      _=> destr_iter(v, items.len(), rest.is_some(), |n, v|item(&items[n], v))
+    }),
+    Destr::Obj {items, rest}=> (rest, match v {
+      Litr::Obj(_)|Litr::Inst(_)|Litr::Ninst(_)=> {
+        let keys = items.iter().map(|(key, _)|*key).collect();
+        destr_props(v, keys, rest.is_some(), |n, v|item(&items[n].1, v))
       }
+      // 对可迭代值按顺序取值
+      _=> destr_iter(v, items.len(), rest.is_some(), |n, v|item(&items[n].1, v))
+    })
+  };
+  if let Some(rest) = rest {
+    bind(*rest, rest_v);
+  }
+}
 
-      // 线性解构
-      Litr::Buf(v)=> {
-        let mut v = v.into_iter();
-        for id in ids {
-          let v = v.next().map_or(Litr::Uninit, |n|Litr::Uint(n as _));
-          s.vars.push(Variant {name:*id, v, locked});
-        }
+/// 按属性名解构, 缺失的键得到uninit
+/// 
+/// 需要rest时将剩余属性收集为Obj返回
+fn destr_props(v:Litr, keys:Vec<Interned>, rest:bool, mut f:impl FnMut(usize, Litr))-> Litr {
+  match v {
+    Litr::Inst(mut inst)=> {
+      let cls = unsafe{&*inst.cls};
+      let mut taken = vec![false; cls.props.len()];
+      for (n, id) in keys.iter().enumerate() {
+        let i = cls.props.iter()
+          .position(|prop|*id==prop.name)
+          .unwrap_or_else(||panic!("本地类'{}'实例没有'{}'属性", cls.name, id));
+        taken[i] = true;
+        f(n, std::mem::take(&mut inst.v[i]));
       }
-      Litr::Str(str)=> {
-        let mut itr = str.chars();
-        for id in ids {
-          let v = itr.next().map_or(Litr::Uninit, |s|Litr::Str(s.to_string()));
-          s.vars.push(Variant {name:*id, v, locked});
-        }
+      if !rest {
+        return Litr::Uninit;
       }
-      Litr::List(v)=> {
-        let mut v = v.into_iter();
-        for id in ids {
-          let v = v.next().unwrap_or(Litr::Uninit);
-          s.vars.push(Variant {name:*id, v, locked});
-        }
+      Litr::Obj(cls.props.iter().zip(inst.v.iter_mut()).zip(taken)
+        .filter(|(_, taken)|!taken)
+        .map(|((prop, v), _)|(prop.name, std::mem::take(v))).collect())
+    }
+    Litr::Ninst(v)=> {
+      assert!(!rest, "原生类实例不可使用..解构剩余属性");
+      let cls = unsafe{&*v.cls};
+      for (n, id) in keys.iter().enumerate() {
+        f(n, (cls.getter)(&v, *id));
+      }
+      Litr::Uninit
+    }
+    Litr::Obj(mut map)=> {
+      for (n, id) in keys.iter().enumerate() {
+        f(n, map.remove(id).unwrap_or(Litr::Uninit));
+      }
+      if rest {Litr::Obj(map)}else {Litr::Uninit}
+    }
+    _=> unreachable!()
+  }
+}
+
+/// 按顺序解构可迭代的值, 不够的位置得到uninit
+/// 
+/// 需要rest时剩余的值会被收集, Buf和Str保持原类型, 其他都收集为List
+fn destr_iter(mut v:Litr, len:usize, rest:bool, mut f:impl FnMut(usize, Litr))-> Litr {
+  use primitive::iter::LitrIterator;
+  // Buf按字节解构, 剩余部分直接切出
+  if let Litr::Buf(b) = &v {
+    for n in 0..len {
+      f(n, b.get(n).map_or(Litr::Uninit, |n|Litr::Uint(*n as usize)));
+    }
+    return if rest {Litr::Buf(b.get(len..).unwrap_or(&[]).to_vec().into())}else {Litr::Uninit};
+  }
+  let rest_v = {
+    let mut itr = LitrIterator::new(&mut v);
+    for n in 0..len {
+      f(n, itr.next().unwrap_or(Litr::Uninit));
+    }
+    if !rest {
+      return Litr::Uninit;
+    }
+    itr.collect::<Vec<Litr>>()
+  };
+  match v {
+    Litr::Str(_)=> Litr::Str(rest_v.into_iter().map(|s|s.str()).collect()),
+    _=> Litr::List(rest_v.into())
+  }
+}
+
+/// 用`Enum::Variant(a, b)`的模式匹配一个值
+/// 
+/// 标识符会被绑定为对应的值, `_`会被忽略, 其他表达式会和对应的值比较
+/// 
+/// targ不是枚举成员时返回None
+fn match_enum(this:Scope, to:&Litr, targ:&Expr, args:&[Expr], binds:&mut Vec<Variant>)-> Option<bool> {
+  let (def, tag) = this.find_enum_variant(targ)?;
+  let e = match to {
+    Litr::Enum(e) if e.same_def(def) && e.tag == tag=> e,
+    _=> return Some(false)
+  };
+  binds.clear();
+  for (arg, v) in args.iter().zip(e.v.iter()) {
+    match arg {
+      Expr::Variant(id)=> if id.vec() != b"_" {
+        binds.push(Variant {name:*id, v:v.clone(), locked:false});
+      }
+      _=> if *this.calc_ref(arg) != *v {
+        binds.clear();
+        return Some(false);
       }
-      _=> panic!("{v:?}无法被解构赋值")
     }
   }
+  Some(true)
 }
 
 /// 判断if后的条件
@@ -306,76 +404,185 @@ fn cond(v:Litr)-> bool {
   }
 }
 
+/// 循环体中的流程控制
+pub enum LoopCtl {
+  Run,
+  /// break携带的标签和值
+  Break(Option<Interned>, Litr),
+  /// continue携带的标签
+  Continue(Option<Interned>)
+}
+
+/// 没有被任何循环接住的带标签的break和continue
+pub fn unhandled_ctl(ctl:LoopCtl) {
+  match ctl {
+    LoopCtl::Break(Some(label), _)|LoopCtl::Continue(Some(label))=>
+      panic!("找不到标签为'#{}'的循环", label),
+    _=> ()
+  }
+}
+
+/// 循环体运行一次后, 根据ctl判断循环是否结束
+/// 
+/// 属于该循环的break会返回其携带的值, 
+/// 属于外层循环的控制会留在ctl中并返回uninit
+fn loop_end(label:Option<Interned>, ctl:&mut LoopCtl)-> Option<Litr> {
+  match ctl {
+    LoopCtl::Run=> None,
+    LoopCtl::Continue(l) if l.is_none() || *l == label=> {
+      *ctl = LoopCtl::Run;
+      None
+    }
+    LoopCtl::Break(l, v) if l.is_none() || *l == label=> {
+      let v = std::mem::take(v);
+      *ctl = LoopCtl::Run;
+      Some(v)
+    }
+    _=> Some(Litr::Uninit)
+  }
+}
+
+/// 运行一个循环语句并返回break携带的值
+pub fn run_loop(this:Scope, code:&Stmt, ctl:&mut LoopCtl)-> Litr {
+  match code {
+    // for ()语句
+    Stmt::ForWhile { label, condition, exec }=>
+      start_loop(this, *label, ||cond(this.calc(condition)), exec, ctl),
+
+    // for!语句
+    Stmt::ForLoop { label, exec }=> start_loop(this, *label, ||true, exec, ctl),
+
+    // for v:iter语句
+    Stmt::ForIter{label, exec, id, iterator: iter}=> {
+      use primitive::iter::LitrIterator;
+
+      let mut iter_ = this.calc_ref(iter);
+      let iter = LitrIterator::new(&mut iter_);
+
+      match &**exec {
+        Stmt::Block(exec)=> {
+          for v in iter {
+            let mut scope = this.subscope();
+            scope.vars = Vec::with_capacity(exec.vars);
+            if let Some(id) = id {
+              let mut vars = scope;
+              destructure(scope, id, v, &mut |name, v|vars.vars.push(Variant {name, v, locked:false}));
+            }
+            loop_run(scope, ctl, exec);
+            outlive::scope_end(scope);
+            if this.ended {
+              return Litr::Uninit;
+            }
+            if let Some(v) = loop_end(*label, ctl) {
+              return v;
+            }
+          }
+          Litr::Uninit
+        },
+
+        // 禁止单语句直接用循环控制语句
+        Stmt::Break(..)=> panic!("不允许`for v:iter break`的写法"),
+        Stmt::Continue(_)=> panic!("不允许`for v:iter continue`的写法`"),
+        
+        // 单语句运行
+        _=> if let None = id {
+          for _ in iter {
+            loop_stmt(this, exec, ctl);
+            if this.ended {
+              return Litr::Uninit;
+            }
+            if let Some(v) = loop_end(*label, ctl) {
+              return v;
+            }
+          }
+          Litr::Uninit
+        }else {
+          // 指定迭代过程的变量名时不可使用单语句写法
+          panic!("指定了变量名的迭代 不可使用单语句")
+        }
+      }
+    },
+    _=> unreachable!()
+  }
+}
+
 /// 在一个作用域开始循环
-fn start_loop(mut this:Scope, mut condition:impl FnMut()-> bool, exec:&Box<Stmt>) {
+fn start_loop(this:Scope, label:Option<Interned>, mut condition:impl FnMut()-> bool, exec:&Box<Stmt>, ctl:&mut LoopCtl)-> Litr {
   if let Stmt::Block(exec) = &**exec {
     let mut scope = this.subscope();
     scope.vars = Vec::with_capacity(exec.vars);
-    let mut breaked = false;
     while condition() {
-      if scope.ended || breaked {
-        outlive::scope_end(scope);
-        return;
-      }
       // 重置此作用域
       scope.vars.clear();
       scope.class_uses.clear();
-      loop_run(scope, &mut breaked, exec);
+      loop_run(scope, ctl, exec);
+      if scope.ended {
+        break;
+      }
+      if let Some(v) = loop_end(label, ctl) {
+        outlive::scope_end(scope);
+        return v;
+      }
     }
     outlive::scope_end(scope);
   // 单语句将由当前作用域代为执行,不再创建新作用域
   }else {
     match &**exec {
-      Stmt::Break=> panic!("不允许`for() break`的写法"),
-      Stmt::Continue=> panic!("不允许`for() continue`的写法`"),
+      Stmt::Break(..)=> panic!("不允许`for() break`的写法"),
+      Stmt::Continue(_)=> panic!("不允许`for() continue`的写法`"),
       _=> while condition() {
+        loop_stmt(this, exec, ctl);
         if this.ended {
-          return;
+          break;
+        }
+        if let Some(v) = loop_end(label, ctl) {
+          return v;
         }
-        this.evil(exec);
       }
     }
   }
+  Litr::Uninit
 }
 
 /// 以循环模式运行一段语句
-fn loop_run(mut scope:Scope,breaked:&mut bool,exec:&Statements) {
-  // 对于单Stmt的run实现
-  macro_rules! loop_run_stmt {($stmt:expr)=>{{
-    match $stmt {
-      Stmt::Block(exec)=> {
-        let mut s = scope.subscope();
-        loop_run(s, breaked, exec);
-        s.ended = true;
-        outlive::scope_end(s);
-      },
-      Stmt::Break=> return *breaked = true,
-      Stmt::Continue=> return,
-      _=> scope.evil($stmt)
-    };
-  }}}
-
+fn loop_run(scope:Scope, ctl:&mut LoopCtl, exec:&Statements) {
   for (l, sm) in &exec.v {
-    // 如果中途遇到return或者break就停止
-    if scope.ended || *breaked {
+    unsafe{
+      LINE = l.end;
+      if crate::debugger::ACTIVE {
+        crate::debugger::on_line(scope, l.start);
+      }
+    }
+    loop_stmt(scope, sm, ctl);
+    // 如果中途遇到return, break或continue就停止
+    if scope.ended || !matches!(ctl, LoopCtl::Run) {
       return;
     }
-    match sm {
-      Stmt::Break=> return *breaked = true,
-      Stmt::Continue=> return,
-      // 把直属该for下的块拦截,检测break和continue
-      Stmt::Block(_)=> loop_run(scope, breaked, exec),
-      Stmt::If { condition, exec, els }=> {
-        if cond(scope.calc(condition)) {
-          loop_run_stmt!(&**exec)
-        }else if let Some(els) = els {
-          loop_run_stmt!(&**els)
-        }
-      },
-      _=> {
-        unsafe{LINE = *l;}
-        scope.evil(sm);
+  }
+}
+
+/// 以循环模式运行单个语句, 拦截其中的break和continue
+fn loop_stmt(mut scope:Scope, sm:&Stmt, ctl:&mut LoopCtl) {
+  match sm {
+    Stmt::Break(label, val)=> *ctl = LoopCtl::Break(*label, scope.calc(val)),
+    Stmt::Continue(label)=> *ctl = LoopCtl::Continue(*label),
+    Stmt::Block(exec)=> {
+      let mut s = scope.subscope();
+      loop_run(s, ctl, exec);
+      s.ended = true;
+      outlive::scope_end(s);
+    },
+    Stmt::If { condition, exec, els }=> {
+      if cond(scope.calc(condition)) {
+        loop_stmt(scope, exec, ctl)
+      }else if let Some(els) = els {
+        loop_stmt(scope, els, ctl)
       }
+    },
+    // 内层循环没接住的控制会留在ctl中交给本循环
+    Stmt::ForWhile {..}|Stmt::ForLoop {..}|Stmt::ForIter {..}=> {
+      run_loop(scope, sm, ctl);
     }
+    _=> scope.evil(sm)
   }
-}
\ No newline at end of file
+}
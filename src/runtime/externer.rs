@@ -67,10 +67,12 @@ pub fn translate(arg:&Litr)-> Result<usize,String> {
         _=> Err("将原生函数传进C函数是未定义行为".to_string())
       }
     }
+    BigInt(_)=> Err("BigInt不可作为C参数传递, 请先使用to_int或to_buf".to_string()),
     List(_)=> Err("列表类型不可作为C指针传递".to_string()),
     Obj(_)=> Err("Ks对象不可作为C指针传递".to_string()),
     Inst(_)=> Err("Ks实例不可作为C指针传递".to_string()),
-    Ninst(_)=> Err("Ks原生实例不可作为C指针传递".to_string())
+    Ninst(_)=> Err("Ks原生实例不可作为C指针传递".to_string()),
+    Enum(_)=> Err("Ks枚举不可作为C指针传递".to_string())
   }
 }
 
@@ -11,7 +11,6 @@ mod externer;
 
 use crate::intern::{intern, Interned};
 use crate::LINE;
-use std::collections::HashMap;
 use std::sync::atomic::AtomicUsize;
 use crate::scan::{
   stmt::*,
@@ -29,10 +28,13 @@ pub enum Module {
 }
 
 /// 类声明，分为本地和原生类声明
+/// 
+/// 枚举声明也和类共用同一个命名空间
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Class {
   Native(*const NativeClassDef),
-  Local(*const ClassDef)
+  Local(*const ClassDef),
+  Enum(*const EnumDef)
 }
 
 #[derive(Debug, Clone)]
@@ -118,7 +120,12 @@ impl Scope {
   pub fn run(mut self, codes:&Statements) {
     for (l, sm) in &codes.v {
       // 运行一行语句
-      unsafe{LINE = *l;}
+      unsafe{
+        LINE = l.end;
+        if crate::debugger::ACTIVE {
+          crate::debugger::on_line(self, l.start);
+        }
+      }
       self.evil(sm);
 
       // 停止已结束的作用域
@@ -182,6 +189,11 @@ impl Scope {
             return Class::Local(*cls);
           }
         }
+        for (name, def) in m.enums.iter() {
+          if *name == s {
+            return Class::Enum(*def);
+          }
+        }
         panic!("模块'{}'中没有'{}'类型",modname.str(), s.str())
       }
       Module::Native(p)=> {
@@ -198,6 +210,29 @@ impl Scope {
   }
 
 
+  /// 将`Enum::Variant`或`mod-:Enum::Variant`解析为枚举声明和成员序号
+  /// 
+  /// 左侧不是枚举时返回None
+  pub fn find_enum_variant(&self, e:&Expr)-> Option<(*const EnumDef, usize)> {
+    let (cls, name) = match e {
+      Expr::ImplAccess(cls, name)=> (&**cls, *name),
+      _=> return None
+    };
+    let cls = match cls {
+      Expr::Variant(id)=> self.find_class(*id)?,
+      Expr::ModClsAcc(modname, id)=> self.find_class_in(*modname, *id),
+      _=> return None
+    };
+    if let Class::Enum(def) = cls {
+      let d = unsafe {&*def};
+      let tag = d.variants.iter().position(|v|v.name == name)
+        .unwrap_or_else(||panic!("枚举'{}'没有'{}'成员", d.name, name));
+      return Some((def, tag));
+    }
+    None
+  }
+
+
   /// 寻找一个导入的模块
   pub fn find_mod(&self, find:Interned)-> Module {
     let imports = unsafe {&*self.imports};
@@ -222,7 +257,7 @@ pub struct RunResult {
 pub fn run(s:&Statements, modpath:&'static str)-> RunResult {
   let mut top_ret = Litr::Uninit;
   let imports = Box::into_raw(Box::new(Vec::new()));
-  let exports = Box::into_raw(Box::new(LocalMod { funcs: Vec::new(), classes: Vec::new(), modpath }));
+  let exports = Box::into_raw(Box::new(LocalMod { funcs: Vec::new(), classes: Vec::new(), enums: Vec::new(), modpath }));
   let mut kself = Litr::Uninit;
   let top = top_scope(&mut top_ret, imports, exports,&mut kself);
   top.run(s);
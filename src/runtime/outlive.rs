@@ -117,6 +117,7 @@ pub fn scope_end(mut scope:Scope) {
       }
       Litr::List(l)=> l.iter().for_each(|item|drop_func(item)),
       Litr::Inst(inst)=> inst.v.iter().for_each(|item|drop_func(item)),
+      Litr::Enum(e)=> e.v.iter().for_each(|item|drop_func(item)),
       Litr::Obj(map)=> map.values().for_each(|item|drop_func(item)),
       _=> ()
     }
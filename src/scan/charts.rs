@@ -4,8 +4,8 @@
 pub const fn prec(x:&[u8])-> u8 {
   match x {
     b"-."|b"-:" => 16,
-    b"::"|b"." => 15,
-    b"("|b"[" => 14, // 代指调用和索引
+    b"::"|b"."|b"?." => 15,
+    b"("|b"["|b"?[" => 14, // 代指调用和索引
     // unary => 13
     b"*" | b"%" | b"/" => 12, 
     b"+" | b"-" => 11, 
@@ -16,7 +16,7 @@ pub const fn prec(x:&[u8])-> u8 {
     b"=="|b"!="|b"<"|b">"|b"<="|b">=" => 6,
     b"is"=> 5,
     b"&&" => 4,
-    b"||" => 3,
+    b"||"|b"??" => 3,
     b"="|b"+="|b"-="|b"*="|b"/="|b"%="|b"&="|b"|="|b"^="|b"<<="|b">>=" => 2,
     b"|>" => 1, // 管道运算符应当最靠后计算
     _=> 0
@@ -1,8 +1,11 @@
 use super::{Scanner, charts};
+use super::stmt::Stmt;
 use crate::primitive::litr::{
   Litr, LocalFuncRaw
 };
 use crate::intern::{intern, Interned};
+use crate::runtime::call::MethodCache;
+use crate::primitive::format::Spec;
 
 /// 可以出现在任何右值的，expression表达式
 #[derive(Debug, Clone)]
@@ -26,6 +29,12 @@ pub enum Expr {
   Property  (Box<Expr>, Interned),
   /// ::运算符 Class::static_method
   ImplAccess(Box<Expr>, Interned),
+  /// 含有?.或?[]的整条可选链 a?.b.c a?.b() a?[i].d
+  /// 
+  /// 链中任意一个OptLeft为uninit时, 整条链直接得到uninit
+  Optional(Box<Expr>),
+  /// 可选链中?.或?[左侧的值
+  OptLeft(Box<Expr>),
 
   /// 调用函数 x()
   Call {
@@ -37,7 +46,8 @@ pub enum Expr {
   CallMethod {
     args: Vec<Expr>,
     targ: Box<Expr>,
-    name: Interned
+    name: Interned,
+    cache: MethodCache
   },
 
   /// 索引表达式
@@ -56,6 +66,8 @@ pub enum Expr {
   List(Vec<Expr>),
   /// 对象表达式
   Obj(Vec<(Interned,Expr)>),
+  /// 带插值的模板字符串 `a{x}b`
+  Template(Vec<TmplPart>),
 
   /// 一元运算 ! -
   Unary{
@@ -74,7 +86,26 @@ pub enum Expr {
   Is {
     left: Box<Expr>,
     right: Box<Expr>
-  }
+  },
+
+  /// 作为表达式的循环 let a = for!{break 1}, 值为break携带的值
+  Loop(Box<Stmt>),
+
+  /// 展开运算 ...a
+  /// 
+  /// 只出现在调用参数, 列表和对象中. 在对象中时其键名无意义
+  Spread(Box<Expr>),
+  /// 具名参数 f(a=1), 只出现在调用参数中
+  Named(Interned, Box<Expr>)
+}
+
+/// 模板字符串的一段
+#[derive(Debug, Clone)]
+pub enum TmplPart {
+  /// 原样输出的文字
+  Text(String),
+  /// {expr}或{expr:格式}, 没有格式时用Litr::str()转换
+  Hole(Expr, Option<Spec>)
 }
 
 /// 使用|>时会将左侧表达式暂存此处, 使用|%|时被取走
@@ -83,6 +114,7 @@ pub static mut ON_PIPE:Option<Expr> = None;
 impl Scanner<'_> {
   /// 从self.i直接开始解析一段表达式
   pub fn expr(&self)-> Expr {
+    crate::runtime::call::stack_guard();
     self.spaces();
     let unary = self.operator_unary();
     self.spaces();
@@ -90,7 +122,11 @@ impl Scanner<'_> {
     let left = if self.cur() == b'(' {
       self.expr_group()
     }else {
-      self.literal()
+      match self.literal() {
+        // 语句开头的for由stmt处理, 这里的for只会出现在表达式中
+        Expr::Variant(id) if id.vec() == b"for"=> Expr::Loop(Box::new(self.foring())),
+        e=> e
+      }
     };
     self.expr_with_left(left, unary)
   }
@@ -191,15 +227,15 @@ impl Scanner<'_> {
         b"("=> {
           self.next();
           self.spaces();
-          let targ = Box::new(expr_stack.pop().unwrap());
+          let (targ, opt) = chain_left(&mut expr_stack);
           let args = parse_input_args(self);
-          expr_stack.push(Expr::Call { args, targ });
+          push_chain(&mut expr_stack, Expr::Call { args, targ }, opt);
           continue;
         }
 
         // 如果是.就说明是属性或者调用方法
         b"."=> {
-          let left = Box::new(expr_stack.pop().unwrap());
+          let (left, opt) = chain_left(&mut expr_stack);
           let name = match self.ident() {
             Some(n)=> intern(n),
             None=> panic!("'.'右边需要属性名")
@@ -209,10 +245,45 @@ impl Scanner<'_> {
           if self.cur() == b'(' {
             self.next();
             let args = parse_input_args(self);
-            expr_stack.push(Expr::CallMethod { args, targ: left, name });
+            push_chain(&mut expr_stack, Expr::CallMethod { args, targ: left, name, cache: MethodCache::default() }, opt);
+          }else {
+            push_chain(&mut expr_stack, Expr::Property(left, name), opt);
+          }
+          continue;
+        }
+
+        // 可选链 a?.b和a?.b()
+        b"?."=> {
+          let left = Box::new(Expr::OptLeft(chain_left(&mut expr_stack).0));
+          let name = match self.ident() {
+            Some(n)=> intern(n),
+            None=> panic!("'?.'右边需要属性名")
+          };
+          self.spaces();
+          let e = if self.cur() == b'(' {
+            self.next();
+            let args = parse_input_args(self);
+            Expr::CallMethod { args, targ: left, name, cache: MethodCache::default() }
           }else {
-            expr_stack.push(Expr::Property(left, name));
+            Expr::Property(left, name)
+          };
+          expr_stack.push(Expr::Optional(Box::new(e)));
+          continue;
+        }
+
+        // 可选索引 a?[i]
+        b"?["=> {
+          self.next();
+          self.spaces();
+          let left = Box::new(Expr::OptLeft(chain_left(&mut expr_stack).0));
+          let i = Box::new(self.expr());
+          if self.i() >= self.src.len() || self.cur() != b']' {
+            panic!("未闭合的右括号']'。");
           }
+          self.next();
+          expr_stack.push(Expr::Optional(Box::new(Expr::Index{
+            left, i
+          })));
           continue;
         }
 
@@ -220,15 +291,15 @@ impl Scanner<'_> {
         b"["=> {
           self.next();
           self.spaces();
-          let left = Box::new(expr_stack.pop().unwrap());
+          let (left, opt) = chain_left(&mut expr_stack);
           let i = Box::new(self.expr());
           if self.i() >= self.src.len() || self.cur() != b']' {
             panic!("未闭合的右括号']'。");
           }
           self.next();
-          expr_stack.push(Expr::Index{
+          push_chain(&mut expr_stack, Expr::Index{
             left, i
-          });
+          }, opt);
           continue;
         }
 
@@ -262,6 +333,39 @@ impl Scanner<'_> {
     }
   }
   
+  /// 解析展开运算...a, 不是展开运算时返回None
+  pub fn spread(&self)-> Option<Expr> {
+    self.spaces();
+    if !self.src.get(self.i()..).map_or(false, |s|s.starts_with(b"...")) {
+      return None;
+    }
+    self.set_i(self.i() + 3);
+    let e = self.expr();
+    if let Expr::Empty = e {
+      panic!("...后需要被展开的值")
+    }
+    Some(Expr::Spread(Box::new(e)))
+  }
+
+  /// 解析具名参数的`name=`部分, 不是具名参数时回到原位置
+  fn named_arg(&self)-> Option<Interned> {
+    let start = self.i();
+    let len = self.src.len();
+    if let Some(id) = self.ident() {
+      let mut i = self.i();
+      while i < len && self.src[i] == b' ' {
+        i += 1;
+      }
+      // 排除==和=>
+      if i + 1 < len && self.src[i] == b'=' && !matches!(self.src[i + 1], b'='|b'>') {
+        self.set_i(i + 1);
+        return Some(intern(id));
+      }
+    }
+    self.set_i(start);
+    None
+  }
+
   /// 匹配带括号的表达式(提升优先级和函数调用)
   pub fn expr_group(&self)-> Expr {
     // 把左括号跳过去
@@ -298,6 +402,26 @@ impl Scanner<'_> {
         }
       }
 
+      // ?开头的运算符只有?. ?[和??
+      b'?'=> if self.i() + 1 < len {
+        match self.src[self.i()+1] {
+          b'.'=> {
+            self.set_i(self.i() + 2);
+            return b"?.";
+          }
+          // 和[一样, 把[留给对应的解析函数
+          b'['=> {
+            self.next();
+            return b"?[";
+          }
+          b'?'=> {
+            self.set_i(self.i() + 2);
+            return b"??";
+          }
+          _=> ()
+        }
+      }
+
       // |开头的运算符只允许|,|>和||,防止和闭包与|%|混淆
       b'|'=> {
         self.next();
@@ -316,6 +440,11 @@ impl Scanner<'_> {
           _=> return b"|"
         }
       }
+
+      // :开头的运算符只有::, 单独的:留给模板字符串的格式等语法
+      b':'=> if self.src.get(self.i()+1) != Some(&b':') {
+        return b"";
+      }
       _=>()
     }
 
@@ -363,11 +492,22 @@ fn parse_input_args(this:&Scanner)-> Vec<Expr> {
   }
 
   loop {
-    let e = this.expr();
-    // 调用参数留空就当作uninit
-    args.push(if let Expr::Empty = e {
-      Expr::Literal(Litr::Uninit)
-    }else {e});
+    this.spaces();
+    if let Some(e) = this.spread() {
+      args.push(e);
+    }else if let Some(name) = this.named_arg() {
+      let e = this.expr();
+      if let Expr::Empty = e {
+        panic!("具名参数'{}'需要一个值", name)
+      }
+      args.push(Expr::Named(name, Box::new(e)));
+    }else {
+      let e = this.expr();
+      // 调用参数留空就当作uninit
+      args.push(if let Expr::Empty = e {
+        Expr::Literal(Litr::Uninit)
+      }else {e});
+    }
     this.spaces();
     if this.cur() != b',' {
       break;
@@ -379,4 +519,16 @@ fn parse_input_args(this:&Scanner)-> Vec<Expr> {
   }
   this.next();
   args
-}
\ No newline at end of file
+}
+/// 取出后缀运算的左侧, 左侧是可选链时取出链本身, 让这次运算接在链的末尾
+fn chain_left(expr_stack:&mut Vec<Expr>)-> (Box<Expr>, bool) {
+  match expr_stack.pop().unwrap() {
+    Expr::Optional(e)=> (e, true),
+    e=> (Box::new(e), false)
+  }
+}
+
+/// 放回后缀运算的结果, 左侧原本是可选链时重新包裹为可选链
+fn push_chain(expr_stack:&mut Vec<Expr>, e:Expr, opt:bool) {
+  expr_stack.push(if opt {Expr::Optional(Box::new(e))}else {e});
+}
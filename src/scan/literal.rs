@@ -24,7 +24,7 @@ impl Scanner<'_> {
         }
         let s = String::from_utf8_lossy(&self.src[(self.i()+1)..i]);
         self.set_i(i+1);
-        Expr::Literal(Litr::Str(s.to_string()))
+        Expr::Literal(Litr::Str(s.to_string().into()))
       }
   
       // 解析带转义的字符串
@@ -32,8 +32,8 @@ impl Scanner<'_> {
         i += 1;
         let mut start = i; // 开始结算的起点
         let mut vec = Vec::<u8>::new();
-        // 给`{}`变量捕获用的
-        let mut expr_catch:Option<Expr> = None;
+        // 给`{}`变量捕获用的, 没有捕获时就是普通字符串
+        let mut parts = Vec::<TmplPart>::new();
 
         loop {
           let c = self.src[i];
@@ -75,39 +75,30 @@ impl Scanner<'_> {
               // 更新结算起点
               start = i;
             }
-            // 变量捕获,其实就是自动变成字符串加号
+            // 变量捕获
             b'{'=> {
               // 结算一次
               vec.extend_from_slice(&self.src[start..i]);
+              if !vec.is_empty() {
+                parts.push(TmplPart::Text(String::from_utf8(std::mem::take(&mut vec)).expect("字符串含非法字符")));
+              }
               
               self.set_i(i+1);
-              let this_e = Box::new(self.expr());
-              let part_vec = std::mem::take(&mut vec);
+              let e = self.expr();
+              // {expr:格式}和fmt使用同样的格式
+              let spec = if self.cur() == b':' {
+                let start = self.i() + 1;
+                let mut end = start;
+                while end < len && self.src[end] != b'}' {
+                  end += 1;
+                }
+                let spec = String::from_utf8(self.src[start..end].to_vec()).expect("字符串含非法字符");
+                self.set_i(end);
+                Some(crate::primitive::format::Spec::parse(&spec))
+              }else {None};
               assert!(self.cur() == b'}', "转义字符串内的大括号未闭合");
               self.next();
-              
-              if let Some(last_e) = &mut expr_catch {
-                let mut left = Box::new(Expr::Empty);
-                std::mem::swap(&mut *left, last_e);
-                // 先把原来的left和后来扫上的字符串相加
-                let left = Box::new(Expr::Binary {
-                  left,
-                  right: Box::new(Expr::Literal(
-                    Litr::Str(String::from_utf8(part_vec).expect("字符串含非法字符")))),
-                  op: b"+".to_vec().into()
-                });
-                // 再把上述expr和这次的捕获表达式相加
-                expr_catch = Some(Expr::Binary {
-                  left, 
-                  right: this_e, op: b"+".to_vec().into()
-                });
-              }else {
-                expr_catch = Some(Expr::Binary {
-                  left: Box::new(Expr::Literal(
-                    Litr::Str(String::from_utf8(part_vec).expect("字符串含非法字符")))), 
-                  right: this_e, op: b"+".to_vec().into()
-                });
-              }
+              parts.push(TmplPart::Hole(e, spec));
 
               // 更新结算起点
               i = self.i();
@@ -122,16 +113,14 @@ impl Scanner<'_> {
         vec.extend_from_slice(&self.src[start..i]);
 
         self.set_i(i + 1);
-        if let Some(e) = &mut expr_catch {
-          let mut left = Box::new(Expr::Empty);
-          std::mem::swap(&mut *left, e);
-          Expr::Binary { left, right: Box::new(Expr::Literal(
-            Litr::Str(String::from_utf8(std::mem::take(&mut vec)).expect("字符串含非法字符")))), 
-            op: b"+".to_vec().into()
-          }
+        let str = String::from_utf8(vec).expect("字符串含非法字符");
+        if parts.is_empty() {
+          Expr::Literal(Litr::Str(str.into()))
         }else {
-          let str = String::from_utf8(vec).expect("字符串含非法字符");
-          Expr::Literal(Litr::Str(str))
+          if !str.is_empty() {
+            parts.push(TmplPart::Text(str));
+          }
+          Expr::Template(parts)
         }
       }
   
@@ -198,7 +187,7 @@ impl Scanner<'_> {
         vec.extend_from_slice(&self.src[start..i]);
 
         self.set_i(i+1);
-        Expr::Literal(Litr::Buf(vec))
+        Expr::Literal(Litr::Buf(vec.into()))
       }
   
       // 解析数字字面量
@@ -217,9 +206,14 @@ impl Scanner<'_> {
                 }
               }
 
-              let n = usize::from_str_radix(
-                &String::from_utf8_lossy(&self.src[self.i()..i]), 16
-              ).unwrap_or_else(|e|panic!("{e}"));
+              let digits = String::from_utf8_lossy(&self.src[self.i()..i]);
+              // n后缀为BigInt
+              if self.src.get(i) == Some(&b'n') {
+                self.set_i(i+1);
+                return Expr::Literal(Litr::BigInt(crate::primitive::bigint::BigInt::parse(&digits, 16)
+                  .unwrap_or_else(||panic!("无法解析数字:{}", digits))));
+              }
+              let n = usize::from_str_radix(&digits, 16).unwrap_or_else(|e|panic!("{e}"));
               self.set_i(i);
 
               return Expr::Literal(Litr::Uint(n));
@@ -235,9 +229,14 @@ impl Scanner<'_> {
                 }
               }
 
-              let n = usize::from_str_radix(
-                &String::from_utf8_lossy(&self.src[self.i()..i]), 2
-              ).unwrap_or_else(|e|panic!("{e}"));
+              let digits = String::from_utf8_lossy(&self.src[self.i()..i]);
+              // n后缀为BigInt
+              if self.src.get(i) == Some(&b'n') {
+                self.set_i(i+1);
+                return Expr::Literal(Litr::BigInt(crate::primitive::bigint::BigInt::parse(&digits, 2)
+                  .unwrap_or_else(||panic!("无法解析数字:{}", digits))));
+              }
+              let n = usize::from_str_radix(&digits, 2).unwrap_or_else(|e|panic!("{e}"));
               self.set_i(i);
 
               return Expr::Literal(Litr::Uint(n));
@@ -289,6 +288,7 @@ impl Scanner<'_> {
             b'f' => parsed!(f64, Float),
             b'u' => parsed!(usize, Uint),
             b'i'=> parsed!(isize, Int),
+            b'n'=> parsed!(crate::primitive::bigint::BigInt, BigInt),
             _=> ()
           }
         }
@@ -308,7 +308,7 @@ impl Scanner<'_> {
   
         let mut ls = Vec::new();
         loop {
-          let e = self.expr();
+          let e = self.spread().unwrap_or_else(||self.expr());
           if let Expr::Empty = e {
             break;
           }
@@ -347,22 +347,15 @@ impl Scanner<'_> {
         }
         
         // 解析闭包参数
-        let args = self.arguments();
+        let args = self.arguments(b'|');
         assert!(self.cur()==b'|', "闭包声明右括号缺失");
         self.next();
 
         // 解析闭包内容
-        let stmt = self.stmt();
-        let stmts = if let super::Stmt::Block(b) = stmt {
-          b
-        }else {
-          Statements {
-            v:vec![(unsafe{crate::LINE}, stmt)],
-            vars:0
-          }
-        };
+        let ret = self.ret_typ(0);
+        let (stmts, generator) = self.func_body();
 
-        Expr::LocalDecl(Box::into_raw(Box::new(LocalFuncRaw { argdecl: args, stmts, name: intern(b"unnamed") })))
+        Expr::LocalDecl(Box::into_raw(Box::new(LocalFuncRaw { argdecl: args, stmts, name: intern(b"unnamed"), ret, generator })))
       }
   
       // 解析字面量或变量
@@ -388,7 +381,21 @@ impl Scanner<'_> {
     self.next();
     self.spaces();
     let mut decl = Vec::new();
-    while let Some(id) = self.ident() {
+    loop {
+      // ...o会把o的键值展开进来
+      if let Some(e) = self.spread() {
+        decl.push((intern(b"..."), e));
+        self.spaces();
+        if self.cur() == b',' {
+          self.next()
+        }
+        self.spaces();
+        continue;
+      }
+      let id = match self.ident() {
+        Some(id)=> id,
+        None=> break
+      };
       let v = if self.cur() == b':' {
         self.next();
         self.expr()
@@ -9,8 +9,8 @@ pub mod stmt;
 pub mod literal;
 pub mod expr;
 
-use stmt::{Statements, Stmt};
-use expr::Expr;
+use stmt::{Statements, Stmt, AssignTo, Lines};
+use expr::{Expr, TmplPart};
 
 /// 将字符扫描为ast
 pub fn scan(src: &[u8])-> Statements {
@@ -23,6 +23,9 @@ pub fn scan(src: &[u8])-> Statements {
   stmts
 }
 
+/// 标记正在解析的函数体中是否出现过yield
+pub static mut YIELDED:bool = false;
+
 struct Scanner<'a> {
   src: &'a [u8],
   i: *mut usize,
@@ -36,17 +39,19 @@ impl Scanner<'_> {
   fn scan(self) {
     let len = self.src.len();
     while self.i() < len {
+      self.spaces();
+      let start = unsafe{LINE};
       let s = self.stmt();
       if let Stmt::Empty = s {
         continue;
       }
-      self.push(s);
+      self.push(start, s);
     }
   }
 
   #[inline]
-  fn push(&self, s:Stmt) {
-    unsafe{(*self.stmts).v.push((LINE, s));}
+  fn push(&self, start:usize, s:Stmt) {
+    unsafe{(*self.stmts).v.push((Lines {start, end:LINE}, s));}
   }
   /// 获取当前字符(ascii u8)
   #[inline]
@@ -154,47 +159,149 @@ impl Scanner<'_> {
   }
   
   /// 解析类型声明
-  fn typ(&self)-> KsType {
+  /// 
+  /// end为所在参数列表的结束符, 闭包参数中的联合类型需要用括号包裹
+  fn typ(&self, end:u8)-> KsType {
     self.spaces();
     if self.cur() == b':' {
       self.next();
-      self.spaces();
-      if let Some(decl) = self.ident() {
-        use KsType::*;
-        match decl {
-          b"Int"=>Int,
-          b"Uint"=>Uint,
-          b"Float"=>Float,
-          b"Bool"=>Bool,
-          b"Func"=>Func, 
-          b"Str"=>Str,
-          b"Buf"=>Buf,
-          b"List"=>List,
-          b"Obj"=>Obj,
-          _=> Class(intern(decl))
-        }
-      }else {panic!("类型声明不可为空")}
+      self.typ_name(end)
     }else {KsType::Any}
   }
 
-  /// 解析函数声明的参数
-  fn arguments(&self)-> LocalFuncRawArg {
+  /// 解析函数的返回值类型声明`-> Type`
+  fn ret_typ(&self, end:u8)-> KsType {
     self.spaces();
+    if self.cur() == b'-' && self.src.get(self.i() + 1) == Some(&b'>') {
+      self.set_i(self.i() + 2);
+      self.typ_name(end)
+    }else {KsType::Any}
+  }
+
+  /// 解析类型名, 可以是用|连接的联合类型
+  fn typ_name(&self, end:u8)-> KsType {
+    let mut ts = vec![self.typ_single(end)];
+    loop {
+      // 联合类型的|只在同一行查找
+      let mut i = self.i();
+      while self.src.get(i) == Some(&b' ') {
+        i += 1;
+      }
+      if end == b'|' || self.src.get(i) != Some(&b'|') || matches!(self.src.get(i + 1), Some(b'|'|b'>')) {
+        break;
+      }
+      self.set_i(i + 1);
+      ts.push(self.typ_single(end));
+    }
+    if ts.len() == 1 {
+      ts.pop().unwrap()
+    }else {KsType::Union(ts)}
+  }
 
-    // 使用自定义参数语法
-    if self.cur() == b'[' {
+  /// 解析单个类型, 包括List<T>, Obj<T>, Func(T)-> R, (T)和可选标记T?
+  fn typ_single(&self, end:u8)-> KsType {
+    use KsType::*;
+    self.spaces();
+    let t = if self.cur() == b'(' {
       self.next();
-      let id = intern(self.ident().expect("自定义参数需要指定自定义参数名"));
+      let t = self.typ_name(0);
       self.spaces();
-      assert!(self.cur()==b']', "自定义参数的']'丢失");
+      assert!(self.cur() == b')', "类型声明的右括号缺失");
       self.next();
+      t
+    }else {
+      let decl = self.ident().unwrap_or_else(||panic!("类型声明不可为空"));
+      let next = self.src.get(self.i()).copied();
+      match (decl, next) {
+        (b"List"|b"Obj", Some(b'<'))=> {
+          self.next();
+          let t = Box::new(self.typ_name(0));
+          self.spaces();
+          assert!(self.cur() == b'>', "泛型类型的'>'缺失");
+          self.next();
+          if decl == b"List" {ListOf(t)}else {ObjOf(t)}
+        }
+        (b"Func", Some(b'('))=> {
+          self.next();
+          let mut args = Vec::new();
+          loop {
+            self.spaces();
+            if self.cur() == b')' {
+              self.next();
+              break;
+            }
+            args.push(self.typ_name(0));
+            self.spaces();
+            if self.cur() == b',' {
+              self.next();
+            }else {
+              assert!(self.cur() == b')', "函数签名的右括号缺失");
+            }
+          }
+          Sig(args, Box::new(self.ret_typ(end)))
+        }
+        (b"Int", _)=>Int,
+        (b"Uint", _)=>Uint,
+        (b"BigInt", _)=>BigInt,
+        (b"Float", _)=>Float,
+        (b"Bool", _)=>Bool,
+        (b"Func", _)=>Func, 
+        (b"Str", _)=>Str,
+        (b"Buf", _)=>Buf,
+        (b"List", _)=>List,
+        (b"Obj", _)=>Obj,
+        _=> Class(intern(decl))
+      }
+    };
+    if self.src.get(self.i()) == Some(&b'?') {
+      self.next();
+      return Optional(Box::new(t));
+    }
+    t
+  }
+
+  /// 解析函数体, 并返回函数体中是否出现过yield
+  fn func_body(&self)-> (Statements, bool) {
+    let outer = unsafe {std::ptr::replace(std::ptr::addr_of_mut!(YIELDED), false)};
+    self.spaces();
+    let start = unsafe{LINE};
+    let stmt = self.stmt();
+    let stmts = if let Stmt::Block(b) = stmt {
+      b
+    }else {
+      Statements {
+        v: vec![(Lines {start, end:unsafe{LINE}}, stmt)],
+        vars:0
+      }
+    };
+    let generator = unsafe {std::ptr::replace(std::ptr::addr_of_mut!(YIELDED), outer)};
+    (stmts, generator)
+  }
+
+  /// 解析函数声明的参数, end为参数列表的结束符
+  fn arguments(&self, end:u8)-> LocalFuncRawArg {
+    self.spaces();
+
+    // 自定义参数语法..args, 所有参数作为一个List传入. []开头的参数是解构
+    if self.cur() == b'.' && self.src.get(self.i() + 1) == Some(&b'.') {
+      self.set_i(self.i() + 2);
+      let id = intern(self.ident().expect("自定义参数需要指定自定义参数名"));
+      self.spaces();
+      assert!(self.cur()==end, "自定义参数..{}后不能再声明其他参数", id);
       return LocalFuncRawArg::Custom(id);
     }
 
     let mut args = Vec::new();
-    while let Some(n) = self.ident() {
-      let name = intern(n);
-      let t = self.typ();
+    loop {
+      // 解构参数没有参数名, 无法作为具名参数传入
+      let (name, pat) = match self.cur() {
+        b'['|b'{'=> (intern(b"..."), Some(AssignTo::Destr(Box::new(self.destr())))),
+        _=> match self.ident() {
+          Some(n)=> (intern(n), None),
+          None=> break
+        }
+      };
+      let t = self.typ(end);
 
       self.spaces();
       let default = if self.cur() == b'=' {
@@ -212,7 +319,7 @@ impl Scanner<'_> {
         self.next();
       }
       self.spaces();
-      args.push(ArgDecl {name, t, default});
+      args.push(ArgDecl {name, t, default, pat});
     };
     LocalFuncRawArg::Normal(args)
   }
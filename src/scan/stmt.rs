@@ -4,18 +4,27 @@ use crate::native::NativeMod;
 use crate::runtime::Scope;
 use crate::LINE;
 use crate::primitive::litr::{
-  Litr, Function, LocalFuncRaw, LocalFunc, ExternFunc, KsType
+  Litr, Function, LocalFuncRaw, LocalFunc, ExternFunc, KsType, ArgDecl, LocalFuncRawArg
 };
 use crate::scan::Expr;
 
 /// 语句列表
 #[derive(Debug, Clone, Default)]
 pub struct Statements {
-  pub v: Vec<(usize, Stmt)>,
+  pub v: Vec<(Lines, Stmt)>,
   /// 标注该块的变量数量
   pub vars: usize
 }
 
+/// 语句所在的行
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Lines {
+  /// 语句开头所在的行, 调试器的断点和调用栈用这个
+  pub start: usize,
+  /// 扫描完语句时的行, 报错时使用
+  pub end: usize
+}
+
 /// 分号分隔的，statement语句
 #[derive(Debug, Clone)]
 pub enum Stmt {
@@ -31,11 +40,14 @@ pub enum Stmt {
   Class     (*const ClassDefRaw),
   // 类别名
   Using     (Interned, Expr),
+  // 定义枚举
+  Enum      (*const EnumDefRaw),
 
   Mod       (Interned, *const LocalMod),
   NativeMod (Interned, *const NativeMod),
   ExportFn  (Interned, *mut LocalFuncRaw),
   ExportCls (*const ClassDefRaw),
+  ExportEnum(*const EnumDefRaw),
 
   Match {
     to: Expr,
@@ -50,21 +62,28 @@ pub enum Stmt {
     exec: Box<Stmt>,
     els: Option<Box<Stmt>>
   },
-  ForLoop(Box<Stmt>),
+  // 循环可以用#label标注, 供内层循环的break和continue指定
+  ForLoop {
+    label: Option<Interned>,
+    exec: Box<Stmt>
+  },
   ForWhile {
+    label: Option<Interned>,
     condition: Expr,
     exec: Box<Stmt>
   },
   ForIter {
+    label: Option<Interned>,
     iterator: Expr,
-    id: Option<Interned>,
+    id: Option<AssignTo>,
     exec: Box<Stmt>
   },
 
   // 流程控制
-  Break,
-  Continue, 
+  Break     (Option<Interned>, Expr),
+  Continue  (Option<Interned>), 
   Return    (Expr),
+  Yield     (Expr),
 
   Throw (Expr),
   Try {
@@ -83,6 +102,8 @@ pub struct AssignDef {
   pub id: AssignTo,
   /// =右侧
   pub val: Expr,
+  /// 变量的类型声明 let a:Int = 1
+  pub typ: KsType,
   /// 是否使用<代替=
   pub take: bool
 }
@@ -92,7 +113,33 @@ pub enum AssignTo {
   /// 单体赋值
   One(Interned),
   /// 解构赋值
-  Destr(Vec<Interned>)
+  Destr(Box<Destr>)
+}
+
+/// 解构模式, 可用于let, const, 函数参数和for循环变量
+#[derive(Debug, Clone)]
+pub enum Destr {
+  /// [a, [b, c], d = 1, ..rest]
+  /// 
+  /// 对Obj和本地类实例使用时按变量名取属性
+  List {
+    items: Vec<DestrItem>,
+    rest: Option<Interned>
+  },
+  /// {a, name: n, pos: {x, y}, d = 1, ..rest}
+  /// 
+  /// 对List等可迭代值使用时按顺序取值
+  Obj {
+    items: Vec<(Interned, DestrItem)>,
+    rest: Option<Interned>
+  }
+}
+
+/// 解构中的一项, 取到的值为uninit时使用默认值
+#[derive(Debug, Clone)]
+pub struct DestrItem {
+  pub to: AssignTo,
+  pub default: Expr
 }
 
 
@@ -100,6 +147,7 @@ pub enum AssignTo {
 pub struct LocalMod {
   pub funcs: Vec<(Interned, LocalFunc)>,
   pub classes: Vec<(Interned, *mut ClassDef)>,
+  pub enums: Vec<(Interned, *mut EnumDef)>,
   pub modpath: &'static str
 }
 
@@ -151,6 +199,34 @@ pub struct ClassFunc {
   pub public: bool
 }
 
+/// 未绑定作用域的枚举声明
+#[derive(Debug, Clone)]
+pub struct EnumDefRaw {
+  pub name: Interned,
+  pub variants: Vec<EnumVariantDecl>
+}
+
+/// 枚举成员的声明, 无值成员的fields为空
+#[derive(Debug, Clone)]
+pub struct EnumVariantDecl {
+  pub name: Interned,
+  pub fields: Vec<ArgDecl>
+}
+
+/// 绑定作用域的枚举声明
+#[derive(Debug, Clone)]
+pub struct EnumDef {
+  pub p: *const EnumDefRaw,
+  /// 成员值的默认值和类型检查都在此作用域进行
+  pub cx: Scope
+}
+impl std::ops::Deref for EnumDef {
+  type Target = EnumDefRaw;
+  fn deref(&self) -> &Self::Target {
+    unsafe{&*self.p}
+  }
+}
+
 #[derive(Debug, Clone)]
 pub enum MatchOrd {
   Greater,
@@ -189,13 +265,14 @@ impl Scanner<'_> {
             return Stmt::Block(stmts);
           }
           
+          let start = unsafe{LINE};
           let s = self.stmt();
           match &s {
             Stmt::Let(_)|Stmt::Const(_)=> stmts.vars += 1,
             Stmt::Empty=> continue,
             _=> ()
           }
-          stmts.v.push((unsafe{LINE}, s))
+          stmts.v.push((Lines {start, end:unsafe{LINE}}, s))
         }
       }
       // 返回语句语法糖
@@ -224,13 +301,15 @@ impl Scanner<'_> {
         },
         b"extern"=> {self.externing();Stmt::Empty},
         b"return"=> self.returning(),
+        b"yield"=> self.yielding(),
         b"class"=> self.classing(),
+        b"enum"=> self.enuming(),
         b"mod"=> self.moding(),
         b"for"=> self.foring(),
         b"if"=> self.ifing(),
         b"else"=> panic!("else必须紧接if. 检查一下是不是if后是单语句还用了分号结尾"),
-        b"break"=> Stmt::Break,
-        b"continue"=> Stmt::Continue,
+        b"break"=> self.breaking(),
+        b"continue"=> Stmt::Continue(self.label()),
         b"async"|b"await"=> panic!("异步关键词暂时保留无法使用"),
         b"throw"=> self.throwing(),
         b"try"=> self.trying(),
@@ -263,26 +342,10 @@ impl Scanner<'_> {
     self.spaces();
 
     let id = match self.cur() {
-      n@b'['|n@b'{'=> {
-        self.next();
-        let mut vec = Vec::new();
-        self.spaces();
-        while let Some(id) = self.ident() {
-          vec.push(intern(id));
-          self.spaces();
-          if self.cur() == b',' {
-            self.next();
-            self.spaces();
-          }
-        }
-        if n + 2 != self.cur() {
-          panic!("let解构错误:未闭合的括号'{}'", String::from_utf8_lossy(&[n]))
-        }
-        self.next();
-        AssignTo::Destr(vec)
-      }
+      b'['|b'{'=> AssignTo::Destr(Box::new(self.destr())),
       _=> AssignTo::One(intern(self.ident().unwrap_or_else(||panic!("let后需要标识符"))))
     };
+    let typ = self.typ(0);
   
     // 检查标识符后的符号
     self.spaces();
@@ -295,7 +358,7 @@ impl Scanner<'_> {
           panic!("无法为空气赋值")
         }
         AssignDef {
-          id, val, take:false
+          id, val, typ, take:false
         }
       }
       // take语法
@@ -306,24 +369,18 @@ impl Scanner<'_> {
           panic!("无法为空气赋值")
         }
         AssignDef {
-          id, val, take:true
+          id, val, typ, take:true
         }
       }
       b'(' => {
         self.next();
-        let args = self.arguments();
+        assert!(matches!(typ, KsType::Any), "函数声明不可使用':'标注类型, 返回值类型请使用'->'");
+        let args = self.arguments(b')');
         assert!(self.cur()==b')', "函数声明右括号缺失");
         self.next();
   
-        let stmt = self.stmt();
-        let stmts = if let Stmt::Block(b) = stmt {
-          b
-        }else {
-          Statements {
-            v: vec![(unsafe{LINE}, stmt)],
-            vars:0
-          }
-        };
+        let ret = self.ret_typ(0);
+        let (stmts, generator) = self.func_body();
   
         let fname = match id {
           AssignTo::One(n)=>n,
@@ -333,12 +390,12 @@ impl Scanner<'_> {
         // 其生命周期应当和Statements相同，绑定作用域时将被复制
         // 绑定作用域行为发生在runtime::Scope::calc
         AssignDef {
-          id, take:false,
-          val: Expr::LocalDecl(Box::into_raw(Box::new(LocalFuncRaw { argdecl: args, stmts, name:fname })))
+          id, take:false, typ,
+          val: Expr::LocalDecl(Box::into_raw(Box::new(LocalFuncRaw { argdecl: args, stmts, name:fname, ret, generator })))
         }
       }
       _ => AssignDef {
-        id, take:false, val:Expr::Literal(Litr::Uninit)
+        id, take:false, typ, val:Expr::Literal(Litr::Uninit)
       }
     }
   }
@@ -382,7 +439,7 @@ impl Scanner<'_> {
       // 解析小括号包裹的参数声明
       assert!(self.cur()==b'(', "extern函数后应有括号");
       self.next();
-      let argdecl = match self.arguments() {
+      let argdecl = match self.arguments(b')') {
         crate::primitive::litr::LocalFuncRawArg::Normal(v)=> v,
         _=> panic!("extern函数不可使用自定义参数")
       };
@@ -399,9 +456,10 @@ impl Scanner<'_> {
         "动态库'{}'中不存在'{}'函数", 
         String::from_utf8_lossy(path), 
         String::from_utf8_lossy(sym)));
-      self.push(Stmt::Let(AssignDef { 
+      self.push(unsafe{LINE}, Stmt::Let(AssignDef { 
         id: AssignTo::One(intern($id)), 
         take: false,
+        typ: KsType::Any,
         val: Expr::Literal(Litr::Func(Function::Extern(ExternFunc { 
           argdecl, 
           ptr
@@ -438,6 +496,18 @@ impl Scanner<'_> {
     }
   }
 
+  /// 解析yield, 并将所在函数标记为生成器
+  fn yielding(&self)-> Stmt {
+    unsafe {super::YIELDED = true;}
+    self.spaces();
+    let expr = self.expr();
+    if let Expr::Empty = expr {
+      Stmt::Yield(Expr::Literal(Litr::Uninit))
+    }else {
+      Stmt::Yield(expr)
+    }
+  }
+
   /// 解析类声明
   fn classing(&self)-> Stmt {
     self.spaces();
@@ -474,22 +544,15 @@ impl Scanner<'_> {
       if self.cur() == b'(' {
         self.next();
         // 参数
-        let args = self.arguments();
+        let args = self.arguments(b')');
         assert!(self.cur() == b')', "函数声明右括号缺失");
         self.next();
   
         // 函数体
-        let stmt = self.stmt();
-        let stmts = if let Stmt::Block(b) = stmt {
-          b
-        }else {
-          Statements {
-            v: vec![(unsafe{LINE}, stmt)],
-            vars:0
-          }
-        };
+        let ret = self.ret_typ(0);
+        let (stmts, generator) = self.func_body();
   
-        let v = ClassFuncRaw {f:LocalFuncRaw{argdecl:args,stmts,name:id}, public};
+        let v = ClassFuncRaw {f:LocalFuncRaw{argdecl:args,stmts,name:id,ret,generator}, public};
         if is_method {
           methods.push(v);
         }else {
@@ -497,7 +560,7 @@ impl Scanner<'_> {
         }
       // 属性
       }else {
-        let typ = self.typ();
+        let typ = self.typ(0);
         let v = ClassProp {
           name: id, typ, public
         };
@@ -520,6 +583,48 @@ impl Scanner<'_> {
   }
   
   
+  /// 解析枚举声明
+  fn enuming(&self)-> Stmt {
+    self.spaces();
+    let name = intern(self.ident().unwrap_or_else(||panic!("enum后需要标识符")));
+    self.spaces();
+    assert!(self.cur() == b'{', "enum需要大括号");
+    self.next();
+
+    let mut variants = Vec::new();
+    loop {
+      self.spaces();
+      let id = match self.ident() {
+        Some(id)=> intern(id),
+        None=> break
+      };
+      self.spaces();
+
+      // 带值的成员使用和函数参数相同的声明
+      let fields = if self.cur() == b'(' {
+        self.next();
+        let fields = match self.arguments(b')') {
+          LocalFuncRawArg::Normal(v)=> v,
+          _=> panic!("枚举成员不可使用自定义参数")
+        };
+        assert!(fields.iter().all(|f|f.pat.is_none()), "枚举成员'{}'的值不可使用解构", id);
+        assert!(self.cur() == b')', "枚举成员'{}'右括号缺失", id);
+        self.next();
+        fields
+      }else {Vec::new()};
+      variants.push(EnumVariantDecl { name:id, fields });
+
+      self.spaces();
+      if self.cur() == b',' {
+        self.next();
+      }
+    }
+
+    assert!(self.cur()==b'}', "enum大括号未闭合");
+    self.next();
+    Stmt::Enum(Box::into_raw(Box::new(EnumDefRaw { name, variants })))
+  }
+  
   /// 解析模块声明
   fn moding(&self)-> Stmt {
     // 先判断是否是导出语句
@@ -538,6 +643,16 @@ impl Scanner<'_> {
       },
       b':' => {
         self.next();
+        self.spaces();
+        // mod:enum导出枚举
+        if self.src[self.i()..].starts_with(b"enum") 
+          && matches!(self.src.get(self.i() + 4), Some(b' '|b'\r'|b'\n')) {
+          self.set_i(self.i() + 4);
+          match self.enuming() {
+            Stmt::Enum(e)=> return Stmt::ExportEnum(e),
+            _=> unreachable!()
+          }
+        }
         let cls = self.classing();
         match cls {
           Stmt::Class(cls)=> return Stmt::ExportCls(cls),
@@ -621,18 +736,28 @@ impl Scanner<'_> {
   }
 
   /// for语句
-  fn foring(&self)-> Stmt {
+  pub fn foring(&self)-> Stmt {
+    let label = self.label();
     self.spaces();
     match self.cur() {
       b'('=> {
         let condition = self.expr_group();
         let exec = Box::new(self.stmt());
-        Stmt::ForWhile { condition, exec }
+        Stmt::ForWhile { label, condition, exec }
       }
       b'!'=> {
         self.next();
         let exec = Box::new(self.stmt());
-        Stmt::ForLoop(exec)
+        Stmt::ForLoop { label, exec }
+      }
+      // 解构迭代值 for [k, v]:iter
+      b'['|b'{' if self.is_destr_for()=> {
+        let id = AssignTo::Destr(Box::new(self.destr()));
+        self.spaces();
+        self.next();
+        let right = self.expr();
+        let exec = Box::new(self.stmt());
+        Stmt::ForIter {label, iterator:right, id:Some(id), exec}
       }
       _=> {
         let left = self.literal();
@@ -643,9 +768,9 @@ impl Scanner<'_> {
           if let Expr::Variant(id) = left {
             let right = self.expr();
             let exec = Box::new(self.stmt());
-            return Stmt::ForIter {iterator:right, id:Some(id), exec};
+            return Stmt::ForIter {label, iterator:right, id:Some(AssignTo::One(id)), exec};
           }
-          panic!("`for v:iter`语句中:左边必须是标识符")
+          panic!("`for v:iter`语句中:左边必须是标识符或解构")
         }
 
         // 不使用迭代器值
@@ -654,9 +779,154 @@ impl Scanner<'_> {
           _=> self.expr_with_left(left, vec![])
         };
         let exec = Box::new(self.stmt());
-        Stmt::ForIter {iterator, id:None, exec}
+        Stmt::ForIter {label, iterator, id:None, exec}
+      }
+    }
+  }
+
+  /// 判断for后的括号是解构模式还是被迭代的列表或对象
+  /// 
+  /// 找到对应的右括号后紧跟':'的就是解构
+  fn is_destr_for(&self)-> bool {
+    let src = self.src;
+    let mut depth = 0;
+    let mut i = self.i();
+    while i < src.len() {
+      match src[i] {
+        b'['|b'{'|b'('=> depth += 1,
+        b']'|b'}'|b')'=> {
+          depth -= 1;
+          if depth == 0 {
+            break;
+          }
+        }
+        // 跳过字符串中的括号
+        q@(b'"'|b'\''|b'`')=> {
+          i += 1;
+          while i < src.len() && src[i] != q {
+            i += 1;
+          }
+        }
+        _=> ()
       }
+      i += 1;
+    }
+    i += 1;
+    while i < src.len() && matches!(src[i], b' '|b'\r'|b'\n') {
+      i += 1;
+    }
+    src.get(i) == Some(&b':')
+  }
+
+  /// 解析解构模式 [a, b] 或 {a, b}
+  pub fn destr(&self)-> Destr {
+    let open = self.cur();
+    let close = open + 2;
+    self.next();
+    let mut rest = None;
+    let mut list = Vec::new();
+    let mut obj = Vec::new();
+    loop {
+      self.spaces();
+      if self.i() >= self.src.len() {
+        panic!("解构错误:未闭合的括号'{}'", String::from_utf8_lossy(&[open]))
+      }
+      if self.cur() == close {
+        self.next();
+        break;
+      }
+
+      // ..rest接收剩余的值
+      if self.src[self.i()..].starts_with(b"..") {
+        assert!(rest.is_none(), "解构中只能有一个..");
+        self.set_i(self.i() + 2);
+        rest = Some(intern(self.ident().expect("..后需要标识符")));
+      }else if open == b'[' {
+        let to = self.destr_to();
+        list.push(DestrItem {to, default: self.destr_default()});
+      }else {
+        let key = intern(self.ident().expect("对象解构需要属性名"));
+        self.spaces();
+        // {key: 新名称或嵌套解构}
+        let to = if self.cur() == b':' {
+          self.next();
+          self.spaces();
+          self.destr_to()
+        }else {AssignTo::One(key)};
+        obj.push((key, DestrItem {to, default: self.destr_default()}));
+      }
+
+      self.spaces();
+      if self.cur() == b',' {
+        self.next();
+      }else if self.cur() != close {
+        panic!("解构错误:需要','或'{}'", String::from_utf8_lossy(&[close]))
+      }
+    }
+
+    if open == b'[' {
+      Destr::List {items: list, rest}
+    }else {
+      Destr::Obj {items: obj, rest}
+    }
+  }
+
+  /// 解构项绑定的变量名或嵌套的解构
+  fn destr_to(&self)-> AssignTo {
+    match self.cur() {
+      b'['|b'{'=> AssignTo::Destr(Box::new(self.destr())),
+      _=> AssignTo::One(intern(self.ident().expect("解构中需要标识符")))
+    }
+  }
+
+  /// 解构项的默认值 = expr
+  fn destr_default(&self)-> Expr {
+    self.spaces();
+    if self.cur() == b'=' {
+      self.next();
+      let e = self.expr();
+      if let Expr::Empty = e {
+        panic!("解构的'='后需要默认值")
+      }
+      return e;
+    }
+    Expr::Literal(Litr::Uninit)
+  }
+
+  /// 解析循环标签#label, 只在同一行中寻找
+  fn label(&self)-> Option<Interned> {
+    let len = self.src.len();
+    while self.i() < len && matches!(self.cur(), b' '|b'\t') {
+      self.next();
+    }
+    if self.i() >= len || self.cur() != b'#' {
+      return None;
+    }
+    self.next();
+    Some(intern(self.ident().expect("#后需要标签名")))
+  }
+
+  /// break #label value
+  /// 
+  /// 携带的值必须和break写在同一行
+  fn breaking(&self)-> Stmt {
+    let label = self.label();
+    let len = self.src.len();
+    while self.i() < len && matches!(self.cur(), b' '|b'\t') {
+      self.next();
+    }
+    if self.i() >= len {
+      return Stmt::Break(label, Expr::Literal(Litr::Uninit));
+    }
+    let is_comment = self.cur() == b'/' && matches!(self.src.get(self.i() + 1), Some(b'/'|b'\''));
+    if is_comment || matches!(self.cur(), b'\r'|b'\n'|b';'|b'}') {
+      return Stmt::Break(label, Expr::Literal(Litr::Uninit));
+    }
+    let val = self.expr();
+    if let Expr::Empty = val {
+      panic!("break后的值无法解析")
     }
+    Stmt::Break(label, val)
   }
 
   /// throw
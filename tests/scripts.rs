@@ -0,0 +1,50 @@
+//! 运行tests/scripts下的ks脚本, 并与同名.out文件比较标准输出
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn run(name:&str) {
+  let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/scripts");
+  let expect = std::fs::read_to_string(dir.join(format!("{name}.out")))
+    .unwrap_or_else(|e|panic!("无法读取{name}.out: {e}"));
+  let out = Command::new(env!("CARGO_BIN_EXE_key-lang"))
+    .arg(dir.join(format!("{name}.ks")))
+    .output()
+    .expect("无法启动key-lang");
+  let stdout = String::from_utf8_lossy(&out.stdout);
+  assert!(out.status.success(), "{name}.ks运行失败:\n{}{}", stdout, String::from_utf8_lossy(&out.stderr));
+  assert_eq!(stdout.replace("\r\n", "\n"), expect.replace("\r\n", "\n"), "{name}.ks输出不符");
+}
+
+#[test]
+fn hash() {run("hash")}
+
+#[test]
+fn zip() {run("zip")}
+
+#[test]
+fn codec() {run("codec")}
+
+#[test]
+fn bigint() {run("bigint")}
+
+#[test]
+fn cow() {run("cow")}
+
+#[test]
+fn loops() {run("loop")}
+
+#[test]
+fn tail_calls() {run("tail")}
+
+#[test]
+fn fmt() {run("fmt")}
+
+#[test]
+fn args() {run("args")}
+
+#[test]
+fn enums() {run("enum")}
+
+#[test]
+fn generators() {run("gen")}